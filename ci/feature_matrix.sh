@@ -0,0 +1,48 @@
+#!/usr/bin/env bash
+# Checks every combination of the domain features for each crate
+set -euo pipefail
+
+DOMAINS=(bus traffic taxi train crowd)
+
+check() {
+  local dir=$1 extra=$2 mask features
+  for ((mask = 0; mask < 1 << ${#DOMAINS[@]}; mask++)); do
+    features=$extra
+    for i in "${!DOMAINS[@]}"; do
+      if ((mask & (1 << i))); then
+        features="$features ${DOMAINS[$i]}"
+      fi
+    done
+    echo "==> $dir: [${features# }]"
+    (cd "$dir" && cargo check --lib --tests --no-default-features --features "${features# }")
+  done
+}
+
+check lta_models ""
+check lta_blocking ""
+check lta_async ""
+check . "blocking"
+check . "async"
+
+for feature in calendar spatial sync; do
+  echo "==> lta_models: [$feature]"
+  (cd lta_models && cargo check --lib --tests --no-default-features --features $feature)
+done
+
+echo "==> lta_models: [gtfs]"
+(cd lta_models && cargo check --lib --tests --no-default-features --features gtfs)
+
+echo "==> lta_models: [geo-types]"
+(cd lta_models && cargo check --lib --tests --no-default-features --features geo-types)
+
+echo "==> lta_models: [geojson]"
+(cd lta_models && cargo check --lib --tests --no-default-features --features geojson)
+
+echo "==> lta_models: [csv-io]"
+(cd lta_models && cargo check --lib --tests --no-default-features --features csv-io)
+
+echo "==> lta_models: [arrow]"
+(cd lta_models && cargo check --lib --tests --no-default-features --features arrow)
+
+echo "==> lta_models: [sqlite]"
+(cd lta_models && cargo check --lib --tests --no-default-features --features sqlite)
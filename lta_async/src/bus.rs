@@ -1,8 +1,10 @@
 //! All APIs pertaining to buses
 
 use crate::lta_client::LTAClient;
-use crate::{build_req_async_with_query, build_req_async_with_skip};
+use crate::request::PagedRequest;
+use crate::{build_req_async_with_query, build_req_async_with_skip, get_page_with_query};
 use lta_models::bus::{bus_arrival, bus_routes, bus_services, bus_stops};
+use lta_utils_commons::parse::{ParseMode, Parsed};
 use lta_utils_commons::LTAResult;
 
 /// Returns real-time Bus Arrival information of Bus Services at a queried Bus Stop,
@@ -66,3 +68,95 @@ pub async fn get_bus_stops(
 ) -> LTAResult<Vec<bus_stops::BusStop>> {
     build_req_async_with_skip::<bus_stops::BusStopsResp, _>(client, bus_stops::URL, skip).await
 }
+
+/// Bus APIs, created with `LTAClient::bus`
+#[derive(Debug, Clone, Copy)]
+pub struct Bus<'a> {
+    pub(crate) client: &'a LTAClient,
+}
+
+impl<'a> Bus<'a> {
+    /// Bus arrivals at a bus stop, see `get_arrival`
+    pub fn arrival(self, bus_stop_code: u32) -> ArrivalRequest<'a> {
+        ArrivalRequest {
+            client: self.client,
+            bus_stop_code,
+            service_no: None,
+        }
+    }
+
+    /// Bus arrivals at each of `bus_stop_codes`, in the same order.
+    ///
+    /// Polling a set of stops this way gives the input of `lta_models::gtfs::realtime`
+    pub async fn arrivals(
+        self,
+        bus_stop_codes: &[u32],
+    ) -> LTAResult<Vec<bus_arrival::BusArrivalResp>> {
+        let mut arrivals = Vec::with_capacity(bus_stop_codes.len());
+        for &code in bus_stop_codes {
+            arrivals.push(get_arrival(self.client, code, None).await?);
+        }
+        Ok(arrivals)
+    }
+
+    /// See `get_bus_services`
+    pub fn services(
+        self,
+    ) -> PagedRequest<'a, bus_services::BusServiceResp, Vec<bus_services::BusService>> {
+        PagedRequest::new(self.client, bus_services::URL)
+    }
+
+    /// See `get_bus_routes`
+    pub fn routes(self) -> PagedRequest<'a, bus_routes::BusRouteResp, Vec<bus_routes::BusRoute>> {
+        PagedRequest::new(self.client, bus_routes::URL)
+    }
+
+    /// See `get_bus_stops`
+    pub fn stops(self) -> PagedRequest<'a, bus_stops::BusStopsResp, Vec<bus_stops::BusStop>> {
+        PagedRequest::new(self.client, bus_stops::URL)
+    }
+}
+
+/// Bus arrival request, created with `Bus::arrival`
+#[derive(Debug, Clone)]
+pub struct ArrivalRequest<'a> {
+    client: &'a LTAClient,
+    bus_stop_code: u32,
+    service_no: Option<String>,
+}
+
+impl<'a> ArrivalRequest<'a> {
+    /// Only return arrivals of this service
+    pub fn service<S>(mut self, service_no: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.service_no = Some(service_no.into());
+        self
+    }
+
+    /// Sends the request, failing on the first bad record
+    pub async fn send(self) -> LTAResult<bus_arrival::BusArrivalResp> {
+        get_arrival(self.client, self.bus_stop_code, self.service_no.as_deref()).await
+    }
+
+    /// Sends the request with the given `ParseMode`
+    pub async fn send_with(
+        self,
+        mode: ParseMode,
+    ) -> LTAResult<Parsed<bus_arrival::BusArrivalResp>> {
+        let bus_stop_code = self.bus_stop_code.to_string();
+        let service_no = self.service_no;
+
+        get_page_with_query::<bus_arrival::RawBusArrivalResp, _, _>(
+            self.client,
+            bus_arrival::URL,
+            |rb| match service_no {
+                Some(srv_no) => rb.query(&[("BusStopCode", bus_stop_code), ("ServiceNo", srv_no)]),
+                None => rb.query(&[("BusStopCode", bus_stop_code)]),
+            },
+            mode,
+        )
+        .await
+    }
+}
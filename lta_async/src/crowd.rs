@@ -3,10 +3,12 @@
 use crate::lta_client::LTAClient;
 use crate::{build_req_async_with_query, build_req_async_with_skip};
 use lta_models::crowd::passenger_vol::{
-    PassengerVolRawResp, VolType, FORMAT, URL_BY_BUS_STOPS, URL_BY_OD_BUS_STOPS, URL_BY_OD_TRAIN,
-    URL_BY_TRAIN,
+    PassengerVolRawResp, VolType, VolZip, YearMonth, FORMAT, URL_BY_BUS_STOPS, URL_BY_OD_BUS_STOPS,
+    URL_BY_OD_TRAIN, URL_BY_TRAIN,
 };
+use lta_utils_commons::parse::ParseError;
 use lta_utils_commons::{chrono::NaiveDate, LTAResult};
+use std::io::Cursor;
 
 /// Creates a new client for every call
 /// **Update freq**: By 15th of every month, the passenger volume for previous month data
@@ -21,12 +23,7 @@ pub async fn get_passenger_vol_by(
 ) -> LTAResult<Vec<String>> {
     let fmt_date = date.map(|f| f.format(FORMAT).to_string());
 
-    let url = match vol_type {
-        VolType::BusStops => URL_BY_BUS_STOPS,
-        VolType::OdBusStop => URL_BY_OD_BUS_STOPS,
-        VolType::Train => URL_BY_TRAIN,
-        VolType::OdTrain => URL_BY_OD_TRAIN,
-    };
+    let url = vol_url(&vol_type);
 
     match fmt_date {
         Some(nd) => {
@@ -38,3 +35,105 @@ pub async fn get_passenger_vol_by(
         None => build_req_async_with_skip::<PassengerVolRawResp, _>(client, url, skip).await,
     }
 }
+
+/// Crowd APIs, created with `LTAClient::crowd`
+#[derive(Debug, Clone, Copy)]
+pub struct Crowd<'a> {
+    pub(crate) client: &'a LTAClient,
+}
+
+impl<'a> Crowd<'a> {
+    /// Links to passenger volume files, see `get_passenger_vol_by`
+    pub fn passenger_volume(self, vol_type: VolType) -> PassengerVolRequest<'a> {
+        PassengerVolRequest {
+            client: self.client,
+            vol_type,
+        }
+    }
+}
+
+/// Passenger volume request, created with `Crowd::passenger_volume`
+#[derive(Debug, Clone)]
+pub struct PassengerVolRequest<'a> {
+    client: &'a LTAClient,
+    vol_type: VolType,
+}
+
+impl<'a> PassengerVolRequest<'a> {
+    /// Links for the most recent month available
+    pub async fn latest(self) -> LTAResult<Vec<String>> {
+        get_passenger_vol_by(self.client, self.vol_type, None, None).await
+    }
+
+    /// Links for the given month, only the last 3 months are available
+    pub async fn month(self, month: YearMonth) -> LTAResult<Vec<String>> {
+        let date = month.to_query();
+        build_req_async_with_query::<PassengerVolRawResp, _, _>(
+            self.client,
+            vol_url(&self.vol_type),
+            move |rb| rb.query(&[("Date", date)]),
+        )
+        .await
+    }
+
+    /// Downloads the most recent file available, see `VolZip::records` to read it
+    ///
+    /// ## Example
+    /// ```rust,no_run
+    /// use lta_async::lta_client::LTAClient;
+    /// use lta_models::crowd::passenger_vol::{OdPassengerVolRecord, VolType};
+    /// use lta_utils_commons::{Client, LTAResult};
+    ///
+    /// async fn example() -> LTAResult<()> {
+    ///     let client = LTAClient::with_api_key("api_key");
+    ///     let mut zip = client
+    ///         .crowd()
+    ///         .passenger_volume(VolType::OdTrain)
+    ///         .download_latest()
+    ///         .await?;
+    ///     for record in zip.records::<OdPassengerVolRecord>()? {
+    ///         println!("{:?}", record?);
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn download_latest(self) -> LTAResult<VolZip<Cursor<Vec<u8>>>> {
+        let client = self.client;
+        let links = self.latest().await?;
+        download(client, links).await
+    }
+
+    /// Downloads the file for the given month, see `VolZip::records` to read it
+    pub async fn download_month(self, month: YearMonth) -> LTAResult<VolZip<Cursor<Vec<u8>>>> {
+        let client = self.client;
+        let links = self.month(month).await?;
+        download(client, links).await
+    }
+}
+
+/// Fetches the first link before it expires and opens the zip in memory
+async fn download(client: &LTAClient, links: Vec<String>) -> LTAResult<VolZip<Cursor<Vec<u8>>>> {
+    let link = links
+        .into_iter()
+        .next()
+        .ok_or_else(|| ParseError::new("value".to_string(), "no passenger volume link"))?;
+
+    let body = client
+        .client
+        .get(&link)
+        .send()
+        .await?
+        .error_for_status()?
+        .bytes()
+        .await?;
+    Ok(VolZip::new(Cursor::new(body.to_vec()))?)
+}
+
+fn vol_url(vol_type: &VolType) -> &'static str {
+    match vol_type {
+        VolType::BusStops => URL_BY_BUS_STOPS,
+        VolType::OdBusStop => URL_BY_OD_BUS_STOPS,
+        VolType::Train => URL_BY_TRAIN,
+        VolType::OdTrain => URL_BY_OD_TRAIN,
+    }
+}
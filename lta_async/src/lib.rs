@@ -1,12 +1,36 @@
 //! Async API calls for lta-rs. Currently uses async/await
+//!
+//! APIs for each domain are behind the `bus`, `traffic`, `taxi`, `train` and `crowd`
+//! features, all enabled by default
+//!
+//! APIs can be called as free functions in each module, or through the domain
+//! handles on `LTAClient`, e.g. `client.bus().arrival(83139).service("15").send().await`
+#![cfg_attr(
+    not(any(
+        feature = "bus",
+        feature = "traffic",
+        feature = "taxi",
+        feature = "train",
+        feature = "crowd"
+    )),
+    allow(dead_code)
+)]
+
+#[cfg(feature = "bus")]
 pub mod bus;
+#[cfg(feature = "crowd")]
 pub mod crowd;
 pub mod lta_client;
+pub mod request;
+#[cfg(feature = "taxi")]
 pub mod taxi;
+#[cfg(feature = "traffic")]
 pub mod traffic;
+#[cfg(feature = "train")]
 pub mod train;
 
 use crate::lta_client::LTAClient;
+use lta_utils_commons::parse::{self, Page, ParseMode, Parsed};
 use lta_utils_commons::{reqwest, serde, Client, LTAError};
 use reqwest::RequestBuilder;
 
@@ -20,8 +44,7 @@ where
     for<'de> T: serde::Deserialize<'de> + Into<M>,
 {
     let skip = skip.unwrap_or(0);
-    let rb = client.get_req_builder(url).query(&[("$skip", skip)]);
-    rb.send().await?.json::<T>().await.map(|f| f.into())
+    build_req_async_with_query::<T, M, _>(client, url, move |rb| rb.query(&[("$skip", skip)])).await
 }
 
 /// Builds an async request that requires queries
@@ -35,20 +58,74 @@ where
     for<'de> T: serde::Deserialize<'de> + Into<M>,
 {
     let rb = client.get_req_builder(url);
-    query(rb)
-        .send()
-        .await?
-        .json::<T>()
-        .await
-        .map(|f: T| f.into())
+    let body = query(rb).send().await?.bytes().await?;
+    let data: T = parse::from_slice_strict(&body)?;
+    Ok(data.into())
+}
+
+/// Same as the functions in each module but lets you choose the `ParseMode`.
+///
+/// With `ParseMode::Lenient`, records that cannot be deserialised are skipped
+/// and returned in `Parsed::errors` instead of failing the whole page.
+///
+/// ## Example
+/// ```rust,no_run
+/// use lta_async::{get_page, lta_client::LTAClient};
+/// use lta_models::bus::bus_services::{BusService, BusServiceResp, URL};
+/// use lta_models::parse::ParseMode;
+/// use lta_utils_commons::{Client, LTAResult};
+///
+/// async fn lenient_bus_services() -> LTAResult<()> {
+///     let client = LTAClient::with_api_key("api_key");
+///     let page = get_page::<BusServiceResp, Vec<BusService>>(&client, URL, None, ParseMode::Lenient)
+///         .await?;
+///     println!("{} ok, {} skipped", page.data.len(), page.errors.len());
+///     Ok(())
+/// }
+/// ```
+pub async fn get_page<P, M>(
+    client: &LTAClient,
+    url: &str,
+    skip: Option<u32>,
+    mode: ParseMode,
+) -> Result<Parsed<M>, LTAError>
+where
+    P: Page + Into<M>,
+{
+    let skip = skip.unwrap_or(0);
+    get_page_with_query::<P, M, _>(client, url, move |rb| rb.query(&[("$skip", skip)]), mode).await
 }
 
-#[cfg(test)]
+/// `get_page` for endpoints that take query parameters, e.g. bus arrival
+pub async fn get_page_with_query<P, M, F>(
+    client: &LTAClient,
+    url: &str,
+    query: F,
+    mode: ParseMode,
+) -> Result<Parsed<M>, LTAError>
+where
+    F: FnOnce(RequestBuilder) -> RequestBuilder,
+    P: Page + Into<M>,
+{
+    let rb = client.get_req_builder(url);
+    let body = query(rb).send().await?.bytes().await?;
+    Ok(parse::parse_page::<P, M>(&body, mode)?)
+}
+
+#[cfg(all(
+    test,
+    feature = "bus",
+    feature = "traffic",
+    feature = "taxi",
+    feature = "train",
+    feature = "crowd"
+))]
 mod tests {
     use crate::lta_client::LTAClient;
     use crate::{bus, crowd, taxi, traffic, train};
     use lta_models::prelude::VolType;
     use lta_models::traffic::road::RoadDetailsType;
+    use lta_utils_commons::parse::ParseMode;
     use lta_utils_commons::{Client, LTAResult};
     use std::env;
 
@@ -115,7 +192,7 @@ mod tests {
 
     #[tokio::test]
     async fn get_cp_avail() -> LTAResult<()> {
-        gen_test!(traffic::get_carkpark_avail)
+        gen_test!(traffic::get_carpark_avail)
     }
 
     #[tokio::test]
@@ -168,4 +245,25 @@ mod tests {
     async fn get_train_service_alerts() -> LTAResult<()> {
         gen_test!(train::get_train_service_alert)
     }
+
+    #[tokio::test]
+    async fn domain_handles() -> LTAResult<()> {
+        let client = get_client();
+        let arrivals = client.bus().arrival(83139).service("15").send().await?;
+        let carparks = client.traffic().carparks().all().await?;
+        let stops = client
+            .bus()
+            .stops()
+            .skip(500)
+            .send_with(ParseMode::Lenient)
+            .await?;
+        let links = client
+            .crowd()
+            .passenger_volume(VolType::Train)
+            .latest()
+            .await?;
+        println!("{:?}", arrivals);
+        println!("{} {} {:?}", carparks.len(), stops.data.len(), links);
+        Ok(())
+    }
 }
@@ -0,0 +1,83 @@
+//! Request builders shared by the domain handles on `LTAClient`
+
+use crate::lta_client::LTAClient;
+use crate::{build_req_async_with_skip, get_page};
+use lta_utils_commons::parse::{Page, ParseMode, Parsed};
+use lta_utils_commons::{serde, LTAResult, PAGE_SIZE};
+use std::marker::PhantomData;
+
+/// Request for an endpoint that returns its records a page at a time.
+///
+/// `P` is the raw response and `M` what it is converted into.
+pub struct PagedRequest<'a, P, M> {
+    client: &'a LTAClient,
+    url: &'static str,
+    skip: Option<u32>,
+    _resp: PhantomData<fn() -> (P, M)>,
+}
+
+impl<'a, P, M> PagedRequest<'a, P, M> {
+    #[cfg_attr(
+        not(any(
+            feature = "bus",
+            feature = "traffic",
+            feature = "taxi",
+            feature = "train"
+        )),
+        allow(dead_code)
+    )]
+    pub(crate) fn new(client: &'a LTAClient, url: &'static str) -> Self {
+        PagedRequest {
+            client,
+            url,
+            skip: None,
+            _resp: PhantomData,
+        }
+    }
+
+    /// Number of records to skip, the API returns at most `PAGE_SIZE` records per request
+    pub fn skip(mut self, skip: u32) -> Self {
+        self.skip = Some(skip);
+        self
+    }
+
+    /// Sends the request, failing on the first bad record
+    pub async fn send(self) -> LTAResult<M>
+    where
+        for<'de> P: serde::Deserialize<'de> + Into<M>,
+    {
+        build_req_async_with_skip::<P, M>(self.client, self.url, self.skip).await
+    }
+
+    /// Sends the request with the given `ParseMode`
+    pub async fn send_with(self, mode: ParseMode) -> LTAResult<Parsed<M>>
+    where
+        P: Page + Into<M>,
+    {
+        get_page::<P, M>(self.client, self.url, self.skip, mode).await
+    }
+}
+
+impl<'a, P, T> PagedRequest<'a, P, Vec<T>> {
+    /// Requests every page starting from `skip` until the API runs out of records
+    pub async fn all(self) -> LTAResult<Vec<T>>
+    where
+        for<'de> P: serde::Deserialize<'de> + Into<Vec<T>>,
+    {
+        let mut skip = self.skip.unwrap_or(0);
+        let mut records = Vec::new();
+
+        loop {
+            let page =
+                build_req_async_with_skip::<P, Vec<T>>(self.client, self.url, Some(skip)).await?;
+            let len = page.len() as u32;
+            records.extend(page);
+
+            if len < PAGE_SIZE {
+                return Ok(records);
+            }
+
+            skip += PAGE_SIZE;
+        }
+    }
+}
@@ -2,6 +2,7 @@
 
 use crate::build_req_async_with_skip;
 use crate::lta_client::LTAClient;
+use crate::request::PagedRequest;
 use lta_models::train::train_service_alert::{TrainServiceAlert, TrainServiceAlertResp, URL};
 use lta_utils_commons::LTAResult;
 
@@ -15,3 +16,16 @@ pub async fn get_train_service_alert(
 ) -> LTAResult<TrainServiceAlert> {
     build_req_async_with_skip::<TrainServiceAlertResp, _>(client, URL, skip).await
 }
+
+/// Train APIs, created with `LTAClient::train`
+#[derive(Debug, Clone, Copy)]
+pub struct Train<'a> {
+    pub(crate) client: &'a LTAClient,
+}
+
+impl<'a> Train<'a> {
+    /// See `get_train_service_alert`
+    pub fn service_alert(self) -> PagedRequest<'a, TrainServiceAlertResp, TrainServiceAlert> {
+        PagedRequest::new(self.client, URL)
+    }
+}
@@ -1,13 +1,36 @@
 //! Blocking API calls for lta-rs
+//!
+//! APIs for each domain are behind the `bus`, `traffic`, `taxi`, `train` and `crowd`
+//! features, all enabled by default
+//!
+//! APIs can be called as free functions in each module, or through the domain
+//! handles on `LTAClient`, e.g. `client.bus().arrival(83139).service("15").send()`
+#![cfg_attr(
+    not(any(
+        feature = "bus",
+        feature = "traffic",
+        feature = "taxi",
+        feature = "train",
+        feature = "crowd"
+    )),
+    allow(dead_code)
+)]
 
 use crate::lta_client::LTAClient;
+use lta_utils_commons::parse::{self, Page, ParseMode, Parsed};
 use lta_utils_commons::{reqwest::blocking, serde, Client, LTAResult};
 
+#[cfg(feature = "bus")]
 pub mod bus;
+#[cfg(feature = "crowd")]
 pub mod crowd;
 pub mod lta_client;
+pub mod request;
+#[cfg(feature = "taxi")]
 pub mod taxi;
+#[cfg(feature = "traffic")]
 pub mod traffic;
+#[cfg(feature = "train")]
 pub mod train;
 
 pub(crate) fn build_req_with_skip<T, M>(
@@ -18,9 +41,7 @@ pub(crate) fn build_req_with_skip<T, M>(
 where
     for<'de> T: serde::Deserialize<'de> + Into<M>,
 {
-    let skip = skip.unwrap_or(0);
-    let rb = client.get_req_builder(url).query(&[("$skip", skip)]);
-    rb.send()?.json().map(|f: T| f.into())
+    build_req_with_query::<T, M, _>(client, url, |rb| rb.query(&[("$skip", skip.unwrap_or(0))]))
 }
 
 pub(crate) fn build_req_with_query<T, M, F>(client: &LTAClient, url: &str, query: F) -> LTAResult<M>
@@ -29,13 +50,75 @@ where
     for<'de> T: serde::Deserialize<'de> + Into<M>,
 {
     let rb = client.get_req_builder(url);
-    query(rb).send()?.json().map(|f: T| f.into())
+    let body = query(rb).send()?.bytes()?;
+    let data: T = parse::from_slice_strict(&body)?;
+    Ok(data.into())
 }
 
-#[cfg(test)]
+/// Same as the functions in each module but lets you choose the `ParseMode`.
+///
+/// With `ParseMode::Lenient`, records that cannot be deserialised are skipped
+/// and returned in `Parsed::errors` instead of failing the whole page.
+///
+/// ## Example
+/// ```rust,no_run
+/// use lta_blocking::{get_page, lta_client::LTAClient};
+/// use lta_models::bus::bus_services::{BusService, BusServiceResp, URL};
+/// use lta_models::parse::ParseMode;
+/// use lta_utils_commons::{Client, LTAResult};
+///
+/// fn main() -> LTAResult<()> {
+///     let client = LTAClient::with_api_key("api_key");
+///     let page = get_page::<BusServiceResp, Vec<BusService>>(&client, URL, None, ParseMode::Lenient)?;
+///     println!("{} ok, {} skipped", page.data.len(), page.errors.len());
+///     Ok(())
+/// }
+/// ```
+pub fn get_page<P, M>(
+    client: &LTAClient,
+    url: &str,
+    skip: Option<u32>,
+    mode: ParseMode,
+) -> LTAResult<Parsed<M>>
+where
+    P: Page + Into<M>,
+{
+    get_page_with_query::<P, M, _>(
+        client,
+        url,
+        |rb| rb.query(&[("$skip", skip.unwrap_or(0))]),
+        mode,
+    )
+}
+
+/// `get_page` for endpoints that take query parameters, e.g. bus arrival
+pub fn get_page_with_query<P, M, F>(
+    client: &LTAClient,
+    url: &str,
+    query: F,
+    mode: ParseMode,
+) -> LTAResult<Parsed<M>>
+where
+    F: FnOnce(blocking::RequestBuilder) -> blocking::RequestBuilder,
+    P: Page + Into<M>,
+{
+    let rb = client.get_req_builder(url);
+    let body = query(rb).send()?.bytes()?;
+    Ok(parse::parse_page::<P, M>(&body, mode)?)
+}
+
+#[cfg(all(
+    test,
+    feature = "bus",
+    feature = "traffic",
+    feature = "taxi",
+    feature = "train",
+    feature = "crowd"
+))]
 mod tests {
     use crate::{bus, crowd, lta_client::LTAClient, taxi, traffic, train};
     use lta_models::prelude::*;
+    use lta_utils_commons::parse::ParseMode;
     use lta_utils_commons::{Client, LTAResult};
     use std::env;
 
@@ -155,4 +238,20 @@ mod tests {
     fn get_train_service_alerts() -> LTAResult<()> {
         gen_test!(train::get_train_service_alert)
     }
+
+    #[test]
+    fn domain_handles() -> LTAResult<()> {
+        let client = get_client();
+        let arrivals = client.bus().arrival(83139).service("15").send()?;
+        let carparks = client.traffic().carparks().all()?;
+        let stops = client
+            .bus()
+            .stops()
+            .skip(500)
+            .send_with(ParseMode::Lenient)?;
+        let links = client.crowd().passenger_volume(VolType::Train).latest()?;
+        println!("{:?}", arrivals);
+        println!("{} {} {:?}", carparks.len(), stops.data.len(), links);
+        Ok(())
+    }
 }
@@ -30,7 +30,7 @@ use lta_utils_commons::{reqwest::blocking as rq_blocking, Client};
 #[derive(Debug, Clone)]
 pub struct LTAClient {
     api_key: Option<String>,
-    client: rq_blocking::Client,
+    pub(crate) client: rq_blocking::Client,
 }
 
 impl Client<rq_blocking::Client, rq_blocking::RequestBuilder> for LTAClient {
@@ -63,3 +63,76 @@ impl Client<rq_blocking::Client, rq_blocking::RequestBuilder> for LTAClient {
         self.client.get(url).header("AccountKey", api_key.as_str())
     }
 }
+
+impl LTAClient {
+    /// Bus APIs
+    ///
+    /// ## Example
+    /// ```rust,no_run
+    /// use lta_blocking::lta_client::LTAClient;
+    /// use lta_utils_commons::{Client, LTAResult};
+    ///
+    /// fn main() -> LTAResult<()> {
+    ///     let client = LTAClient::with_api_key("api_key");
+    ///     let arrivals = client.bus().arrival(83139).service("15").send()?;
+    ///     println!("{:?}", arrivals);
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg(feature = "bus")]
+    pub fn bus(&self) -> crate::bus::Bus<'_> {
+        crate::bus::Bus { client: self }
+    }
+
+    /// Traffic APIs
+    ///
+    /// ## Example
+    /// ```rust,no_run
+    /// use lta_blocking::lta_client::LTAClient;
+    /// use lta_utils_commons::{Client, LTAResult};
+    ///
+    /// fn main() -> LTAResult<()> {
+    ///     let client = LTAClient::with_api_key("api_key");
+    ///     let carparks = client.traffic().carparks().all()?;
+    ///     println!("{}", carparks.len());
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg(feature = "traffic")]
+    pub fn traffic(&self) -> crate::traffic::Traffic<'_> {
+        crate::traffic::Traffic { client: self }
+    }
+
+    /// Taxi APIs
+    #[cfg(feature = "taxi")]
+    pub fn taxi(&self) -> crate::taxi::Taxi<'_> {
+        crate::taxi::Taxi { client: self }
+    }
+
+    /// Train APIs
+    #[cfg(feature = "train")]
+    pub fn train(&self) -> crate::train::Train<'_> {
+        crate::train::Train { client: self }
+    }
+
+    /// Crowd APIs
+    ///
+    /// ## Example
+    /// ```rust,no_run
+    /// use lta_blocking::lta_client::LTAClient;
+    /// use lta_models::crowd::passenger_vol::{VolType, YearMonth};
+    /// use lta_utils_commons::{Client, LTAResult};
+    ///
+    /// fn main() -> LTAResult<()> {
+    ///     let client = LTAClient::with_api_key("api_key");
+    ///     let month = YearMonth::new(2019, 11).expect("valid month");
+    ///     let links = client.crowd().passenger_volume(VolType::Train).month(month)?;
+    ///     println!("{:?}", links);
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg(feature = "crowd")]
+    pub fn crowd(&self) -> crate::crowd::Crowd<'_> {
+        crate::crowd::Crowd { client: self }
+    }
+}
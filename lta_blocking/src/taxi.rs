@@ -2,6 +2,7 @@
 
 use crate::build_req_with_skip;
 use crate::lta_client::LTAClient;
+use crate::request::PagedRequest;
 use lta_models::taxi::taxi_avail::TaxiAvailResp;
 use lta_models::taxi::taxi_stands::{TaxiStand, TaxiStandsResp};
 use lta_models::taxi::{taxi_avail, taxi_stands};
@@ -20,4 +21,22 @@ pub fn get_taxi_avail(client: &LTAClient, skip: Option<u32>) -> LTAResult<Vec<Co
 /// **Update freq**: Monthly
 pub fn get_taxi_stands(client: &LTAClient, skip: Option<u32>) -> LTAResult<Vec<TaxiStand>> {
     build_req_with_skip::<TaxiStandsResp, _>(client, taxi_stands::URL, skip)
-}
\ No newline at end of file
+}
+
+/// Taxi APIs, created with `LTAClient::taxi`
+#[derive(Debug, Clone, Copy)]
+pub struct Taxi<'a> {
+    pub(crate) client: &'a LTAClient,
+}
+
+impl<'a> Taxi<'a> {
+    /// See `get_taxi_avail`
+    pub fn avail(self) -> PagedRequest<'a, TaxiAvailResp, Vec<Coordinates>> {
+        PagedRequest::new(self.client, taxi_avail::URL)
+    }
+
+    /// See `get_taxi_stands`
+    pub fn stands(self) -> PagedRequest<'a, TaxiStandsResp, Vec<TaxiStand>> {
+        PagedRequest::new(self.client, taxi_stands::URL)
+    }
+}
@@ -1,11 +1,13 @@
 //! All APIs pertaining to traffic
 
 use crate::lta_client::LTAClient;
-use crate::{build_req_with_query, build_req_with_skip};
+use crate::request::PagedRequest;
+use crate::{build_req_with_query, build_req_with_skip, get_page_with_query};
 use lta_models::traffic::{
     bike_parking, carpark_avail, erp_rates, est_travel_time, faulty_traffic_lights, road,
     traffic_images, traffic_incidents, traffic_speed_bands, vms_emas,
 };
+use lta_utils_commons::parse::{ParseMode, Parsed};
 use lta_utils_commons::LTAResult;
 
 /// Returns ERP rates of all vehicle types across all timings for each
@@ -137,3 +139,137 @@ pub fn get_bike_parking(
         rb.query(&[("Lat", lat), ("Long", long), ("Dist", unwrapped_dist)])
     })
 }
+
+/// Traffic APIs, created with `LTAClient::traffic`
+#[derive(Debug, Clone, Copy)]
+pub struct Traffic<'a> {
+    pub(crate) client: &'a LTAClient,
+}
+
+impl<'a> Traffic<'a> {
+    /// See `get_erp_rates`
+    pub fn erp_rates(self) -> PagedRequest<'a, erp_rates::ErpRatesResp, Vec<erp_rates::ErpRate>> {
+        PagedRequest::new(self.client, erp_rates::URL)
+    }
+
+    /// See `get_carpark_avail`
+    pub fn carparks(
+        self,
+    ) -> PagedRequest<'a, carpark_avail::CarparkAvailResp, Vec<carpark_avail::CarPark>> {
+        PagedRequest::new(self.client, carpark_avail::URL)
+    }
+
+    /// See `get_est_travel_time`
+    pub fn est_travel_time(
+        self,
+    ) -> PagedRequest<'a, est_travel_time::EstTravelTimeResp, Vec<est_travel_time::EstTravelTime>>
+    {
+        PagedRequest::new(self.client, est_travel_time::URL)
+    }
+
+    /// See `get_faulty_traffic_lights`
+    pub fn faulty_traffic_lights(
+        self,
+    ) -> PagedRequest<
+        'a,
+        faulty_traffic_lights::FaultyTrafficLightResp,
+        Vec<faulty_traffic_lights::FaultyTrafficLight>,
+    > {
+        PagedRequest::new(self.client, faulty_traffic_lights::URL)
+    }
+
+    /// See `get_road_details`
+    pub fn road_details(
+        self,
+        road_details_type: road::RoadDetailsType,
+    ) -> PagedRequest<'a, road::RoadDetailsResp, Vec<road::RoadDetails>> {
+        let url = match road_details_type {
+            road::RoadDetailsType::RoadOpening => road::URL_ROAD_OPENING,
+            road::RoadDetailsType::RoadWorks => road::URL_ROAD_WORKS,
+        };
+
+        PagedRequest::new(self.client, url)
+    }
+
+    /// See `get_traffic_images`
+    pub fn images(
+        self,
+    ) -> PagedRequest<'a, traffic_images::TrafficImageResp, Vec<traffic_images::TrafficImage>> {
+        PagedRequest::new(self.client, traffic_images::URL)
+    }
+
+    /// See `get_traffic_incidents`
+    pub fn incidents(
+        self,
+    ) -> PagedRequest<
+        'a,
+        traffic_incidents::TrafficIncidentResp,
+        Vec<traffic_incidents::TrafficIncident>,
+    > {
+        PagedRequest::new(self.client, traffic_incidents::URL)
+    }
+
+    /// See `get_traffic_speed_band`
+    pub fn speed_bands(
+        self,
+    ) -> PagedRequest<
+        'a,
+        traffic_speed_bands::TrafficSpeedBandResp,
+        Vec<traffic_speed_bands::TrafficSpeedBand>,
+    > {
+        PagedRequest::new(self.client, traffic_speed_bands::URL)
+    }
+
+    /// See `get_vms_emas`
+    pub fn vms_emas(self) -> PagedRequest<'a, vms_emas::VMSResp, Vec<vms_emas::VMS>> {
+        PagedRequest::new(self.client, vms_emas::URL)
+    }
+
+    /// Bicycle parking around a point, see `get_bike_parking`
+    pub fn bike_parking(self, lat: f64, long: f64) -> BikeParkingRequest<'a> {
+        BikeParkingRequest {
+            client: self.client,
+            lat,
+            long,
+            dist: None,
+        }
+    }
+}
+
+/// Bicycle parking request, created with `Traffic::bike_parking`
+#[derive(Debug, Clone)]
+pub struct BikeParkingRequest<'a> {
+    client: &'a LTAClient,
+    lat: f64,
+    long: f64,
+    dist: Option<f64>,
+}
+
+impl<'a> BikeParkingRequest<'a> {
+    /// Search radius in km, 0.5 if not set
+    pub fn dist(mut self, dist: f64) -> Self {
+        self.dist = Some(dist);
+        self
+    }
+
+    /// Sends the request, failing on the first bad record
+    pub fn send(self) -> LTAResult<Vec<bike_parking::BikeParking>> {
+        get_bike_parking(self.client, self.lat, self.long, self.dist)
+    }
+
+    /// Sends the request with the given `ParseMode`
+    pub fn send_with(self, mode: ParseMode) -> LTAResult<Parsed<Vec<bike_parking::BikeParking>>> {
+        let query = [
+            ("Lat", self.lat),
+            ("Long", self.long),
+            ("Dist", self.dist.unwrap_or(0.5)),
+        ];
+
+        get_page_with_query::<bike_parking::BikeParkingResp, _, _>(
+            self.client,
+            bike_parking::URL,
+            |rb| rb.query(&query),
+            mode,
+        )
+    }
+}
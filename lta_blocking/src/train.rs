@@ -2,6 +2,7 @@
 
 use crate::build_req_with_skip;
 use crate::lta_client::LTAClient;
+use crate::request::PagedRequest;
 use lta_models::train::train_service_alert;
 use lta_utils_commons::LTAResult;
 
@@ -19,3 +20,22 @@ pub fn get_train_service_alert(
         skip,
     )
 }
+
+/// Train APIs, created with `LTAClient::train`
+#[derive(Debug, Clone, Copy)]
+pub struct Train<'a> {
+    pub(crate) client: &'a LTAClient,
+}
+
+impl<'a> Train<'a> {
+    /// See `get_train_service_alert`
+    pub fn service_alert(
+        self,
+    ) -> PagedRequest<
+        'a,
+        train_service_alert::TrainServiceAlertResp,
+        train_service_alert::TrainServiceAlert,
+    > {
+        PagedRequest::new(self.client, train_service_alert::URL)
+    }
+}
@@ -1 +1,57 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use lta_models::geo::BoundingBox;
+use lta_models::spatial::SpatialIndex;
+use lta_utils_commons::Coordinates;
 
+/// About as many points as there are bus stops, spread over Singapore
+fn points(n: usize) -> Vec<Coordinates> {
+    let mut seed = 42u64;
+    let mut next = move || {
+        seed = seed
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        (seed >> 11) as f64 / (1u64 << 53) as f64
+    };
+
+    (0..n)
+        .map(|_| Coordinates::new(1.24 + next() * 0.23, 103.62 + next() * 0.4))
+        .collect()
+}
+
+fn spatial_index(c: &mut Criterion) {
+    let points = points(5000);
+    let index: SpatialIndex<_> = points.iter().collect();
+    let here = Coordinates::new(1.3521, 103.8198);
+
+    c.bench_function("spatial build 5000", |b| {
+        b.iter(|| SpatialIndex::new(black_box(&points)))
+    });
+
+    c.bench_function("spatial nearest 5", |b| {
+        b.iter(|| index.nearest(black_box(&here), 5))
+    });
+
+    c.bench_function("brute force nearest 5", |b| {
+        b.iter(|| {
+            let mut all: Vec<_> = points
+                .iter()
+                .map(|p| (p, black_box(&here).distance_m(p)))
+                .collect();
+            all.sort_by(|a, b| a.1.total_cmp(&b.1));
+            all.truncate(5);
+            all
+        })
+    });
+
+    c.bench_function("spatial within 500m", |b| {
+        b.iter(|| index.within_radius(black_box(&here), 500.0))
+    });
+
+    let bbox = BoundingBox::around(&here, 1000.0);
+    c.bench_function("spatial within bbox", |b| {
+        b.iter(|| index.within_bbox(black_box(&bbox)))
+    });
+}
+
+criterion_group!(benches, spatial_index);
+criterion_main!(benches);
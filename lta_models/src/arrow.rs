@@ -0,0 +1,964 @@
+//! Arrow record batches and Parquet files of every model
+//!
+//! Each field is a column named after it:
+//!
+//! - enums are dictionary encoded strings of `as_str`, enums the API sends as
+//!   integers stay `UInt32`
+//! - times are `Time64(Microsecond)`, dates `Date32` and timestamps
+//!   `Timestamp(Microsecond, "+08:00")`
+//! - `Coordinates`, `Location`, `BusFreq` and `NextBus` are struct columns
+//! - lists are list columns and `next_bus` a fixed size list of 3
+//! - `Option` fields are nullable, every other column is not
+//!
+//! Bus arrivals and train service alerts are written as they are, with their
+//! services, segments and messages as lists of structs.
+//!
+//! ## Example
+//! ```rust
+//! use lta_models::arrow::{read_parquet, to_record_batch, write_parquet};
+//! use lta_models::bus::bus_stops::{BusStop, BusStopsResp};
+//!
+//! let stops: BusStopsResp =
+//!     serde_json::from_str(include_str!("../../dumped_data/bus_stops.json")).unwrap();
+//!
+//! let batch = to_record_batch(&stops.value).unwrap();
+//! assert_eq!(batch.num_rows(), stops.value.len());
+//! assert_eq!(batch.schema().field(0).name(), "bus_stop_code");
+//!
+//! let path = std::env::temp_dir().join("lta_bus_stops.parquet");
+//! write_parquet(std::fs::File::create(&path).unwrap(), &stops.value).unwrap();
+//! let read: Vec<BusStop> = read_parquet(std::fs::File::open(&path).unwrap()).unwrap();
+//! assert_eq!(read, stops.value);
+//! # std::fs::remove_file(path).unwrap();
+//! ```
+
+use arrow_array::{
+    Array, ArrayRef, BooleanArray, Date32Array, FixedSizeListArray, Float32Array, Float64Array,
+    Int32Array, ListArray, RecordBatch, StringArray, StructArray, Time64MicrosecondArray,
+    TimestampMicrosecondArray, UInt32Array, UInt64Array, UInt8Array,
+};
+use arrow_buffer::{NullBuffer, OffsetBuffer};
+use arrow_schema::{DataType, Field, Fields, Schema, SchemaRef, TimeUnit};
+use lta_utils_commons::chrono::{DateTime, Datelike, NaiveDate, NaiveTime, Timelike};
+use lta_utils_commons::sg_time::{self, SgDateTime};
+use lta_utils_commons::{Coordinates, Location};
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use parquet::file::reader::ChunkReader;
+use std::convert::TryInto;
+use std::fmt::{self, Display, Formatter};
+use std::io::Write;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+/// Timezone of timestamp columns
+pub const TIMEZONE: &str = "+08:00";
+
+/// Days from 0001-01-01 to 1970-01-01
+const UNIX_EPOCH_DAYS_FROM_CE: i32 = 719_163;
+
+/// Error converting to or from Arrow or Parquet
+#[derive(Debug)]
+pub enum ArrowError {
+    Arrow(arrow_schema::ArrowError),
+    Parquet(parquet::errors::ParquetError),
+
+    /// Column, with the names of its parents joined by `.`, and what is wrong with it
+    Column {
+        column: String,
+        msg: String,
+    },
+}
+
+impl Display for ArrowError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ArrowError::Arrow(e) => write!(f, "arrow error: {}", e),
+            ArrowError::Parquet(e) => write!(f, "parquet error: {}", e),
+            ArrowError::Column { column, msg } => write!(f, "column {}: {}", column, msg),
+        }
+    }
+}
+
+impl std::error::Error for ArrowError {}
+
+impl From<arrow_schema::ArrowError> for ArrowError {
+    fn from(e: arrow_schema::ArrowError) -> Self {
+        ArrowError::Arrow(e)
+    }
+}
+
+impl From<parquet::errors::ParquetError> for ArrowError {
+    fn from(e: parquet::errors::ParquetError) -> Self {
+        ArrowError::Parquet(e)
+    }
+}
+
+fn column_error<M: Into<String>>(column: &str, msg: M) -> ArrowError {
+    ArrowError::Column {
+        column: column.to_string(),
+        msg: msg.into(),
+    }
+}
+
+/// A value stored in an Arrow column
+pub trait ArrowField: Sized {
+    fn data_type() -> DataType;
+
+    /// Whether the column can hold nulls, only for `Option`
+    fn nullable() -> bool {
+        false
+    }
+
+    /// Column of the values, `None` is a null
+    fn to_array(values: &[Option<&Self>]) -> ArrayRef;
+
+    /// Values of a column, `column` is only used in errors
+    fn from_array(array: &ArrayRef, column: &str) -> Result<Vec<Option<Self>>, ArrowError>;
+}
+
+/// A model stored as a row of a record batch
+pub trait ArrowRecord: ArrowField {
+    /// Columns of the record batch
+    fn fields() -> Fields;
+}
+
+/// Schema of the record batches of `T`
+pub fn schema<T: ArrowRecord>() -> SchemaRef {
+    Arc::new(Schema::new(T::fields()))
+}
+
+/// Record batch with a row for every record
+pub fn to_record_batch<T: ArrowRecord>(records: &[T]) -> Result<RecordBatch, ArrowError> {
+    let values: Vec<_> = records.iter().map(Some).collect();
+    let array = T::to_array(&values);
+    let columns = downcast::<StructArray>(&array, "")?.columns().to_vec();
+    Ok(RecordBatch::try_new(schema::<T>(), columns)?)
+}
+
+/// Records of every row of a batch
+pub fn from_record_batch<T: ArrowRecord>(batch: &RecordBatch) -> Result<Vec<T>, ArrowError> {
+    let array: ArrayRef = Arc::new(StructArray::from(batch.clone()));
+    T::from_array(&array, "")?
+        .into_iter()
+        .map(|r| r.ok_or_else(|| column_error("", "null row")))
+        .collect()
+}
+
+/// Writes record batches to a Parquet file, compressed with Snappy
+pub struct ParquetWriter<W: Write + Send, T> {
+    writer: ArrowWriter<W>,
+    record: PhantomData<T>,
+}
+
+impl<W: Write + Send, T: ArrowRecord> ParquetWriter<W, T> {
+    pub fn new(writer: W) -> Result<Self, ArrowError> {
+        let props = WriterProperties::builder()
+            .set_compression(Compression::SNAPPY)
+            .build();
+        Ok(ParquetWriter {
+            writer: ArrowWriter::try_new(writer, schema::<T>(), Some(props))?,
+            record: PhantomData,
+        })
+    }
+
+    /// Writes the records as one batch
+    pub fn write(&mut self, records: &[T]) -> Result<(), ArrowError> {
+        self.writer.write(&to_record_batch(records)?)?;
+        Ok(())
+    }
+
+    /// Writes the file footer and returns the writer
+    pub fn finish(self) -> Result<W, ArrowError> {
+        Ok(self.writer.into_inner()?)
+    }
+}
+
+/// Writes a Parquet file of every record
+pub fn write_parquet<W, T>(writer: W, records: &[T]) -> Result<W, ArrowError>
+where
+    W: Write + Send,
+    T: ArrowRecord,
+{
+    let mut writer = ParquetWriter::new(writer)?;
+    writer.write(records)?;
+    writer.finish()
+}
+
+/// Reads every record of a Parquet file written by `ParquetWriter`,
+/// e.g. from a `File`
+pub fn read_parquet<T, R>(reader: R) -> Result<Vec<T>, ArrowError>
+where
+    T: ArrowRecord,
+    R: ChunkReader + 'static,
+{
+    let mut records = Vec::new();
+    for batch in ParquetRecordBatchReaderBuilder::try_new(reader)?.build()? {
+        records.extend(from_record_batch(&batch?)?);
+    }
+    Ok(records)
+}
+
+fn downcast<'a, A: Array + 'static>(
+    array: &'a ArrayRef,
+    column: &str,
+) -> Result<&'a A, ArrowError> {
+    array
+        .as_any()
+        .downcast_ref::<A>()
+        .ok_or_else(|| column_error(column, format!("unexpected type {}", array.data_type())))
+}
+
+fn join(parent: &str, name: &str) -> String {
+    if parent.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", parent, name)
+    }
+}
+
+fn nulls<T>(values: &[Option<T>]) -> Option<NullBuffer> {
+    if values.iter().all(Option::is_some) {
+        return None;
+    }
+    Some(values.iter().map(Option::is_some).collect())
+}
+
+/// Field of a struct column, the closure is only used to name the field's type
+fn struct_field<R, F, G>(name: &str, _: G) -> Field
+where
+    F: ArrowField,
+    G: Fn(&R) -> &F,
+{
+    Field::new(name, F::data_type(), F::nullable())
+}
+
+/// Child column of a struct column
+fn field_array<R, F, G>(values: &[Option<&R>], get: G) -> ArrayRef
+where
+    F: ArrowField,
+    G: Fn(&R) -> &F,
+{
+    let fields: Vec<_> = values.iter().map(|v| v.map(&get)).collect();
+    F::to_array(&fields)
+}
+
+/// Values of the child column `name` of a struct column
+fn field_values<R, F, G>(
+    array: &StructArray,
+    name: &str,
+    column: &str,
+    _: G,
+) -> Result<std::vec::IntoIter<Option<F>>, ArrowError>
+where
+    F: ArrowField,
+    G: Fn(&R) -> &F,
+{
+    let column = join(column, name);
+    let child = array
+        .column_by_name(name)
+        .ok_or_else(|| column_error(&column, "missing column"))?;
+    Ok(F::from_array(child, &column)?.into_iter())
+}
+
+/// Values with an Arrow primitive array of the same type
+macro_rules! primitive_fields {
+    ($($value:ty => $array:ty, $data_type:expr);* $(;)?) => {
+        $(impl ArrowField for $value {
+            fn data_type() -> DataType {
+                $data_type
+            }
+
+            fn to_array(values: &[Option<&Self>]) -> ArrayRef {
+                Arc::new(values.iter().map(|v| v.copied()).collect::<$array>())
+            }
+
+            fn from_array(array: &ArrayRef, column: &str) -> Result<Vec<Option<Self>>, ArrowError> {
+                Ok(downcast::<$array>(array, column)?.iter().collect())
+            }
+        })*
+    };
+}
+
+/// Values stored as strings with `Display` and `FromStr`
+#[cfg(feature = "crowd")]
+macro_rules! str_fields {
+    ($($value:ty),* $(,)?) => {
+        $(impl ArrowField for $value {
+            fn data_type() -> DataType {
+                DataType::Utf8
+            }
+
+            fn to_array(values: &[Option<&Self>]) -> ArrayRef {
+                let strings = values.iter().map(|v| v.map(|v| v.to_string()));
+                Arc::new(strings.collect::<StringArray>())
+            }
+
+            fn from_array(array: &ArrayRef, column: &str) -> Result<Vec<Option<Self>>, ArrowError> {
+                downcast::<StringArray>(array, column)?
+                    .iter()
+                    .map(|v| v.map(|s| s.parse().map_err(|e| {
+                        column_error(column, format!("invalid value `{}`: {}", s, e))
+                    })).transpose())
+                    .collect()
+            }
+        })*
+    };
+}
+
+/// Enums generated by `str_enum`, stored as dictionary encoded strings
+#[cfg(any(
+    feature = "bus",
+    feature = "traffic",
+    feature = "taxi",
+    feature = "train",
+    feature = "crowd"
+))]
+macro_rules! dict_fields {
+    ($($value:ty),* $(,)?) => {
+        $(impl ArrowField for $value {
+            fn data_type() -> DataType {
+                DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8))
+            }
+
+            fn to_array(values: &[Option<&Self>]) -> ArrayRef {
+                use arrow_array::builder::StringDictionaryBuilder;
+                use arrow_array::types::Int32Type;
+
+                let mut builder = StringDictionaryBuilder::<Int32Type>::new();
+                for v in values {
+                    builder.append_option(v.map(|v| v.as_str()));
+                }
+                Arc::new(builder.finish())
+            }
+
+            fn from_array(array: &ArrayRef, column: &str) -> Result<Vec<Option<Self>>, ArrowError> {
+                use arrow_array::types::Int32Type;
+                use arrow_array::DictionaryArray;
+
+                let dict = downcast::<DictionaryArray<Int32Type>>(array, column)?;
+                let strings = downcast::<StringArray>(dict.values(), column)?;
+                Ok(dict
+                    .keys()
+                    .iter()
+                    .map(|k| k.map(|k| match strings.value(k as usize).parse() {
+                        Ok(v) => v,
+                        Err(e) => match e {},
+                    }))
+                    .collect())
+            }
+        })*
+    };
+}
+
+/// Enums generated by `int_enum`, stored as their integer value
+#[cfg(any(feature = "traffic", feature = "train"))]
+macro_rules! int_fields {
+    ($($value:ty),* $(,)?) => {
+        $(impl ArrowField for $value {
+            fn data_type() -> DataType {
+                DataType::UInt32
+            }
+
+            fn to_array(values: &[Option<&Self>]) -> ArrayRef {
+                Arc::new(values.iter().map(|v| v.map(|v| v.value())).collect::<UInt32Array>())
+            }
+
+            fn from_array(array: &ArrayRef, column: &str) -> Result<Vec<Option<Self>>, ArrowError> {
+                Ok(downcast::<UInt32Array>(array, column)?
+                    .iter()
+                    .map(|v| v.map(<$value>::from))
+                    .collect())
+            }
+        })*
+    };
+}
+
+/// Structs stored as struct columns with every field listed in order
+macro_rules! record_fields {
+    ($record:ty { $($field:ident),* $(,)? }) => {
+        impl ArrowField for $record {
+            fn data_type() -> DataType {
+                DataType::Struct(<$record as ArrowRecord>::fields())
+            }
+
+            fn to_array(values: &[Option<&Self>]) -> ArrayRef {
+                let arrays = vec![$(field_array(values, |r: &$record| &r.$field),)*];
+                Arc::new(StructArray::new(
+                    <$record as ArrowRecord>::fields(),
+                    arrays,
+                    nulls(values),
+                ))
+            }
+
+            fn from_array(array: &ArrayRef, column: &str) -> Result<Vec<Option<Self>>, ArrowError> {
+                let array = downcast::<StructArray>(array, column)?;
+                $(let mut $field =
+                    field_values(array, stringify!($field), column, |r: &$record| &r.$field)?;)*
+
+                (0..array.len())
+                    .map(|i| {
+                        $(let $field = $field.next().flatten();)*
+                        if array.is_null(i) {
+                            return Ok(None);
+                        }
+
+                        Ok(Some(Self {
+                            $($field: $field.ok_or_else(|| {
+                                column_error(&join(column, stringify!($field)), "missing value")
+                            })?,)*
+                        }))
+                    })
+                    .collect()
+            }
+        }
+
+        impl ArrowRecord for $record {
+            fn fields() -> Fields {
+                Fields::from(vec![$(struct_field(stringify!($field), |r: &$record| &r.$field),)*])
+            }
+        }
+    };
+}
+
+primitive_fields!(
+    u8 => UInt8Array, DataType::UInt8;
+    u32 => UInt32Array, DataType::UInt32;
+    u64 => UInt64Array, DataType::UInt64;
+    i32 => Int32Array, DataType::Int32;
+    f32 => Float32Array, DataType::Float32;
+    f64 => Float64Array, DataType::Float64;
+);
+
+impl ArrowField for bool {
+    fn data_type() -> DataType {
+        DataType::Boolean
+    }
+
+    fn to_array(values: &[Option<&Self>]) -> ArrayRef {
+        Arc::new(values.iter().map(|v| v.copied()).collect::<BooleanArray>())
+    }
+
+    fn from_array(array: &ArrayRef, column: &str) -> Result<Vec<Option<Self>>, ArrowError> {
+        Ok(downcast::<BooleanArray>(array, column)?.iter().collect())
+    }
+}
+
+impl ArrowField for String {
+    fn data_type() -> DataType {
+        DataType::Utf8
+    }
+
+    fn to_array(values: &[Option<&Self>]) -> ArrayRef {
+        Arc::new(
+            values
+                .iter()
+                .map(|v| v.map(String::as_str))
+                .collect::<StringArray>(),
+        )
+    }
+
+    fn from_array(array: &ArrayRef, column: &str) -> Result<Vec<Option<Self>>, ArrowError> {
+        Ok(downcast::<StringArray>(array, column)?
+            .iter()
+            .map(|v| v.map(str::to_string))
+            .collect())
+    }
+}
+
+impl ArrowField for NaiveTime {
+    fn data_type() -> DataType {
+        DataType::Time64(TimeUnit::Microsecond)
+    }
+
+    fn to_array(values: &[Option<&Self>]) -> ArrayRef {
+        let micros = |t: &NaiveTime| {
+            t.num_seconds_from_midnight() as i64 * 1_000_000 + t.nanosecond() as i64 / 1_000
+        };
+        Arc::new(
+            values
+                .iter()
+                .map(|v| v.map(micros))
+                .collect::<Time64MicrosecondArray>(),
+        )
+    }
+
+    fn from_array(array: &ArrayRef, column: &str) -> Result<Vec<Option<Self>>, ArrowError> {
+        downcast::<Time64MicrosecondArray>(array, column)?
+            .iter()
+            .map(|v| {
+                v.map(|v| {
+                    NaiveTime::from_num_seconds_from_midnight_opt(
+                        (v / 1_000_000) as u32,
+                        (v % 1_000_000) as u32 * 1_000,
+                    )
+                    .ok_or_else(|| column_error(column, format!("invalid time {}", v)))
+                })
+                .transpose()
+            })
+            .collect()
+    }
+}
+
+impl ArrowField for NaiveDate {
+    fn data_type() -> DataType {
+        DataType::Date32
+    }
+
+    fn to_array(values: &[Option<&Self>]) -> ArrayRef {
+        Arc::new(
+            values
+                .iter()
+                .map(|v| v.map(|d| d.num_days_from_ce() - UNIX_EPOCH_DAYS_FROM_CE))
+                .collect::<Date32Array>(),
+        )
+    }
+
+    fn from_array(array: &ArrayRef, column: &str) -> Result<Vec<Option<Self>>, ArrowError> {
+        downcast::<Date32Array>(array, column)?
+            .iter()
+            .map(|v| {
+                v.map(|v| {
+                    NaiveDate::from_num_days_from_ce_opt(v + UNIX_EPOCH_DAYS_FROM_CE)
+                        .ok_or_else(|| column_error(column, format!("invalid date {}", v)))
+                })
+                .transpose()
+            })
+            .collect()
+    }
+}
+
+impl ArrowField for SgDateTime {
+    fn data_type() -> DataType {
+        DataType::Timestamp(TimeUnit::Microsecond, Some(TIMEZONE.into()))
+    }
+
+    fn to_array(values: &[Option<&Self>]) -> ArrayRef {
+        let array: TimestampMicrosecondArray = values
+            .iter()
+            .map(|v| v.map(|t| t.timestamp_micros()))
+            .collect();
+        Arc::new(array.with_timezone(TIMEZONE))
+    }
+
+    fn from_array(array: &ArrayRef, column: &str) -> Result<Vec<Option<Self>>, ArrowError> {
+        downcast::<TimestampMicrosecondArray>(array, column)?
+            .iter()
+            .map(|v| {
+                v.map(|v| {
+                    DateTime::from_timestamp_micros(v)
+                        .map(|t| sg_time::to_sg(&t))
+                        .ok_or_else(|| column_error(column, format!("invalid timestamp {}", v)))
+                })
+                .transpose()
+            })
+            .collect()
+    }
+}
+
+impl<T: ArrowField> ArrowField for Option<T> {
+    fn data_type() -> DataType {
+        T::data_type()
+    }
+
+    fn nullable() -> bool {
+        true
+    }
+
+    fn to_array(values: &[Option<&Self>]) -> ArrayRef {
+        let values: Vec<_> = values.iter().map(|v| v.and_then(Option::as_ref)).collect();
+        T::to_array(&values)
+    }
+
+    fn from_array(array: &ArrayRef, column: &str) -> Result<Vec<Option<Self>>, ArrowError> {
+        Ok(T::from_array(array, column)?
+            .into_iter()
+            .map(Some)
+            .collect())
+    }
+}
+
+fn item_field<T: ArrowField>() -> Arc<Field> {
+    Arc::new(Field::new("item", T::data_type(), T::nullable()))
+}
+
+/// Items of a list column, in order, with an error for missing ones
+fn required_items<T: ArrowField>(
+    values: &ArrayRef,
+    column: &str,
+) -> Result<std::vec::IntoIter<Option<T>>, ArrowError> {
+    Ok(T::from_array(values, &join(column, "item"))?.into_iter())
+}
+
+impl<T: ArrowField> ArrowField for Vec<T> {
+    fn data_type() -> DataType {
+        DataType::List(item_field::<T>())
+    }
+
+    fn to_array(values: &[Option<&Self>]) -> ArrayRef {
+        let items: Vec<_> = values
+            .iter()
+            .flatten()
+            .flat_map(|v| v.iter().map(Some))
+            .collect();
+        let offsets = OffsetBuffer::from_lengths(values.iter().map(|v| v.map_or(0, Vec::len)));
+        Arc::new(ListArray::new(
+            item_field::<T>(),
+            offsets,
+            T::to_array(&items),
+            nulls(values),
+        ))
+    }
+
+    fn from_array(array: &ArrayRef, column: &str) -> Result<Vec<Option<Self>>, ArrowError> {
+        let list = downcast::<ListArray>(array, column)?;
+        let offsets = list.value_offsets();
+        let mut items = required_items::<T>(list.values(), column)?.skip(offsets[0] as usize);
+
+        offsets
+            .windows(2)
+            .enumerate()
+            .map(|(i, w)| {
+                let row = items
+                    .by_ref()
+                    .take((w[1] - w[0]) as usize)
+                    .map(|item| item.ok_or_else(|| column_error(column, "null item")))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(if list.is_null(i) { None } else { Some(row) })
+            })
+            .collect()
+    }
+}
+
+impl<T: ArrowField, const N: usize> ArrowField for [T; N] {
+    fn data_type() -> DataType {
+        DataType::FixedSizeList(item_field::<T>(), N as i32)
+    }
+
+    fn to_array(values: &[Option<&Self>]) -> ArrayRef {
+        let items: Vec<_> = values
+            .iter()
+            .flat_map(|v| (0..N).map(move |i| v.map(|v| &v[i])))
+            .collect();
+        Arc::new(FixedSizeListArray::new(
+            item_field::<T>(),
+            N as i32,
+            T::to_array(&items),
+            nulls(values),
+        ))
+    }
+
+    fn from_array(array: &ArrayRef, column: &str) -> Result<Vec<Option<Self>>, ArrowError> {
+        let list = downcast::<FixedSizeListArray>(array, column)?;
+        if list.value_length() as usize != N {
+            return Err(column_error(column, format!("expected lists of {}", N)));
+        }
+        let mut items = required_items::<T>(list.values(), column)?;
+
+        (0..list.len())
+            .map(|i| {
+                let row = items.by_ref().take(N).collect::<Vec<_>>();
+                if list.is_null(i) {
+                    return Ok(None);
+                }
+
+                let row = row
+                    .into_iter()
+                    .map(|item| item.ok_or_else(|| column_error(column, "null item")))
+                    .collect::<Result<Vec<_>, _>>()?;
+                match row.try_into() {
+                    Ok(row) => Ok(Some(row)),
+                    Err(_) => Err(column_error(column, "list too short")),
+                }
+            })
+            .collect()
+    }
+}
+
+record_fields!(Coordinates { lat, long });
+record_fields!(Location { start, end });
+
+#[cfg(feature = "bus")]
+mod bus {
+    use super::*;
+    use crate::bus::bus_arrival::{ArrivalBusService, BusArrivalResp, NextBus};
+    use crate::bus::bus_routes::BusRoute;
+    use crate::bus::bus_services::{BusFreq, BusService};
+    use crate::bus::bus_stops::BusStop;
+    use crate::bus_enums::{BusCategory, BusFeature, BusLoad, BusType, Operator};
+
+    dict_fields!(Operator, BusType, BusLoad, BusFeature, BusCategory);
+
+    record_fields!(NextBus {
+        origin_code,
+        dest_code,
+        est_arrival,
+        lat,
+        long,
+        visit_no,
+        load,
+        feature,
+        bus_type,
+    });
+    record_fields!(ArrivalBusService {
+        service_no,
+        operator,
+        next_bus,
+    });
+    record_fields!(BusArrivalResp {
+        bus_stop_code,
+        services,
+    });
+    record_fields!(BusFreq { min, max });
+    record_fields!(BusService {
+        service_no,
+        operator,
+        no_direction,
+        category,
+        origin_code,
+        dest_code,
+        am_peak_freq,
+        am_offpeak_freq,
+        pm_peak_freq,
+        pm_offpeak_freq,
+        loop_desc,
+    });
+    record_fields!(BusRoute {
+        service_no,
+        operator,
+        direction,
+        stop_seq,
+        bus_stop_code,
+        dist,
+        wd_first,
+        wd_last,
+        sat_first,
+        sat_last,
+        sun_first,
+        sun_last,
+    });
+    record_fields!(BusStop {
+        bus_stop_code,
+        road_name,
+        desc,
+        lat,
+        long,
+    });
+}
+
+#[cfg(feature = "traffic")]
+mod traffic {
+    use super::*;
+    use crate::traffic::bike_parking::{BikeParking, RackType};
+    use crate::traffic::carpark_avail::{Agency, CarPark, LotType};
+    use crate::traffic::erp_rates::{DayType, ErpRate, VehicleType, ZoneId};
+    use crate::traffic::est_travel_time::{EstTravelTime, Highway, HighwayDirection};
+    use crate::traffic::faulty_traffic_lights::{FaultyTrafficLight, TechnicalAlarmType};
+    use crate::traffic::road::RoadDetails;
+    use crate::traffic::traffic_images::TrafficImage;
+    use crate::traffic::traffic_incidents::{IncidentType, TrafficIncident};
+    use crate::traffic::traffic_speed_bands::{RoadCategory, TrafficSpeedBand};
+    use crate::traffic::vms_emas::VMS;
+
+    dict_fields!(
+        VehicleType,
+        DayType,
+        ZoneId,
+        LotType,
+        Agency,
+        Highway,
+        IncidentType,
+        RoadCategory,
+        RackType,
+    );
+    int_fields!(HighwayDirection, TechnicalAlarmType);
+
+    record_fields!(ErpRate {
+        vehicle_type,
+        day_type,
+        start_time,
+        end_time,
+        zone_id,
+        charge_amt,
+        effective_date,
+    });
+    record_fields!(CarPark {
+        carpark_id,
+        area,
+        dev,
+        coords,
+        avail_lots,
+        lot_type,
+        agency,
+    });
+    record_fields!(EstTravelTime {
+        name,
+        direction,
+        far_end_pt,
+        start_pt,
+        end_pt,
+        est_travel_time,
+    });
+    record_fields!(FaultyTrafficLight {
+        alarm_id,
+        node_id,
+        technical_alarm_type,
+        start_date,
+        end_date,
+        message,
+    });
+    record_fields!(RoadDetails {
+        event_id,
+        start_date,
+        end_date,
+        service_dept,
+        road_name,
+        other,
+    });
+    record_fields!(TrafficImage {
+        camera_id,
+        lat,
+        long,
+        image_link,
+    });
+    record_fields!(TrafficIncident {
+        incident_type,
+        lat,
+        long,
+        msg,
+    });
+    record_fields!(TrafficSpeedBand {
+        link_id,
+        road_name,
+        road_category,
+        speed_band,
+        min_speed,
+        max_speed,
+        coord_start_end,
+    });
+    record_fields!(VMS {
+        equipment_id,
+        lat,
+        long,
+        msg,
+    });
+    record_fields!(BikeParking {
+        desc,
+        lat,
+        long,
+        rack_type,
+        rack_count,
+        shelter_indicator,
+    });
+}
+
+#[cfg(feature = "taxi")]
+mod taxi {
+    use super::*;
+    use crate::taxi::taxi_avail::InternalCoordinates;
+    use crate::taxi::taxi_stands::{TaxiStand, TaxiStandOwner, TaxiStandType};
+
+    dict_fields!(TaxiStandOwner, TaxiStandType);
+
+    record_fields!(InternalCoordinates { lat, long });
+    record_fields!(TaxiStand {
+        taxi_code,
+        lat,
+        long,
+        is_barrier_free,
+        owner,
+        stand_type,
+        name,
+    });
+}
+
+#[cfg(feature = "train")]
+mod train {
+    use super::*;
+    use crate::train::train_service_alert::{
+        AffectedSegment, MrtLine, StringWrap, TrainServiceAlert, TrainServiceAlertMessage,
+        TrainStatus,
+    };
+
+    dict_fields!(MrtLine);
+    int_fields!(TrainStatus);
+
+    impl ArrowField for StringWrap {
+        fn data_type() -> DataType {
+            DataType::Utf8
+        }
+
+        fn to_array(values: &[Option<&Self>]) -> ArrayRef {
+            Arc::new(
+                values
+                    .iter()
+                    .map(|v| v.map(|v| v.as_str()))
+                    .collect::<StringArray>(),
+            )
+        }
+
+        fn from_array(array: &ArrayRef, column: &str) -> Result<Vec<Option<Self>>, ArrowError> {
+            downcast::<StringArray>(array, column)?
+                .iter()
+                .map(|v| {
+                    v.map(|s| {
+                        s.parse()
+                            .map_err(|e| column_error(column, format!("{}", e)))
+                    })
+                    .transpose()
+                })
+                .collect()
+        }
+    }
+
+    record_fields!(AffectedSegment {
+        line,
+        direction,
+        stations,
+        free_public_bus,
+        free_mrt_shuttle,
+        mrt_shuttle_dir,
+    });
+    record_fields!(TrainServiceAlertMessage {
+        content,
+        created_date,
+    });
+    record_fields!(TrainServiceAlert {
+        status,
+        affected_segments,
+        message,
+    });
+}
+
+#[cfg(feature = "crowd")]
+mod crowd {
+    use super::*;
+    use crate::crowd::passenger_vol::{
+        DayType, Link, OdPassengerVolRecord, PassengerVolRecord, PtType, YearMonth,
+    };
+
+    dict_fields!(DayType, PtType);
+    str_fields!(YearMonth);
+
+    record_fields!(Link { link });
+    record_fields!(PassengerVolRecord {
+        year_month,
+        day_type,
+        hour,
+        pt_type,
+        pt_code,
+        tap_in,
+        tap_out,
+    });
+    record_fields!(OdPassengerVolRecord {
+        year_month,
+        day_type,
+        hour,
+        pt_type,
+        origin_pt_code,
+        destination_pt_code,
+        total_trips,
+    });
+}
@@ -9,12 +9,17 @@ pub mod prelude {
     };
 }
 
+pub mod network;
+pub mod planner;
+pub mod timetable;
+
 pub mod bus_arrival {
-    use lta_utils_commons::chrono::prelude::*;
     use serde::{Deserialize, Serialize};
 
     use crate::bus_enums::{BusFeature, BusLoad, BusType, Operator};
-    use lta_utils_commons::de::{from_str, treat_error_as_none};
+    use lta_utils_commons::de::{from_str, treat_empty_as_none};
+    use lta_utils_commons::serde_date::rfc3339;
+    use lta_utils_commons::sg_time::SgDateTime;
 
     pub const URL: &str = "http://datamall2.mytransport.sg/ltaodataservice/BusArrivalv2";
 
@@ -25,13 +30,13 @@ pub mod bus_arrival {
 
         pub operator: Operator,
 
-        #[serde(deserialize_with = "treat_error_as_none")]
+        #[serde(deserialize_with = "treat_empty_as_none")]
         pub next_bus: Option<NextBus>,
 
-        #[serde(deserialize_with = "treat_error_as_none")]
+        #[serde(deserialize_with = "treat_empty_as_none")]
         pub next_bus_2: Option<NextBus>,
 
-        #[serde(deserialize_with = "treat_error_as_none")]
+        #[serde(deserialize_with = "treat_empty_as_none")]
         pub next_bus_3: Option<NextBus>,
     }
 
@@ -64,8 +69,8 @@ pub mod bus_arrival {
         #[serde(deserialize_with = "from_str", alias = "DestinationCode")]
         pub dest_code: u32,
 
-        #[serde(alias = "EstimatedArrival")]
-        pub est_arrival: DateTime<FixedOffset>,
+        #[serde(alias = "EstimatedArrival", with = "rfc3339")]
+        pub est_arrival: SgDateTime,
 
         #[serde(deserialize_with = "from_str", alias = "Latitude")]
         pub lat: f64,
@@ -107,13 +112,20 @@ pub mod bus_arrival {
             }
         }
     }
+
+    impl_page!(
+        RawBusArrivalResp,
+        RawArrivalBusService,
+        services,
+        "Services"
+    );
 }
 
 pub mod bus_services {
     use crate::bus_enums::{BusCategory, Operator};
+    use lta_utils_commons::chrono::NaiveTime;
     use lta_utils_commons::de::from_str;
-    use lta_utils_commons::regex::BUS_FREQ_RE;
-    use serde::{Deserialize, Deserializer, Serialize};
+    use serde::{de, Deserialize, Deserializer, Serialize};
     pub const URL: &str = "http://datamall2.mytransport.sg/ltaodataservice/BusServices";
 
     /// Both min and max are in terms of minutes
@@ -152,6 +164,64 @@ pub mod bus_services {
         }
     }
 
+    impl BusFreq {
+        /// Typical minutes between buses, the middle of `min` and `max`.
+        /// `None` if there is no timing
+        pub fn headway_mins(&self) -> Option<u32> {
+            match (self.min, self.max) {
+                (Some(min), Some(max)) if max >= min => Some((min + max) / 2),
+                (Some(min), _) => Some(min),
+                _ => None,
+            }
+        }
+    }
+
+    /// Periods of the day with their own `BusFreq`
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum FreqBand {
+        /// 06:30 to 08:30
+        AmPeak,
+
+        /// 08:31 to 16:59
+        AmOffpeak,
+
+        /// 17:00 to 19:00
+        PmPeak,
+
+        /// 19:01 to 06:29 the next morning
+        PmOffpeak,
+    }
+
+    impl FreqBand {
+        pub const ALL: [FreqBand; 4] = [
+            FreqBand::AmPeak,
+            FreqBand::AmOffpeak,
+            FreqBand::PmPeak,
+            FreqBand::PmOffpeak,
+        ];
+
+        /// Band in force at `time`
+        pub fn at(time: NaiveTime) -> FreqBand {
+            FreqBand::ALL
+                .iter()
+                .rev()
+                .find(|b| b.start() <= time)
+                .copied()
+                .unwrap_or(FreqBand::PmOffpeak)
+        }
+
+        /// When the band starts, it lasts until the next band starts
+        pub fn start(&self) -> NaiveTime {
+            let (h, m) = match self {
+                FreqBand::AmPeak => (6, 30),
+                FreqBand::AmOffpeak => (8, 31),
+                FreqBand::PmPeak => (17, 0),
+                FreqBand::PmOffpeak => (19, 1),
+            };
+            NaiveTime::from_hms_opt(h, m, 0).expect("band starts are valid times")
+        }
+    }
+
     #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
     #[serde(rename_all(deserialize = "PascalCase"))]
     pub struct BusService {
@@ -185,15 +255,47 @@ pub mod bus_services {
         pub loop_desc: Option<String>,
     }
 
+    impl BusService {
+        pub fn freq(&self, band: FreqBand) -> &BusFreq {
+            match band {
+                FreqBand::AmPeak => &self.am_peak_freq,
+                FreqBand::AmOffpeak => &self.am_offpeak_freq,
+                FreqBand::PmPeak => &self.pm_peak_freq,
+                FreqBand::PmOffpeak => &self.pm_offpeak_freq,
+            }
+        }
+
+        /// Frequency at a time of day
+        pub fn freq_at(&self, time: NaiveTime) -> &BusFreq {
+            self.freq(FreqBand::at(time))
+        }
+    }
+
     fn from_str_to_bus_freq<'de, D>(deserializer: D) -> Result<BusFreq, D::Error>
     where
         D: Deserializer<'de>,
     {
         let s: String = String::deserialize(deserializer)?;
 
-        let caps = BUS_FREQ_RE.captures(&s).unwrap();
-        let min: u32 = caps.get(1).map_or(0, |m| m.as_str().parse().unwrap());
-        let max: u32 = caps.get(2).map_or(0, |m| m.as_str().parse().unwrap());
+        let invalid = || de::Error::custom(format!("invalid bus frequency `{}`", s));
+
+        // `min-max`, `min` or `-`, where each number has up to 3 digits
+        let parse_part = |part: &str| -> Result<u32, D::Error> {
+            if part.is_empty() {
+                Ok(0)
+            } else if part.len() <= 3 && part.bytes().all(|b| b.is_ascii_digit()) {
+                part.parse().map_err(|_| invalid())
+            } else {
+                Err(invalid())
+            }
+        };
+
+        let (min, max) = match s.find('-') {
+            Some(i) => (&s[..i], &s[i + 1..]),
+            None => (s.as_str(), ""),
+        };
+        let min = parse_part(min)?;
+        let max = parse_part(max)?;
 
         let bus_freq = if min == 0 && max == 0 {
             BusFreq::no_timing()
@@ -216,6 +318,8 @@ pub mod bus_services {
             self.value
         }
     }
+
+    impl_page!(BusServiceResp, BusService);
 }
 pub mod bus_routes {
     use lta_utils_commons::chrono::prelude::*;
@@ -224,6 +328,7 @@ pub mod bus_routes {
     use crate::bus_enums::Operator;
     use lta_utils_commons::de::from_str;
     use lta_utils_commons::serde_date::str_time_option::{de_str_time_opt_br, ser_str_time_opt};
+    use lta_utils_commons::sg_time::{self, SgDateTime};
 
     pub const URL: &str = "http://datamall2.mytransport.sg/ltaodataservice/BusRoutes";
 
@@ -288,6 +393,66 @@ pub mod bus_routes {
         pub sun_last: Option<NaiveTime>,
     }
 
+    /// Which first and last bus timings apply
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+    pub enum ServiceDay {
+        Weekday,
+        Saturday,
+
+        /// Sundays and public holidays
+        SundayHoliday,
+    }
+
+    impl ServiceDay {
+        pub const ALL: [ServiceDay; 3] = [
+            ServiceDay::Weekday,
+            ServiceDay::Saturday,
+            ServiceDay::SundayHoliday,
+        ];
+
+        /// Service day of `date` by day of week, public holidays are not detected here
+        pub fn of(date: NaiveDate) -> ServiceDay {
+            match date.weekday() {
+                Weekday::Sat => ServiceDay::Saturday,
+                Weekday::Sun => ServiceDay::SundayHoliday,
+                _ => ServiceDay::Weekday,
+            }
+        }
+    }
+
+    impl BusRoute {
+        /// First and last bus timings for a service day
+        pub fn timings(&self, day: ServiceDay) -> (Option<NaiveTime>, Option<NaiveTime>) {
+            match day {
+                ServiceDay::Weekday => (self.wd_first, self.wd_last),
+                ServiceDay::Saturday => (self.sat_first, self.sat_last),
+                ServiceDay::SundayHoliday => (self.sun_first, self.sun_last),
+            }
+        }
+
+        /// First and last bus timings for the day of week of `date`.
+        ///
+        /// Public holidays follow Sunday timings but are not detected here
+        pub fn timings_for(&self, date: NaiveDate) -> (Option<NaiveTime>, Option<NaiveTime>) {
+            self.timings(ServiceDay::of(date))
+        }
+
+        /// First bus at this stop on `date`
+        pub fn first_bus_on(&self, date: NaiveDate) -> Option<SgDateTime> {
+            self.timings_for(date).0.map(|t| sg_time::at(date, t))
+        }
+
+        /// Last bus at this stop for the service day starting on `date`.
+        /// Last buses after midnight are on the following calendar day
+        pub fn last_bus_on(&self, date: NaiveDate) -> Option<SgDateTime> {
+            match self.timings_for(date) {
+                (Some(first), Some(last)) => Some(sg_time::on_service_date(date, last, first)),
+                (None, Some(last)) => Some(sg_time::at(date, last)),
+                _ => None,
+            }
+        }
+    }
+
     #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
     pub struct BusRouteResp {
         pub value: Vec<BusRoute>,
@@ -298,6 +463,8 @@ pub mod bus_routes {
             self.value
         }
     }
+
+    impl_page!(BusRouteResp, BusRoute);
 }
 pub mod bus_stops {
     use serde::{Deserialize, Serialize};
@@ -334,4 +501,14 @@ pub mod bus_stops {
             self.value
         }
     }
+
+    impl_page!(BusStopsResp, BusStop);
+    impl_has_location!(BusStop);
+
+    #[cfg(feature = "geo-types")]
+    impl From<BusStopsResp> for geo_types::MultiPoint<f64> {
+        fn from(resp: BusStopsResp) -> Self {
+            lta_utils_commons::geo::to_multi_point(&resp.value)
+        }
+    }
 }
@@ -0,0 +1,263 @@
+//! The static bus datasets as one network
+//!
+//! `BusNetwork` joins `BusStop`, `BusService` and `BusRoute` records so that
+//! routes can be walked stop by stop and stops can be looked up by service.
+//!
+//! ## Example
+//! ```rust
+//! use lta_models::bus::bus_routes::BusRouteResp;
+//! use lta_models::bus::network::BusNetwork;
+//!
+//! let routes: BusRouteResp =
+//!     serde_json::from_str(include_str!("../../../dumped_data/bus_route.json")).unwrap();
+//! let network = BusNetwork::new(vec![], vec![], routes.value);
+//!
+//! let route = network.route("10", 1).unwrap();
+//! assert_eq!(route[0].bus_stop_code, 75009);
+//! assert!(network.services_at(75009).iter().any(|s| s.service_no == "10"));
+//! ```
+
+use crate::bus::bus_routes::BusRoute;
+use crate::bus::bus_services::BusService;
+use crate::bus::bus_stops::BusStop;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::{self, Display, Formatter};
+
+/// A bus service in one direction
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ServiceDir {
+    pub service_no: String,
+    pub direction: u32,
+}
+
+impl ServiceDir {
+    pub fn new<S: Into<String>>(service_no: S, direction: u32) -> Self {
+        ServiceDir {
+            service_no: service_no.into(),
+            direction,
+        }
+    }
+}
+
+impl Display for ServiceDir {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.service_no, self.direction)
+    }
+}
+
+/// Travel from one stop to the next stop of a service
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hop {
+    pub service: ServiceDir,
+    pub from: u32,
+    pub to: u32,
+
+    /// Distance along the route in km
+    pub dist: f64,
+}
+
+/// Inconsistency between the datasets found by `BusNetwork::validate`
+#[derive(Debug, Clone, PartialEq)]
+pub enum NetworkIssue {
+    /// A route stops at a bus stop that is not in `BusStop`
+    MissingStop {
+        service: ServiceDir,
+        stop_seq: u32,
+        bus_stop_code: u32,
+    },
+
+    /// A route has no matching `BusService`
+    MissingService(ServiceDir),
+}
+
+impl Display for NetworkIssue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            NetworkIssue::MissingStop {
+                service,
+                stop_seq,
+                bus_stop_code,
+            } => write!(
+                f,
+                "service {} stop {} is bus stop {:05} which does not exist",
+                service, stop_seq, bus_stop_code
+            ),
+            NetworkIssue::MissingService(service) => {
+                write!(f, "service {} has a route but no service record", service)
+            }
+        }
+    }
+}
+
+/// Bus stops, services and routes joined together
+#[derive(Debug, Clone, Default)]
+pub struct BusNetwork {
+    stops: HashMap<u32, BusStop>,
+    services: HashMap<ServiceDir, BusService>,
+
+    /// Sorted by `stop_seq`
+    routes: HashMap<ServiceDir, Vec<BusRoute>>,
+
+    /// Services stopping at each stop, sorted
+    stop_services: HashMap<u32, Vec<ServiceDir>>,
+}
+
+impl BusNetwork {
+    pub fn new(stops: Vec<BusStop>, services: Vec<BusService>, routes: Vec<BusRoute>) -> Self {
+        let stops = stops.into_iter().map(|s| (s.bus_stop_code, s)).collect();
+
+        let services = services
+            .into_iter()
+            .map(|s| (ServiceDir::new(s.service_no.clone(), s.no_direction), s))
+            .collect();
+
+        let mut by_service: HashMap<ServiceDir, Vec<BusRoute>> = HashMap::new();
+        for r in routes {
+            by_service
+                .entry(ServiceDir::new(r.service_no.clone(), r.direction))
+                .or_default()
+                .push(r);
+        }
+
+        let mut stop_services: HashMap<u32, Vec<ServiceDir>> = HashMap::new();
+        for (service, route) in by_service.iter_mut() {
+            route.sort_by_key(|r| r.stop_seq);
+            for r in route.iter() {
+                stop_services
+                    .entry(r.bus_stop_code)
+                    .or_default()
+                    .push(service.clone());
+            }
+        }
+
+        for services in stop_services.values_mut() {
+            services.sort();
+            // loop services stop twice at the same stop
+            services.dedup();
+        }
+
+        BusNetwork {
+            stops,
+            services,
+            routes: by_service,
+            stop_services,
+        }
+    }
+
+    pub fn stop(&self, bus_stop_code: u32) -> Option<&BusStop> {
+        self.stops.get(&bus_stop_code)
+    }
+
+    pub fn stops(&self) -> impl Iterator<Item = &BusStop> {
+        self.stops.values()
+    }
+
+    pub fn services(&self) -> impl Iterator<Item = &BusService> {
+        self.services.values()
+    }
+
+    pub fn service(&self, service_no: &str, direction: u32) -> Option<&BusService> {
+        self.services.get(&ServiceDir::new(service_no, direction))
+    }
+
+    /// Every service and direction with a route, sorted
+    pub fn service_dirs(&self) -> Vec<&ServiceDir> {
+        let mut dirs: Vec<_> = self.routes.keys().collect();
+        dirs.sort();
+        dirs
+    }
+
+    /// Stops of a service in one direction, in order of `stop_seq`
+    pub fn route(&self, service_no: &str, direction: u32) -> Option<&[BusRoute]> {
+        self.routes
+            .get(&ServiceDir::new(service_no, direction))
+            .map(Vec::as_slice)
+    }
+
+    /// Bus stop codes of a service in one direction, in order
+    pub fn stop_codes(&self, service_no: &str, direction: u32) -> Vec<u32> {
+        self.route(service_no, direction)
+            .map(|r| r.iter().map(|s| s.bus_stop_code).collect())
+            .unwrap_or_default()
+    }
+
+    /// Services stopping at a bus stop
+    pub fn services_at(&self, bus_stop_code: u32) -> &[ServiceDir] {
+        self.stop_services
+            .get(&bus_stop_code)
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// Distance in km from `from` to `to` along a service.
+    ///
+    /// `None` if the service does not stop at `from` and then at `to`.
+    /// For loop services the first stop at `from` is used.
+    pub fn distance(&self, service_no: &str, direction: u32, from: u32, to: u32) -> Option<f64> {
+        let route = self.route(service_no, direction)?;
+        let start = route.iter().position(|r| r.bus_stop_code == from)?;
+        let end = route[start + 1..].iter().find(|r| r.bus_stop_code == to)?;
+
+        Some(end.dist - route[start].dist)
+    }
+
+    /// Hops to the next stop of every service at a bus stop
+    pub fn next_stops(&self, bus_stop_code: u32) -> Vec<Hop> {
+        let mut hops = Vec::new();
+        for service in self.services_at(bus_stop_code) {
+            let route = &self.routes[service];
+            for w in route.windows(2) {
+                if w[0].bus_stop_code == bus_stop_code {
+                    hops.push(Hop {
+                        service: service.clone(),
+                        from: bus_stop_code,
+                        to: w[1].bus_stop_code,
+                        dist: w[1].dist - w[0].dist,
+                    });
+                }
+            }
+        }
+
+        hops
+    }
+
+    /// Stops directly before or after a bus stop on any service
+    pub fn neighbours(&self, bus_stop_code: u32) -> BTreeSet<u32> {
+        let mut neighbours = BTreeSet::new();
+        for service in self.services_at(bus_stop_code) {
+            let route = &self.routes[service];
+            for w in route.windows(2) {
+                if w[0].bus_stop_code == bus_stop_code {
+                    neighbours.insert(w[1].bus_stop_code);
+                }
+                if w[1].bus_stop_code == bus_stop_code {
+                    neighbours.insert(w[0].bus_stop_code);
+                }
+            }
+        }
+
+        neighbours.remove(&bus_stop_code);
+        neighbours
+    }
+
+    /// Checks that every route stop is a known `BusStop` and every route has a `BusService`
+    pub fn validate(&self) -> Vec<NetworkIssue> {
+        let mut issues = Vec::new();
+        for service in self.service_dirs() {
+            if !self.services.contains_key(service) {
+                issues.push(NetworkIssue::MissingService(service.clone()));
+            }
+
+            for r in &self.routes[service] {
+                if !self.stops.contains_key(&r.bus_stop_code) {
+                    issues.push(NetworkIssue::MissingStop {
+                        service: service.clone(),
+                        stop_seq: r.stop_seq,
+                        bus_stop_code: r.bus_stop_code,
+                    });
+                }
+            }
+        }
+
+        issues
+    }
+}
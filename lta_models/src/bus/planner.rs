@@ -0,0 +1,589 @@
+//! Journey planning over a `BusNetwork`, fully offline
+//!
+//! Journeys are found round by round, one more bus per round, so the result is
+//! the fastest itinerary for each number of transfers. Ranking is by arrival
+//! time, then by number of transfers.
+//!
+//! There are no trip timetables in the API, so times are estimated:
+//! - waiting is half the scheduled headway of the `BusService` for the time of day,
+//!   or until the first bus at the stop, and no bus is boarded after the last bus
+//! - time on the bus comes from the difference in first bus times between stops,
+//!   falling back to the route distance at `bus_speed`
+//! - walking is the straight line distance at `walk_speed`, to stops within `max_walk`
+//!
+//! ## Example
+//! ```rust
+//! use lta_models::bus::bus_routes::BusRouteResp;
+//! use lta_models::bus::network::BusNetwork;
+//! use lta_models::bus::planner::{JourneyPlanner, Place};
+//! use lta_utils_commons::chrono::{NaiveDate, NaiveTime};
+//! use lta_utils_commons::sg_time;
+//!
+//! let routes: BusRouteResp =
+//!     serde_json::from_str(include_str!("../../../dumped_data/bus_route.json")).unwrap();
+//! let network = BusNetwork::new(vec![], vec![], routes.value);
+//! let planner = JourneyPlanner::new(&network);
+//!
+//! let date = NaiveDate::from_ymd_opt(2019, 11, 19).unwrap();
+//! let depart = sg_time::at(date, NaiveTime::from_hms_opt(9, 0, 0).unwrap());
+//! let itineraries = planner.plan(Place::Stop(75009), Place::Stop(96289), depart);
+//!
+//! let best = &itineraries[0];
+//! assert_eq!(best.transfers(), 0);
+//! assert!(best.arrive() > depart);
+//! ```
+
+use crate::bus::bus_routes::BusRoute;
+use crate::bus::bus_stops::BusStop;
+use crate::bus::network::{BusNetwork, ServiceDir};
+use crate::bus::timetable::Timetable;
+use crate::spatial::SpatialIndex;
+use lta_utils_commons::chrono::{Duration, NaiveDate};
+use lta_utils_commons::geo::HasLocation;
+use lta_utils_commons::sg_time::SgDateTime;
+use lta_utils_commons::Coordinates;
+use std::collections::HashMap;
+
+/// Start or end of a journey
+#[derive(Debug, Clone, PartialEq)]
+pub enum Place {
+    Stop(u32),
+    Coords(Coordinates),
+}
+
+/// Part of an itinerary
+#[derive(Debug, Clone, PartialEq)]
+pub enum Leg {
+    Walk {
+        from: Place,
+        to: Place,
+        dist_m: f64,
+        depart: SgDateTime,
+        arrive: SgDateTime,
+    },
+    Bus {
+        service: ServiceDir,
+        board: u32,
+        alight: u32,
+
+        /// Number of stops travelled
+        stops: usize,
+
+        /// Expected wait at `board` before `depart`
+        wait: Duration,
+        depart: SgDateTime,
+        arrive: SgDateTime,
+    },
+}
+
+impl Leg {
+    /// When the leg starts, for bus legs this is after waiting
+    pub fn depart(&self) -> SgDateTime {
+        match self {
+            Leg::Walk { depart, .. } | Leg::Bus { depart, .. } => *depart,
+        }
+    }
+
+    pub fn arrive(&self) -> SgDateTime {
+        match self {
+            Leg::Walk { arrive, .. } | Leg::Bus { arrive, .. } => *arrive,
+        }
+    }
+
+    pub fn is_bus(&self) -> bool {
+        matches!(self, Leg::Bus { .. })
+    }
+}
+
+/// A way to get from one place to another
+#[derive(Debug, Clone, PartialEq)]
+pub struct Itinerary {
+    pub depart: SgDateTime,
+    pub legs: Vec<Leg>,
+}
+
+impl Itinerary {
+    pub fn arrive(&self) -> SgDateTime {
+        self.legs.last().map_or(self.depart, Leg::arrive)
+    }
+
+    /// From the requested departure time to arrival, including waiting
+    pub fn duration(&self) -> Duration {
+        self.arrive() - self.depart
+    }
+
+    pub fn transfers(&self) -> usize {
+        self.legs
+            .iter()
+            .filter(|l| l.is_bus())
+            .count()
+            .saturating_sub(1)
+    }
+
+    /// Total walking distance in metres
+    pub fn walk_m(&self) -> f64 {
+        self.legs
+            .iter()
+            .map(|l| match l {
+                Leg::Walk { dist_m, .. } => *dist_m,
+                Leg::Bus { .. } => 0.0,
+            })
+            .sum()
+    }
+}
+
+/// How a stop was reached in a round
+#[derive(Debug, Clone)]
+enum Label {
+    Origin,
+    Access {
+        dist_m: f64,
+    },
+    Bus {
+        service: ServiceDir,
+        board: u32,
+
+        /// Round of the arrival at `board`
+        board_round: usize,
+        stops: usize,
+        wait: Duration,
+        depart: SgDateTime,
+    },
+    Walk {
+        from: u32,
+        dist_m: f64,
+    },
+}
+
+#[derive(Debug, Clone)]
+struct Arrival {
+    at: SgDateTime,
+    label: Label,
+}
+
+/// Bus currently ridden while scanning a route
+struct Boarded {
+    idx: usize,
+    round: usize,
+    wait: Duration,
+    depart: SgDateTime,
+}
+
+/// Plans journeys on a `BusNetwork`
+#[derive(Debug, Clone)]
+pub struct JourneyPlanner<'a> {
+    network: &'a BusNetwork,
+    stops: SpatialIndex<&'a BusStop>,
+    timetable: Timetable<'a>,
+    max_transfers: usize,
+    max_walk: f64,
+    walk_speed: f64,
+    bus_speed: f64,
+    default_headway: u32,
+}
+
+impl<'a> JourneyPlanner<'a> {
+    pub fn new(network: &'a BusNetwork) -> Self {
+        JourneyPlanner {
+            network,
+            stops: network.stops().collect(),
+            timetable: Timetable::new(network),
+            max_transfers: 3,
+            max_walk: 400.0,
+            walk_speed: 1.2,
+            bus_speed: 20.0,
+            default_headway: 15,
+        }
+    }
+
+    /// Most transfers in an itinerary, 3 by default
+    pub fn max_transfers(mut self, max_transfers: usize) -> Self {
+        self.max_transfers = max_transfers;
+        self
+    }
+
+    /// Longest walk in metres to, from or between stops, 400 by default
+    pub fn max_walk(mut self, metres: f64) -> Self {
+        self.max_walk = metres;
+        self
+    }
+
+    /// Walking speed in m/s, 1.2 by default
+    pub fn walk_speed(mut self, mps: f64) -> Self {
+        self.walk_speed = mps;
+        self
+    }
+
+    /// Bus speed in km/h where first bus times are missing, 20 by default
+    pub fn bus_speed(mut self, kmh: f64) -> Self {
+        self.bus_speed = kmh;
+        self
+    }
+
+    /// Headway in minutes for services without frequencies, 15 by default
+    pub fn default_headway(mut self, mins: u32) -> Self {
+        self.default_headway = mins;
+        self
+    }
+
+    /// Dates that run on Sunday timings
+    pub fn with_holidays<I>(mut self, holidays: I) -> Self
+    where
+        I: IntoIterator<Item = NaiveDate>,
+    {
+        self.timetable = self.timetable.with_holidays(holidays);
+        self
+    }
+
+    /// Itineraries from `from` leaving at `depart` to `to`, best first.
+    ///
+    /// Empty if `to` cannot be reached within `max_transfers`
+    pub fn plan(&self, from: Place, to: Place, depart: SgDateTime) -> Vec<Itinerary> {
+        let mut itineraries = Vec::new();
+        let mut best_dest: Option<SgDateTime> = None;
+
+        let from_coords = self.coords(&from);
+        let to_coords = self.coords(&to);
+
+        if let (Some(a), Some(b)) = (&from_coords, &to_coords) {
+            let dist_m = a.distance_m(b);
+            if from != to && dist_m <= self.max_walk {
+                let arrive = depart + self.walk_time(dist_m);
+                best_dest = Some(arrive);
+                itineraries.push(Itinerary {
+                    depart,
+                    legs: vec![Leg::Walk {
+                        from: from.clone(),
+                        to: to.clone(),
+                        dist_m,
+                        depart,
+                        arrive,
+                    }],
+                });
+            }
+        }
+
+        let egress: HashMap<u32, f64> = match (&to, &to_coords) {
+            (Place::Stop(code), _) => vec![(*code, 0.0)].into_iter().collect(),
+            (Place::Coords(_), Some(c)) => self.stops_near(c).into_iter().collect(),
+            _ => HashMap::new(),
+        };
+
+        let mut rounds: Vec<HashMap<u32, Arrival>> = Vec::new();
+        let mut best: HashMap<u32, (SgDateTime, usize)> = HashMap::new();
+
+        let mut access = HashMap::new();
+        if let Place::Stop(code) = from {
+            access.insert(
+                code,
+                Arrival {
+                    at: depart,
+                    label: Label::Origin,
+                },
+            );
+        }
+        if let Some(c) = &from_coords {
+            for (code, dist_m) in self.stops_near(c) {
+                if dist_m > 0.0 && !access.contains_key(&code) {
+                    access.insert(
+                        code,
+                        Arrival {
+                            at: depart + self.walk_time(dist_m),
+                            label: Label::Access { dist_m },
+                        },
+                    );
+                }
+            }
+        }
+        for (code, arrival) in &access {
+            best.insert(*code, (arrival.at, 0));
+        }
+        rounds.push(access);
+
+        for k in 1..=self.max_transfers + 1 {
+            let prev = best.clone();
+            let mut current: HashMap<u32, Arrival> = HashMap::new();
+
+            let mut routes: HashMap<&ServiceDir, usize> = HashMap::new();
+            for code in rounds[k - 1].keys() {
+                for service in self.network.services_at(*code) {
+                    let stops = self.route(service);
+                    if let Some(i) = stops.iter().position(|r| r.bus_stop_code == *code) {
+                        let first = routes.entry(service).or_insert(i);
+                        *first = (*first).min(i);
+                    }
+                }
+            }
+
+            for (service, start) in routes {
+                let stops = self.route(service);
+                let mut boarded: Option<Boarded> = None;
+
+                for i in start..stops.len() {
+                    let code = stops[i].bus_stop_code;
+
+                    let on_board = boarded
+                        .as_ref()
+                        .map(|b| (b, b.depart + self.ride_time(&stops[b.idx], &stops[i])));
+
+                    if let Some((b, at)) = on_board {
+                        let improves = best.get(&code).is_none_or(|(t, _)| at < *t)
+                            && best_dest.is_none_or(|t| at < t);
+                        if improves {
+                            best.insert(code, (at, k));
+                            current.insert(
+                                code,
+                                Arrival {
+                                    at,
+                                    label: Label::Bus {
+                                        service: service.clone(),
+                                        board: stops[b.idx].bus_stop_code,
+                                        board_round: b.round,
+                                        stops: i - b.idx,
+                                        wait: b.wait,
+                                        depart: b.depart,
+                                    },
+                                },
+                            );
+                        }
+                    }
+
+                    let (arrived, round) = match prev.get(&code) {
+                        Some(a) => *a,
+                        None => continue,
+                    };
+                    if let Some((bus_depart, wait)) =
+                        self.next_departure(service, &stops[i], arrived)
+                    {
+                        let better = on_board.is_none_or(|(_, at)| bus_depart < at);
+                        if better {
+                            boarded = Some(Boarded {
+                                idx: i,
+                                round,
+                                wait,
+                                depart: bus_depart,
+                            });
+                        }
+                    }
+                }
+            }
+
+            let mut by_bus: Vec<(SgDateTime, u32)> =
+                current.iter().map(|(c, a)| (a.at, *c)).collect();
+            by_bus.sort();
+            for (at, from_code) in by_bus {
+                // reached sooner on foot from another stop, walks are not chained
+                if current[&from_code].at != at {
+                    continue;
+                }
+
+                let coords = match self.network.stop(from_code) {
+                    Some(s) => Coordinates::new(s.lat, s.long),
+                    None => continue,
+                };
+
+                for (code, dist_m) in self.stops_near(&coords) {
+                    if code == from_code {
+                        continue;
+                    }
+
+                    let walked = at + self.walk_time(dist_m);
+                    let improves = best.get(&code).is_none_or(|(t, _)| walked < *t)
+                        && best_dest.is_none_or(|t| walked < t);
+                    if improves {
+                        best.insert(code, (walked, k));
+                        current.insert(
+                            code,
+                            Arrival {
+                                at: walked,
+                                label: Label::Walk {
+                                    from: from_code,
+                                    dist_m,
+                                },
+                            },
+                        );
+                    }
+                }
+            }
+
+            let reached = current
+                .iter()
+                .filter_map(|(code, a)| {
+                    egress
+                        .get(code)
+                        .map(|dist_m| (*code, *dist_m, a.at + self.walk_time(*dist_m)))
+                })
+                .min_by_key(|(code, _, at)| (*at, *code));
+
+            if current.is_empty() {
+                break;
+            }
+            rounds.push(current);
+
+            if let Some((code, dist_m, at)) = reached {
+                if best_dest.is_none_or(|t| at < t) {
+                    best_dest = Some(at);
+                    let mut legs = self.legs(&rounds, k, code, &from);
+                    if dist_m > 0.0 {
+                        let last = legs.last().map_or(depart, Leg::arrive);
+                        legs.push(Leg::Walk {
+                            from: Place::Stop(code),
+                            to: to.clone(),
+                            dist_m,
+                            depart: last,
+                            arrive: at,
+                        });
+                    }
+                    itineraries.push(Itinerary { depart, legs });
+                }
+            }
+        }
+
+        itineraries.sort_by_key(|it| (it.arrive(), it.transfers()));
+        itineraries
+    }
+
+    fn route(&self, service: &ServiceDir) -> &'a [BusRoute] {
+        self.network
+            .route(&service.service_no, service.direction)
+            .unwrap_or_default()
+    }
+
+    fn coords(&self, place: &Place) -> Option<Coordinates> {
+        match place {
+            Place::Stop(code) => self.network.stop(*code).and_then(|s| s.coordinates()),
+            Place::Coords(c) => Some(*c),
+        }
+    }
+
+    /// Stops within `max_walk` of `coords` and their distance in metres
+    fn stops_near(&self, coords: &Coordinates) -> Vec<(u32, f64)> {
+        self.stops
+            .within_radius(coords, self.max_walk)
+            .into_iter()
+            .map(|(stop, dist_m)| (stop.bus_stop_code, dist_m))
+            .collect()
+    }
+
+    fn walk_time(&self, dist_m: f64) -> Duration {
+        Duration::seconds((dist_m / self.walk_speed).round() as i64)
+    }
+
+    /// Time on the bus between two stops of a route
+    fn ride_time(&self, from: &BusRoute, to: &BusRoute) -> Duration {
+        if let (Some(a), Some(b)) = (from.wd_first, to.wd_first) {
+            let mut secs = (b - a).num_seconds();
+            if secs < 0 {
+                secs += 24 * 3600;
+            }
+            // first bus times are to the minute, don't trust them over long gaps
+            if secs > 0 && secs < 4 * 3600 {
+                return Duration::seconds(secs);
+            }
+        }
+
+        let km = (to.dist - from.dist).max(0.0);
+        Duration::seconds((km / self.bus_speed * 3600.0).round() as i64)
+    }
+
+    /// When the next bus of `service` leaves `stop` after arriving at `at`, and the wait
+    fn next_departure(
+        &self,
+        service: &ServiceDir,
+        stop: &BusRoute,
+        at: SgDateTime,
+    ) -> Option<(SgDateTime, Duration)> {
+        let today = at.date_naive();
+        let service_days = [today.pred_opt(), Some(today)];
+
+        service_days
+            .iter()
+            .flatten()
+            .filter_map(|&date| {
+                let (first_bus, last_bus) = self.timetable.window_at(stop, date)?;
+
+                if at > last_bus {
+                    None
+                } else if at <= first_bus {
+                    Some((first_bus, first_bus - at))
+                } else {
+                    let wait = self.expected_wait(service, at);
+                    Some(((at + wait).min(last_bus), wait.min(last_bus - at)))
+                }
+            })
+            .min_by_key(|(depart, _)| *depart)
+    }
+
+    /// Half the headway of the service for the time of day
+    fn expected_wait(&self, service: &ServiceDir, at: SgDateTime) -> Duration {
+        let mins = self
+            .network
+            .service(&service.service_no, service.direction)
+            .and_then(|s| s.freq_at(at.time()).headway_mins())
+            .unwrap_or(self.default_headway);
+
+        Duration::seconds(i64::from(mins) * 30)
+    }
+
+    /// Walks labels back from `code` in round `k`
+    fn legs(
+        &self,
+        rounds: &[HashMap<u32, Arrival>],
+        k: usize,
+        code: u32,
+        from: &Place,
+    ) -> Vec<Leg> {
+        let mut legs = Vec::new();
+        let (mut k, mut code) = (k, code);
+
+        loop {
+            let arrival = &rounds[k][&code];
+            match &arrival.label {
+                Label::Origin => break,
+                Label::Access { dist_m } => {
+                    legs.push(Leg::Walk {
+                        from: from.clone(),
+                        to: Place::Stop(code),
+                        dist_m: *dist_m,
+                        depart: arrival.at - self.walk_time(*dist_m),
+                        arrive: arrival.at,
+                    });
+                    break;
+                }
+                Label::Walk { from, dist_m } => {
+                    legs.push(Leg::Walk {
+                        from: Place::Stop(*from),
+                        to: Place::Stop(code),
+                        dist_m: *dist_m,
+                        depart: rounds[k][from].at,
+                        arrive: arrival.at,
+                    });
+                    code = *from;
+                }
+                Label::Bus {
+                    service,
+                    board,
+                    board_round,
+                    stops,
+                    wait,
+                    depart,
+                } => {
+                    legs.push(Leg::Bus {
+                        service: service.clone(),
+                        board: *board,
+                        alight: code,
+                        stops: *stops,
+                        wait: *wait,
+                        depart: *depart,
+                        arrive: arrival.at,
+                    });
+                    code = *board;
+                    k = *board_round;
+                }
+            }
+        }
+
+        legs.reverse();
+        legs
+    }
+}
@@ -0,0 +1,186 @@
+//! First and last buses and headways of a service at a stop
+//!
+//! Timings are picked by `ServiceDay`: weekdays, Saturdays, or Sundays and public
+//! holidays. Add public holidays with `Timetable::with_holidays`, e.g. from
+//! `calendar::Calendar::sg().dates()` with the `calendar` feature.
+//!
+//! A service day starts at the first bus and can end after midnight, so a bus at
+//! 00:20 on a Wednesday belongs to the Tuesday service when the last bus is at 00:30.
+//!
+//! ## Example
+//! ```rust
+//! use lta_models::bus::bus_routes::BusRouteResp;
+//! use lta_models::bus::network::BusNetwork;
+//! use lta_models::bus::timetable::Timetable;
+//! use lta_utils_commons::chrono::{NaiveDate, NaiveTime};
+//! use lta_utils_commons::sg_time;
+//!
+//! let routes: BusRouteResp =
+//!     serde_json::from_str(include_str!("../../../dumped_data/bus_route.json")).unwrap();
+//! let network = BusNetwork::new(vec![], vec![], routes.value);
+//! let timetable = Timetable::new(&network);
+//!
+//! let tuesday = NaiveDate::from_ymd_opt(2019, 11, 19).unwrap();
+//! let at = sg_time::at(tuesday, NaiveTime::from_hms_opt(4, 0, 0).unwrap());
+//! assert!(!timetable.is_running("10", 1, 75009, at));
+//! assert_eq!(
+//!     timetable.next_first_bus("10", 1, 75009, at),
+//!     Some(sg_time::at(tuesday, NaiveTime::from_hms_opt(5, 0, 0).unwrap()))
+//! );
+//! ```
+
+use crate::bus::bus_routes::{BusRoute, ServiceDay};
+use crate::bus::bus_services::{BusFreq, FreqBand};
+use crate::bus::network::BusNetwork;
+use lta_utils_commons::chrono::{Duration, NaiveDate};
+use lta_utils_commons::sg_time::{self, SgDateTime};
+use std::collections::HashSet;
+
+/// How far ahead `next_first_bus` looks, covers long weekends
+const LOOKAHEAD_DAYS: i64 = 7;
+
+/// Schedule queries on a `BusNetwork`
+#[derive(Debug, Clone)]
+pub struct Timetable<'a> {
+    network: &'a BusNetwork,
+    holidays: HashSet<NaiveDate>,
+}
+
+impl<'a> Timetable<'a> {
+    pub fn new(network: &'a BusNetwork) -> Self {
+        Timetable {
+            network,
+            holidays: HashSet::new(),
+        }
+    }
+
+    /// Dates that run on Sunday timings
+    pub fn with_holidays<I>(mut self, holidays: I) -> Self
+    where
+        I: IntoIterator<Item = NaiveDate>,
+    {
+        self.holidays.extend(holidays);
+        self
+    }
+
+    pub fn is_holiday(&self, date: NaiveDate) -> bool {
+        self.holidays.contains(&date)
+    }
+
+    /// Which timings apply on `date`
+    pub fn service_day(&self, date: NaiveDate) -> ServiceDay {
+        if self.is_holiday(date) {
+            ServiceDay::SundayHoliday
+        } else {
+            ServiceDay::of(date)
+        }
+    }
+
+    /// First and last bus at a route stop for the service day starting on `date`
+    pub fn window_at(&self, stop: &BusRoute, date: NaiveDate) -> Option<(SgDateTime, SgDateTime)> {
+        match stop.timings(self.service_day(date)) {
+            (Some(first), Some(last)) => Some((
+                sg_time::at(date, first),
+                sg_time::on_service_date(date, last, first),
+            )),
+            _ => None,
+        }
+    }
+
+    /// First and last bus of a service at a stop for the service day starting on `date`.
+    /// `None` if the service does not stop there or does not run that day
+    pub fn window(
+        &self,
+        service_no: &str,
+        direction: u32,
+        bus_stop_code: u32,
+        date: NaiveDate,
+    ) -> Option<(SgDateTime, SgDateTime)> {
+        let stop = self.route_stop(service_no, direction, bus_stop_code)?;
+        self.window_at(stop, date)
+    }
+
+    /// Whether buses are still due at the stop at `at`, between the first and last bus
+    pub fn is_running(
+        &self,
+        service_no: &str,
+        direction: u32,
+        bus_stop_code: u32,
+        at: SgDateTime,
+    ) -> bool {
+        let stop = match self.route_stop(service_no, direction, bus_stop_code) {
+            Some(stop) => stop,
+            None => return false,
+        };
+
+        let today = at.date_naive();
+        [today.pred_opt(), Some(today)]
+            .iter()
+            .flatten()
+            .filter_map(|&date| self.window_at(stop, date))
+            .any(|(first, last)| first <= at && at <= last)
+    }
+
+    /// Next first bus at the stop at or after `at`, looking up to a week ahead
+    pub fn next_first_bus(
+        &self,
+        service_no: &str,
+        direction: u32,
+        bus_stop_code: u32,
+        at: SgDateTime,
+    ) -> Option<SgDateTime> {
+        let stop = self.route_stop(service_no, direction, bus_stop_code)?;
+        let today = at.date_naive();
+
+        (0..=LOOKAHEAD_DAYS)
+            .map(|days| today + Duration::days(days))
+            .filter_map(|date| self.window_at(stop, date))
+            .map(|(first, _)| first)
+            .find(|first| *first >= at)
+    }
+
+    /// Last bus at the stop of the service day running at `at`, if any
+    pub fn last_bus(
+        &self,
+        service_no: &str,
+        direction: u32,
+        bus_stop_code: u32,
+        at: SgDateTime,
+    ) -> Option<SgDateTime> {
+        let stop = self.route_stop(service_no, direction, bus_stop_code)?;
+        let today = at.date_naive();
+
+        [today.pred_opt(), Some(today)]
+            .iter()
+            .flatten()
+            .filter_map(|&date| self.window_at(stop, date))
+            .find(|(first, last)| *first <= at && at <= *last)
+            .map(|(_, last)| last)
+    }
+
+    /// Frequency band in force at `at` and the service's frequency in it.
+    /// `None` if the service is unknown
+    pub fn headway(
+        &self,
+        service_no: &str,
+        direction: u32,
+        at: SgDateTime,
+    ) -> Option<(FreqBand, &'a BusFreq)> {
+        let service = self.network.service(service_no, direction)?;
+        let band = FreqBand::at(at.time());
+        Some((band, service.freq(band)))
+    }
+
+    /// First stop of the route at `bus_stop_code`
+    fn route_stop(
+        &self,
+        service_no: &str,
+        direction: u32,
+        bus_stop_code: u32,
+    ) -> Option<&'a BusRoute> {
+        self.network
+            .route(service_no, direction)?
+            .iter()
+            .find(|r| r.bus_stop_code == bus_stop_code)
+    }
+}
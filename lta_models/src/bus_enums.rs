@@ -1,111 +1,72 @@
 //! Enums for buses and operators
 //! Used for transforming stringly typed data from API to enums
-
-use serde::{Deserialize, Serialize};
-
-/// SBST -> SBS Transit
-///
-/// SMRT -> SMRT Corporation
-///
-/// TTS -> Tower Transit Singapore
-///
-/// GAS -> Go Ahead Singapore
-#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
-#[serde(rename_all(deserialize = "PascalCase"))]
-pub enum Operator {
-    SBST,
-    SMRT,
-    TTS,
-    GAS,
-
-    #[serde(other)]
-    Unknown
+//!
+//! Values not known to lta-rs are kept in the `Unknown` variant of each enum
+
+str_enum! {
+    /// SBST -> SBS Transit
+    ///
+    /// SMRT -> SMRT Corporation
+    ///
+    /// TTS -> Tower Transit Singapore
+    ///
+    /// GAS -> Go Ahead Singapore
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum Operator {
+        SBST,
+        SMRT,
+        TTS,
+        GAS,
+    }
 }
 
-/// SD -> Single Decker
-///
-/// DD -> Double Decker
-///
-/// BD -> Bendy
-#[derive(Debug, Clone, PartialEq, PartialOrd, Deserialize, Serialize)]
-#[serde(rename_all(deserialize = "PascalCase"))]
-pub enum BusType {
-    #[serde(alias = "SD")]
-    SingleDecker,
-
-    #[serde(alias = "DD")]
-    DoubleDecker,
-
-    #[serde(alias = "BD")]
-    Bendy,
-
-    #[serde(other)]
-    Unknown
+str_enum! {
+    /// SD -> Single Decker
+    ///
+    /// DD -> Double Decker
+    ///
+    /// BD -> Bendy
+    #[derive(Debug, Clone, PartialEq, PartialOrd)]
+    pub enum BusType {
+        SingleDecker | "SD",
+        DoubleDecker | "DD",
+        Bendy | "BD",
+    }
 }
 
-/// SEA -> Seats available
-///
-/// SDA -> Standing available
-///
-/// LSD -> Limited standing
-#[derive(Debug, Clone, PartialEq, PartialOrd, Deserialize, Serialize)]
-#[serde(rename_all(deserialize = "PascalCase"))]
-pub enum BusLoad {
-    #[serde(alias = "SEA")]
-    SeatsAvailable,
-
-    #[serde(alias = "SDA")]
-    StandingAvailable,
-
-    #[serde(alias = "LSD")]
-    LimitedStanding,
-
-    #[serde(other)]
-    Unknown
+str_enum! {
+    /// SEA -> Seats available
+    ///
+    /// SDA -> Standing available
+    ///
+    /// LSD -> Limited standing
+    #[derive(Debug, Clone, PartialEq, PartialOrd)]
+    pub enum BusLoad {
+        SeatsAvailable | "SEA",
+        StandingAvailable | "SDA",
+        LimitedStanding | "LSD",
+    }
 }
 
-#[derive(Debug, Clone, PartialEq, PartialOrd, Deserialize, Serialize)]
-#[serde(rename_all(deserialize = "PascalCase"))]
-pub enum BusFeature {
-    #[serde(alias = "WAB")]
-    WheelChairAccessible,
-
-    #[serde(other)]
-    Unknown
+str_enum! {
+    #[derive(Debug, Clone, PartialEq, PartialOrd)]
+    pub enum BusFeature {
+        WheelChairAccessible | "WAB",
+    }
 }
 
-#[derive(Debug, Clone, PartialEq, PartialOrd, Deserialize, Serialize)]
-pub enum BusCategory {
-    #[serde(alias = "EXPRESS")]
-    Express,
-
-    #[serde(alias = "FEEDER")]
-    Feeder,
-
-    #[serde(alias = "INDUSTRIAL")]
-    Industrial,
-
-    #[serde(alias = "TOWNLINK")]
-    TownLink,
-
-    #[serde(alias = "TRUNK")]
-    Trunk,
-
-    #[serde(alias = "2-TIER FLAT FARE")]
-    TwoTierFlatFare,
-
-    #[serde(alias = "FLATFEE")]
-    FlatFee,
-
-    #[serde(alias = "NIGHT SERVICE")]
-    NightService,
-
-    #[serde(alias = "CITY_LINK")]
-    CityLink,
-
-    #[serde(alias = "FLAT FARE $2.00")]
-    FlatFareTwoDollar,
-
-    #[serde(other)]
-    Unknown
+str_enum! {
+    #[derive(Debug, Clone, PartialEq, PartialOrd)]
+    pub enum BusCategory {
+        Express | "EXPRESS",
+        Feeder | "FEEDER",
+        Industrial | "INDUSTRIAL",
+        TownLink | "TOWNLINK",
+        Trunk | "TRUNK",
+        TwoTierFlatFare | "2-TIER FLAT FARE",
+        FlatFee | "FLATFEE",
+        NightService | "NIGHT SERVICE",
+        CityLink | "CITY_LINK",
+        FlatFareTwoDollar | "FLAT FARE $2.00",
+    }
 }
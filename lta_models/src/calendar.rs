@@ -0,0 +1,257 @@
+//! Singapore public holidays and the day type of a date
+//!
+//! Each dataset splits days differently: ERP charges weekdays and Saturdays,
+//! bus timings are for weekdays, Saturdays or Sundays and public holidays, and
+//! passenger volumes are for weekdays or weekends and holidays. `Calendar` knows
+//! the public holidays and classifies a date for each of them.
+//!
+//! `Calendar::sg` has the gazetted holidays from 2019 to 2026. A holiday falling on a
+//! Sunday adds a day in lieu on the next day that is not already a holiday. Newer
+//! years can be loaded from a file of `date,name` lines with `Calendar::update_from_file`.
+//!
+//! ## Example
+//! ```rust
+//! use lta_models::calendar::{Calendar, DayKind};
+//! use lta_utils_commons::chrono::NaiveDate;
+//!
+//! let calendar = Calendar::sg();
+//!
+//! // Vesak Day 2019 was on a Sunday
+//! let monday = NaiveDate::from_ymd_opt(2019, 5, 20).unwrap();
+//! assert_eq!(calendar.day_kind(monday), DayKind::Holiday);
+//! assert!(calendar.holiday(monday).unwrap().in_lieu);
+//! ```
+
+use lta_utils_commons::chrono::{Datelike, Duration, NaiveDate, Weekday};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{self, Display, Formatter};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+
+const SG_PUBLIC_HOLIDAYS: &str = include_str!("../data/sg_public_holidays.csv");
+
+/// A public holiday or a day in lieu of one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Holiday {
+    pub date: NaiveDate,
+    pub name: String,
+
+    /// Off in lieu of `name` falling on a Sunday
+    pub in_lieu: bool,
+}
+
+/// Kind of day, public holidays take precedence over the day of week
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DayKind {
+    Weekday,
+    Saturday,
+    Sunday,
+    Holiday,
+}
+
+/// Error reading a holiday file
+#[derive(Debug)]
+pub enum CalendarError {
+    Io(io::Error),
+
+    /// Line number starting from 1 and what is wrong with it
+    Parse(usize, String),
+}
+
+impl Display for CalendarError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            CalendarError::Io(e) => write!(f, "io error: {}", e),
+            CalendarError::Parse(line, msg) => write!(f, "line {}: {}", line, msg),
+        }
+    }
+}
+
+impl std::error::Error for CalendarError {}
+
+impl From<io::Error> for CalendarError {
+    fn from(e: io::Error) -> Self {
+        CalendarError::Io(e)
+    }
+}
+
+/// Public holidays, including days in lieu
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Calendar {
+    holidays: BTreeMap<NaiveDate, Holiday>,
+}
+
+impl Calendar {
+    /// Calendar without holidays, every date is classified by day of week
+    pub fn new() -> Self {
+        Calendar::default()
+    }
+
+    /// Singapore public holidays shipped with the crate
+    pub fn sg() -> Self {
+        Calendar::from_reader(SG_PUBLIC_HOLIDAYS.as_bytes())
+            .expect("embedded public holidays are valid")
+    }
+
+    /// Reads `date,name` lines with dates as `YYYY-MM-DD`.
+    /// Blank lines, lines starting with `#` and a `date,name` header are skipped
+    pub fn from_reader<R: BufRead>(reader: R) -> Result<Self, CalendarError> {
+        let mut calendar = Calendar::new();
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line == "date,name" {
+                continue;
+            }
+
+            let (date, name) = line
+                .split_once(',')
+                .ok_or_else(|| CalendarError::Parse(i + 1, "expected date,name".to_string()))?;
+            let date = NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d")
+                .map_err(|e| CalendarError::Parse(i + 1, format!("bad date {}: {}", date, e)))?;
+
+            calendar.insert(date, name.trim());
+        }
+
+        calendar.add_in_lieu();
+        Ok(calendar)
+    }
+
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, CalendarError> {
+        Calendar::from_reader(BufReader::new(File::open(path)?))
+    }
+
+    /// Replaces the holidays of every year in `other` with the ones in `other`,
+    /// other years are kept
+    pub fn update(&mut self, other: Calendar) {
+        let years = other.years();
+        self.holidays
+            .retain(|date, h| !h.in_lieu && !years.contains(&date.year()));
+        for (date, holiday) in other.holidays {
+            if !holiday.in_lieu {
+                self.holidays.insert(date, holiday);
+            }
+        }
+
+        self.add_in_lieu();
+    }
+
+    /// `update` with the holidays read from a file
+    pub fn update_from_file<P: AsRef<Path>>(&mut self, path: P) -> Result<(), CalendarError> {
+        self.update(Calendar::from_file(path)?);
+        Ok(())
+    }
+
+    /// Years with at least one holiday. Dates in other years are classified by day of week
+    pub fn years(&self) -> BTreeSet<i32> {
+        self.holidays.keys().map(|d| d.year()).collect()
+    }
+
+    pub fn holiday(&self, date: NaiveDate) -> Option<&Holiday> {
+        self.holidays.get(&date)
+    }
+
+    pub fn is_holiday(&self, date: NaiveDate) -> bool {
+        self.holidays.contains_key(&date)
+    }
+
+    /// Holidays in order of date
+    pub fn holidays(&self) -> impl Iterator<Item = &Holiday> {
+        self.holidays.values()
+    }
+
+    /// Holiday dates in order, for the `with_holidays` builders
+    pub fn dates(&self) -> impl Iterator<Item = NaiveDate> + '_ {
+        self.holidays.keys().copied()
+    }
+
+    pub fn day_kind(&self, date: NaiveDate) -> DayKind {
+        if self.is_holiday(date) {
+            return DayKind::Holiday;
+        }
+
+        match date.weekday() {
+            Weekday::Sat => DayKind::Saturday,
+            Weekday::Sun => DayKind::Sunday,
+            _ => DayKind::Weekday,
+        }
+    }
+
+    /// Which first and last bus timings apply on `date`
+    #[cfg(feature = "bus")]
+    pub fn service_day(&self, date: NaiveDate) -> crate::bus::bus_routes::ServiceDay {
+        use crate::bus::bus_routes::ServiceDay;
+
+        match self.day_kind(date) {
+            DayKind::Weekday => ServiceDay::Weekday,
+            DayKind::Saturday => ServiceDay::Saturday,
+            DayKind::Sunday | DayKind::Holiday => ServiceDay::SundayHoliday,
+        }
+    }
+
+    /// ERP day type of `date`, `None` on Sundays and public holidays when there is no charge
+    #[cfg(feature = "traffic")]
+    pub fn erp_day_type(&self, date: NaiveDate) -> Option<crate::traffic::erp_rates::DayType> {
+        use crate::traffic::erp_rates::DayType;
+
+        match self.day_kind(date) {
+            DayKind::Weekday => Some(DayType::Weekdays),
+            DayKind::Saturday => Some(DayType::Saturday),
+            DayKind::Sunday | DayKind::Holiday => None,
+        }
+    }
+
+    /// Passenger volume day type of `date`
+    #[cfg(feature = "crowd")]
+    pub fn volume_day_type(&self, date: NaiveDate) -> crate::crowd::passenger_vol::DayType {
+        use crate::crowd::passenger_vol::DayType;
+
+        match self.day_kind(date) {
+            DayKind::Weekday => DayType::Weekday,
+            _ => DayType::WeekendsHoliday,
+        }
+    }
+
+    fn insert(&mut self, date: NaiveDate, name: &str) {
+        self.holidays
+            .entry(date)
+            .and_modify(|h| {
+                h.name.push_str(", ");
+                h.name.push_str(name);
+            })
+            .or_insert_with(|| Holiday {
+                date,
+                name: name.to_string(),
+                in_lieu: false,
+            });
+    }
+
+    /// Recomputes days in lieu for holidays on Sundays
+    fn add_in_lieu(&mut self) {
+        self.holidays.retain(|_, h| !h.in_lieu);
+
+        let sundays: Vec<_> = self
+            .holidays
+            .values()
+            .filter(|h| h.date.weekday() == Weekday::Sun)
+            .cloned()
+            .collect();
+
+        for sunday in sundays {
+            let mut date = sunday.date + Duration::days(1);
+            while self.holidays.contains_key(&date) {
+                date += Duration::days(1);
+            }
+
+            self.holidays.insert(
+                date,
+                Holiday {
+                    date,
+                    name: sunday.name,
+                    in_lieu: true,
+                },
+            );
+        }
+    }
+}
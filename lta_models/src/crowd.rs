@@ -1,11 +1,20 @@
 //! Crowd structs and data structures
 
 pub mod prelude {
-    pub use crate::crowd::passenger_vol::{Link, PassengerVolRawResp, VolType};
+    pub use crate::crowd::passenger_vol::{
+        Link, OdPassengerVolRecord, PassengerVolRawResp, PassengerVolRecord, VolType, YearMonth,
+    };
 }
 
+pub mod analytics;
+
 pub mod passenger_vol {
-    use serde::{Deserialize, Serialize};
+    use lta_utils_commons::parse::ParseError;
+    use serde::de::{self, DeserializeOwned};
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::fmt::{self, Display, Formatter};
+    use std::io::{Read, Seek};
+    use std::str::FromStr;
 
     pub const URL_BY_BUS_STOPS: &str = "http://datamall2.mytransport.sg/ltaodataservice/PV/Bus";
 
@@ -35,9 +44,6 @@ pub mod passenger_vol {
         /// Returns tap in and tap out passenger volume by weekdays and
         /// weekends for individual train station
         OdTrain,
-
-        #[serde(other)]
-        Unknown
     }
 
     #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
@@ -62,4 +68,255 @@ pub mod passenger_vol {
             self.value.into_iter().map(|f| f.link).collect()
         }
     }
+
+    impl_page!(PassengerVolRawResp, Link);
+
+    /// Month of a passenger volume file, written as `2019-11`
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub struct YearMonth {
+        pub year: i32,
+        pub month: u32,
+    }
+
+    impl YearMonth {
+        /// Returns `None` if `month` is not within 1 to 12
+        pub fn new(year: i32, month: u32) -> Option<Self> {
+            if (1..=12).contains(&month) {
+                Some(YearMonth { year, month })
+            } else {
+                None
+            }
+        }
+
+        /// The month before, e.g. `2019-12` for `2020-01`
+        pub fn prev(self) -> Self {
+            match self.month {
+                1 => YearMonth {
+                    year: self.year - 1,
+                    month: 12,
+                },
+                m => YearMonth {
+                    year: self.year,
+                    month: m - 1,
+                },
+            }
+        }
+
+        /// Value of the `Date` query parameter, e.g. `201911`
+        pub fn to_query(self) -> String {
+            format!("{:04}{:02}", self.year, self.month)
+        }
+    }
+
+    impl Display for YearMonth {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "{:04}-{:02}", self.year, self.month)
+        }
+    }
+
+    impl FromStr for YearMonth {
+        type Err = String;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            let mut parts = s.trim().splitn(2, '-');
+            let year = parts.next().and_then(|y| y.parse().ok());
+            let month = parts.next().and_then(|m| m.parse().ok());
+
+            match (year, month) {
+                (Some(year), Some(month)) => YearMonth::new(year, month),
+                _ => None,
+            }
+            .ok_or_else(|| format!("invalid year and month `{}`", s))
+        }
+    }
+
+    impl Serialize for YearMonth {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            serializer.collect_str(self)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for YearMonth {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            let s = String::deserialize(deserializer)?;
+            s.parse().map_err(de::Error::custom)
+        }
+    }
+
+    str_enum! {
+        /// WEEKDAY -> Weekday
+        ///
+        /// WEEKENDS/HOLIDAY -> Weekends and public holidays
+        #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+        pub enum DayType {
+            Weekday | "WEEKDAY",
+            WeekendsHoliday | "WEEKENDS/HOLIDAY",
+        }
+    }
+
+    str_enum! {
+        #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+        pub enum PtType {
+            Bus | "BUS",
+            Train | "TRAIN",
+        }
+    }
+
+    /// Tap in and tap out volume at a bus stop or train station for an hour of the day,
+    /// from `VolType::BusStops` and `VolType::Train` files
+    #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+    #[serde(rename_all(deserialize = "SCREAMING_SNAKE_CASE"))]
+    pub struct PassengerVolRecord {
+        pub year_month: YearMonth,
+
+        pub day_type: DayType,
+
+        /// Hour of the day, 0 to 23
+        #[serde(alias = "TIME_PER_HOUR")]
+        pub hour: u8,
+
+        pub pt_type: PtType,
+
+        /// Bus stop code or train station code(s), e.g. `NS1-EW24`
+        pub pt_code: String,
+
+        #[serde(alias = "TOTAL_TAP_IN_VOLUME")]
+        pub tap_in: u64,
+
+        #[serde(alias = "TOTAL_TAP_OUT_VOLUME")]
+        pub tap_out: u64,
+    }
+
+    /// Number of trips between two bus stops or train stations for an hour of the day,
+    /// from `VolType::OdBusStop` and `VolType::OdTrain` files
+    #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+    #[serde(rename_all(deserialize = "SCREAMING_SNAKE_CASE"))]
+    pub struct OdPassengerVolRecord {
+        pub year_month: YearMonth,
+
+        pub day_type: DayType,
+
+        /// Hour of the day, 0 to 23
+        #[serde(alias = "TIME_PER_HOUR")]
+        pub hour: u8,
+
+        pub pt_type: PtType,
+
+        pub origin_pt_code: String,
+
+        pub destination_pt_code: String,
+
+        pub total_trips: u64,
+    }
+
+    /// Records read one at a time from a passenger volume CSV, see `read_csv`
+    pub struct VolRecords<R, T> {
+        inner: csv::DeserializeRecordsIntoIter<R, T>,
+    }
+
+    impl<R, T> Iterator for VolRecords<R, T>
+    where
+        R: Read,
+        T: DeserializeOwned,
+    {
+        type Item = Result<T, ParseError>;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            self.inner.next().map(|res| res.map_err(csv_error))
+        }
+    }
+
+    fn csv_error(err: csv::Error) -> ParseError {
+        let path = match err.position() {
+            Some(pos) => format!("line {}", pos.line()),
+            None => "csv".to_string(),
+        };
+
+        ParseError::new(path, err)
+    }
+
+    /// Reads a passenger volume CSV, with headers, without loading it into memory.
+    ///
+    /// Use `PassengerVolRecord` for `VolType::BusStops` and `VolType::Train` files,
+    /// `OdPassengerVolRecord` for the origin destination ones.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use lta_models::crowd::passenger_vol::{read_csv, DayType, PassengerVolRecord};
+    ///
+    /// let csv = "YEAR_MONTH,DAY_TYPE,TIME_PER_HOUR,PT_TYPE,PT_CODE,TOTAL_TAP_IN_VOLUME,TOTAL_TAP_OUT_VOLUME
+    /// 2019-11,WEEKDAY,17,BUS,45379,48,29";
+    ///
+    /// let records = read_csv::<_, PassengerVolRecord>(csv.as_bytes())
+    ///     .collect::<Result<Vec<_>, _>>()
+    ///     .unwrap();
+    /// assert_eq!(records[0].day_type, DayType::Weekday);
+    /// assert_eq!(records[0].tap_in, 48);
+    /// ```
+    pub fn read_csv<R, T>(reader: R) -> VolRecords<R, T>
+    where
+        R: Read,
+        T: DeserializeOwned,
+    {
+        let inner = csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_reader(reader)
+            .into_deserialize();
+
+        VolRecords { inner }
+    }
+
+    /// Zip archive of a passenger volume file as downloaded from the link
+    pub struct VolZip<R> {
+        archive: zip::ZipArchive<R>,
+        index: usize,
+    }
+
+    impl<R> VolZip<R>
+    where
+        R: Read + Seek,
+    {
+        /// Opens the archive and finds the CSV inside
+        pub fn new(reader: R) -> Result<Self, ParseError> {
+            let zip_error = |e| ParseError::new("zip".to_string(), e);
+            let mut archive = zip::ZipArchive::new(reader).map_err(zip_error)?;
+
+            for index in 0..archive.len() {
+                let file = archive.by_index(index).map_err(zip_error)?;
+                if file.name().to_lowercase().ends_with(".csv") {
+                    drop(file);
+                    return Ok(VolZip { archive, index });
+                }
+            }
+
+            Err(ParseError::new("zip".to_string(), "no csv file in archive"))
+        }
+
+        /// Name of the CSV in the archive, e.g. `origin_destination_bus_201911.csv`
+        pub fn file_name(&mut self) -> Result<String, ParseError> {
+            self.archive
+                .by_index(self.index)
+                .map(|f| f.name().to_string())
+                .map_err(|e| ParseError::new("zip".to_string(), e))
+        }
+
+        /// Reads the records of the CSV as it is unzipped
+        pub fn records<T>(&mut self) -> Result<VolRecords<zip::read::ZipFile<'_>, T>, ParseError>
+        where
+            T: DeserializeOwned,
+        {
+            let file = self
+                .archive
+                .by_index(self.index)
+                .map_err(|e| ParseError::new("zip".to_string(), e))?;
+
+            Ok(read_csv(file))
+        }
+    }
 }
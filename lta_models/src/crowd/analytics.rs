@@ -0,0 +1,513 @@
+//! Aggregations over passenger volume records
+//!
+//! Everything here is built by streaming records through, usually straight from
+//! `read_csv` or `VolZip::records`. Memory grows with the number of distinct
+//! stops, stations and origin destination pairs, not with the number of rows,
+//! so the multi-million row OD files do not have to be loaded first.
+//!
+//! ## Example
+//! ```rust
+//! use lta_models::crowd::analytics::OdMatrix;
+//! use lta_models::crowd::passenger_vol::{read_csv, OdPassengerVolRecord};
+//!
+//! let csv = "YEAR_MONTH,DAY_TYPE,TIME_PER_HOUR,PT_TYPE,ORIGIN_PT_CODE,DESTINATION_PT_CODE,TOTAL_TRIPS
+//! 2019-11,WEEKDAY,8,TRAIN,NS1-EW24,NS24-NE6-CC1,1420
+//! 2019-11,WEEKDAY,9,TRAIN,NS1-EW24,NS24-NE6-CC1,80";
+//!
+//! let matrix = OdMatrix::try_from_records(read_csv::<_, OdPassengerVolRecord>(csv.as_bytes())).unwrap();
+//! assert_eq!(matrix.trips("NS1-EW24", "NS24-NE6-CC1"), 1500);
+//! ```
+
+use crate::crowd::passenger_vol::{DayType, OdPassengerVolRecord, PassengerVolRecord, YearMonth};
+use std::collections::HashMap;
+use std::iter::FromIterator;
+
+/// Interns stop and station codes so each one is only stored once
+#[derive(Debug, Clone, Default)]
+struct Codes {
+    ids: HashMap<String, u32>,
+    names: Vec<String>,
+}
+
+impl Codes {
+    fn id(&mut self, code: &str) -> u32 {
+        if let Some(id) = self.ids.get(code) {
+            return *id;
+        }
+
+        let id = self.names.len() as u32;
+        self.ids.insert(code.to_string(), id);
+        self.names.push(code.to_string());
+        id
+    }
+
+    fn get(&self, code: &str) -> Option<u32> {
+        self.ids.get(code).copied()
+    }
+
+    fn name(&self, id: u32) -> &str {
+        &self.names[id as usize]
+    }
+}
+
+/// Sorts by count, highest first, then by code and keeps the first `n`
+fn top_n(mut counts: Vec<(&str, u64)>, n: usize) -> Vec<(&str, u64)> {
+    counts.sort_unstable_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+    counts.truncate(n);
+    counts
+}
+
+/// Sparse origin destination matrix of total trips, keyed by bus stop or train station code
+#[derive(Debug, Clone, Default)]
+pub struct OdMatrix {
+    codes: Codes,
+    trips: HashMap<(u32, u32), u64>,
+    origins: HashMap<u32, u64>,
+    destinations: HashMap<u32, u64>,
+}
+
+impl OdMatrix {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Adds `trips` from `origin` to `destination`
+    pub fn add_trips(&mut self, origin: &str, destination: &str, trips: u64) {
+        let o = self.codes.id(origin);
+        let d = self.codes.id(destination);
+        *self.trips.entry((o, d)).or_insert(0) += trips;
+        *self.origins.entry(o).or_insert(0) += trips;
+        *self.destinations.entry(d).or_insert(0) += trips;
+    }
+
+    pub fn add(&mut self, record: &OdPassengerVolRecord) {
+        self.add_trips(
+            &record.origin_pt_code,
+            &record.destination_pt_code,
+            record.total_trips,
+        );
+    }
+
+    /// Builds a matrix from records that may fail to parse, stopping at the first error
+    pub fn try_from_records<I, E>(records: I) -> Result<Self, E>
+    where
+        I: IntoIterator<Item = Result<OdPassengerVolRecord, E>>,
+    {
+        let mut matrix = OdMatrix::new();
+        for record in records {
+            matrix.add(&record?);
+        }
+
+        Ok(matrix)
+    }
+
+    /// Total trips from `origin` to `destination`, 0 if there are none
+    pub fn trips(&self, origin: &str, destination: &str) -> u64 {
+        match (self.codes.get(origin), self.codes.get(destination)) {
+            (Some(o), Some(d)) => self.trips.get(&(o, d)).copied().unwrap_or(0),
+            _ => 0,
+        }
+    }
+
+    /// Total trips starting at `origin`
+    pub fn trips_from(&self, origin: &str) -> u64 {
+        self.codes
+            .get(origin)
+            .and_then(|o| self.origins.get(&o))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Total trips ending at `destination`
+    pub fn trips_to(&self, destination: &str) -> u64 {
+        self.codes
+            .get(destination)
+            .and_then(|d| self.destinations.get(&d))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Total trips in the matrix
+    pub fn total_trips(&self) -> u64 {
+        self.origins.values().sum()
+    }
+
+    /// Number of origin destination pairs with trips
+    pub fn len(&self) -> usize {
+        self.trips.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.trips.is_empty()
+    }
+
+    /// Every `(origin, destination, trips)`, in no particular order
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str, u64)> + '_ {
+        self.trips
+            .iter()
+            .map(move |(&(o, d), &t)| (self.codes.name(o), self.codes.name(d), t))
+    }
+
+    /// `n` origins with the most trips, highest first
+    pub fn top_origins(&self, n: usize) -> Vec<(&str, u64)> {
+        let counts = self
+            .origins
+            .iter()
+            .map(|(&o, &t)| (self.codes.name(o), t))
+            .collect();
+        top_n(counts, n)
+    }
+
+    /// `n` destinations with the most trips, highest first
+    pub fn top_destinations(&self, n: usize) -> Vec<(&str, u64)> {
+        let counts = self
+            .destinations
+            .iter()
+            .map(|(&d, &t)| (self.codes.name(d), t))
+            .collect();
+        top_n(counts, n)
+    }
+
+    /// `n` origin destination pairs with the most trips, highest first
+    pub fn top_pairs(&self, n: usize) -> Vec<(&str, &str, u64)> {
+        let mut pairs: Vec<_> = self.iter().collect();
+        pairs.sort_unstable_by(|a, b| {
+            b.2.cmp(&a.2)
+                .then_with(|| a.0.cmp(b.0))
+                .then_with(|| a.1.cmp(b.1))
+        });
+        pairs.truncate(n);
+        pairs
+    }
+
+    /// Trips by origin, to compare months with `compare_months`
+    pub fn origin_totals(&self) -> VolumeTotals {
+        let by_code = self
+            .origins
+            .iter()
+            .map(|(&o, &t)| (self.codes.name(o).to_string(), t))
+            .collect();
+
+        VolumeTotals { by_code }
+    }
+}
+
+impl<'a> Extend<&'a OdPassengerVolRecord> for OdMatrix {
+    fn extend<I: IntoIterator<Item = &'a OdPassengerVolRecord>>(&mut self, iter: I) {
+        iter.into_iter().for_each(|r| self.add(r));
+    }
+}
+
+impl Extend<OdPassengerVolRecord> for OdMatrix {
+    fn extend<I: IntoIterator<Item = OdPassengerVolRecord>>(&mut self, iter: I) {
+        iter.into_iter().for_each(|r| self.add(&r));
+    }
+}
+
+impl FromIterator<OdPassengerVolRecord> for OdMatrix {
+    fn from_iter<I: IntoIterator<Item = OdPassengerVolRecord>>(iter: I) -> Self {
+        let mut matrix = OdMatrix::new();
+        matrix.extend(iter);
+        matrix
+    }
+}
+
+/// Counts for each hour of the day, split by weekday and weekend/holiday
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct HourlyProfile {
+    pub weekday: [u64; 24],
+    pub weekends_holiday: [u64; 24],
+}
+
+impl HourlyProfile {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Adds `count` to `hour`. Returns `false` and ignores the count if the
+    /// day type is unknown or the hour is not within 0 to 23
+    pub fn add(&mut self, day_type: &DayType, hour: u8, count: u64) -> bool {
+        let hours = match day_type {
+            DayType::Weekday => &mut self.weekday,
+            DayType::WeekendsHoliday => &mut self.weekends_holiday,
+            DayType::Unknown(_) => return false,
+        };
+
+        match hours.get_mut(hour as usize) {
+            Some(c) => {
+                *c += count;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Counts of `day_type`, all zero if it is unknown
+    pub fn hours(&self, day_type: &DayType) -> [u64; 24] {
+        match day_type {
+            DayType::Weekday => self.weekday,
+            DayType::WeekendsHoliday => self.weekends_holiday,
+            DayType::Unknown(_) => [0; 24],
+        }
+    }
+
+    /// Busiest hour of `day_type`, `None` if there are no counts
+    pub fn peak_hour(&self, day_type: &DayType) -> Option<u8> {
+        let hours = self.hours(day_type);
+        (0..24u8)
+            .filter(|&h| hours[h as usize] > 0)
+            .max_by(|&a, &b| hours[a as usize].cmp(&hours[b as usize]).then(b.cmp(&a)))
+    }
+
+    pub fn total(&self) -> u64 {
+        self.weekday.iter().chain(&self.weekends_holiday).sum()
+    }
+}
+
+/// Trips of origin destination records by hour
+impl<'a> Extend<&'a OdPassengerVolRecord> for HourlyProfile {
+    fn extend<I: IntoIterator<Item = &'a OdPassengerVolRecord>>(&mut self, iter: I) {
+        iter.into_iter().for_each(|r| {
+            self.add(&r.day_type, r.hour, r.total_trips);
+        });
+    }
+}
+
+impl Extend<OdPassengerVolRecord> for HourlyProfile {
+    fn extend<I: IntoIterator<Item = OdPassengerVolRecord>>(&mut self, iter: I) {
+        iter.into_iter().for_each(|r| {
+            self.add(&r.day_type, r.hour, r.total_trips);
+        });
+    }
+}
+
+impl FromIterator<OdPassengerVolRecord> for HourlyProfile {
+    fn from_iter<I: IntoIterator<Item = OdPassengerVolRecord>>(iter: I) -> Self {
+        let mut profile = HourlyProfile::new();
+        profile.extend(iter);
+        profile
+    }
+}
+
+/// Tap in and tap out profiles of a bus stop or train station
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TapProfile {
+    pub tap_in: HourlyProfile,
+    pub tap_out: HourlyProfile,
+}
+
+/// Hourly tap in and tap out profiles for every bus stop or train station in a file
+#[derive(Debug, Clone, Default)]
+pub struct TapProfiles {
+    by_code: HashMap<String, TapProfile>,
+}
+
+impl TapProfiles {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn add(&mut self, record: &PassengerVolRecord) {
+        // avoids cloning the code for every row of a stop that is already known
+        if !self.by_code.contains_key(&record.pt_code) {
+            self.by_code
+                .insert(record.pt_code.clone(), TapProfile::default());
+        }
+        let profile = self
+            .by_code
+            .get_mut(&record.pt_code)
+            .expect("profile was just inserted");
+
+        profile
+            .tap_in
+            .add(&record.day_type, record.hour, record.tap_in);
+        profile
+            .tap_out
+            .add(&record.day_type, record.hour, record.tap_out);
+    }
+
+    /// Builds profiles from records that may fail to parse, stopping at the first error
+    pub fn try_from_records<I, E>(records: I) -> Result<Self, E>
+    where
+        I: IntoIterator<Item = Result<PassengerVolRecord, E>>,
+    {
+        let mut profiles = TapProfiles::new();
+        for record in records {
+            profiles.add(&record?);
+        }
+
+        Ok(profiles)
+    }
+
+    /// Profile of a bus stop or train station code
+    pub fn get(&self, pt_code: &str) -> Option<&TapProfile> {
+        self.by_code.get(pt_code)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &TapProfile)> {
+        self.by_code.iter().map(|(c, p)| (c.as_str(), p))
+    }
+
+    /// Sum of every profile, i.e. the network wide profile
+    pub fn network(&self) -> TapProfile {
+        let mut total = TapProfile::default();
+        for p in self.by_code.values() {
+            for h in 0..24 {
+                total.tap_in.weekday[h] += p.tap_in.weekday[h];
+                total.tap_in.weekends_holiday[h] += p.tap_in.weekends_holiday[h];
+                total.tap_out.weekday[h] += p.tap_out.weekday[h];
+                total.tap_out.weekends_holiday[h] += p.tap_out.weekends_holiday[h];
+            }
+        }
+
+        total
+    }
+
+    /// Tap ins plus tap outs by code, to compare months with `compare_months`
+    pub fn totals(&self) -> VolumeTotals {
+        let by_code = self
+            .by_code
+            .iter()
+            .map(|(c, p)| (c.clone(), p.tap_in.total() + p.tap_out.total()))
+            .collect();
+
+        VolumeTotals { by_code }
+    }
+}
+
+impl<'a> Extend<&'a PassengerVolRecord> for TapProfiles {
+    fn extend<I: IntoIterator<Item = &'a PassengerVolRecord>>(&mut self, iter: I) {
+        iter.into_iter().for_each(|r| self.add(r));
+    }
+}
+
+impl FromIterator<PassengerVolRecord> for TapProfiles {
+    fn from_iter<I: IntoIterator<Item = PassengerVolRecord>>(iter: I) -> Self {
+        let mut profiles = TapProfiles::new();
+        iter.into_iter().for_each(|r| profiles.add(&r));
+        profiles
+    }
+}
+
+/// Hourly profile of all trips in an origin destination file, streamed from
+/// `read_csv` or `VolZip::records`. Stops at the first record that fails to parse.
+pub fn od_hourly_profile<I, E>(records: I) -> Result<HourlyProfile, E>
+where
+    I: IntoIterator<Item = Result<OdPassengerVolRecord, E>>,
+{
+    let mut profile = HourlyProfile::new();
+    for record in records {
+        let r = record?;
+        profile.add(&r.day_type, r.hour, r.total_trips);
+    }
+
+    Ok(profile)
+}
+
+/// Volume of each bus stop or train station for a month
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct VolumeTotals {
+    pub by_code: HashMap<String, u64>,
+}
+
+impl VolumeTotals {
+    pub fn total(&self) -> u64 {
+        self.by_code.values().sum()
+    }
+}
+
+/// Change in volume of a code between two months
+#[derive(Debug, Clone, PartialEq)]
+pub struct VolumeChange {
+    pub pt_code: String,
+    pub prev: u64,
+    pub curr: u64,
+}
+
+impl VolumeChange {
+    pub fn diff(&self) -> i64 {
+        self.curr as i64 - self.prev as i64
+    }
+
+    /// Percentage change, `None` if there was no volume in the previous month
+    pub fn pct(&self) -> Option<f64> {
+        if self.prev == 0 {
+            None
+        } else {
+            Some(self.diff() as f64 / self.prev as f64 * 100.0)
+        }
+    }
+}
+
+/// Month over month comparison of two downloads of the same `VolType`
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonthComparison {
+    pub prev_month: YearMonth,
+    pub curr_month: YearMonth,
+
+    /// Every code in either month, largest absolute change first
+    pub changes: Vec<VolumeChange>,
+}
+
+impl MonthComparison {
+    pub fn prev_total(&self) -> u64 {
+        self.changes.iter().map(|c| c.prev).sum()
+    }
+
+    pub fn curr_total(&self) -> u64 {
+        self.changes.iter().map(|c| c.curr).sum()
+    }
+
+    /// Codes that only have volume in the current month
+    pub fn added(&self) -> impl Iterator<Item = &VolumeChange> {
+        self.changes.iter().filter(|c| c.prev == 0 && c.curr > 0)
+    }
+
+    /// Codes that only have volume in the previous month
+    pub fn removed(&self) -> impl Iterator<Item = &VolumeChange> {
+        self.changes.iter().filter(|c| c.prev > 0 && c.curr == 0)
+    }
+}
+
+/// Compares the totals of two months, e.g. `TapProfiles::totals` of two
+/// `VolType::BusStops` downloads
+pub fn compare_months(
+    prev_month: YearMonth,
+    prev: &VolumeTotals,
+    curr_month: YearMonth,
+    curr: &VolumeTotals,
+) -> MonthComparison {
+    let mut changes: Vec<VolumeChange> = prev
+        .by_code
+        .iter()
+        .map(|(code, &p)| VolumeChange {
+            pt_code: code.clone(),
+            prev: p,
+            curr: curr.by_code.get(code).copied().unwrap_or(0),
+        })
+        .collect();
+
+    changes.extend(
+        curr.by_code
+            .iter()
+            .filter(|(code, _)| !prev.by_code.contains_key(*code))
+            .map(|(code, &c)| VolumeChange {
+                pt_code: code.clone(),
+                prev: 0,
+                curr: c,
+            }),
+    );
+
+    changes.sort_unstable_by(|a, b| {
+        b.diff()
+            .abs()
+            .cmp(&a.diff().abs())
+            .then_with(|| a.pt_code.cmp(&b.pt_code))
+    });
+
+    MonthComparison {
+        prev_month,
+        curr_month,
+        changes,
+    }
+}
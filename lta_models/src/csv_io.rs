@@ -0,0 +1,901 @@
+//! CSV output and input for every model
+//!
+//! Every field is a column named after it. Nested values are flattened with the
+//! field name as a prefix:
+//!
+//! - `BusFreq` fields become `<field>_min` and `<field>_max`
+//! - `next_bus` becomes `next_bus_1_<field>` to `next_bus_3_<field>`
+//! - `Location` becomes `start_lat`, `start_long`, `end_lat` and `end_long`
+//!   and `Coordinates` become `lat` and `long`
+//! - `Vec<VehicleType>` and train stations are joined with `/` and `-` like the API,
+//!   an empty list is an empty cell
+//! - times are written as `HH:MM`, with seconds only if there are any, dates as
+//!   `YYYY-MM-DD` and timestamps as RFC 3339
+//! - enums are written as `as_str` or their integer value
+//!
+//! A missing value is an empty cell, an optional group of columns is `None` when
+//! all of its cells are empty, so an optional string is never `Some("")`. The
+//! exceptions are lists and `BusService::loop_desc`, which the API sends as an empty
+//! string for services that do not loop. An empty list and a `None` description are
+//! an empty cell, other values get a leading `\` when they are empty or start with
+//! one, e.g. `Some("")` is `\`. Headers are checked when reading so a file written by
+//! another version with different columns is rejected instead of misread.
+//!
+//! Bus arrivals and train service alerts hold lists, they are written one row per
+//! service or per affected segment and message with `BusArrivalRow` and `TrainAlertRow`.
+//!
+//! ## Example
+//! ```rust
+//! use lta_models::bus::bus_stops::{BusStop, BusStopsResp};
+//! use lta_models::csv_io::{read_csv, write_csv};
+//!
+//! let stops: BusStopsResp =
+//!     serde_json::from_str(include_str!("../../dumped_data/bus_stops.json")).unwrap();
+//!
+//! let csv = write_csv(Vec::new(), &stops.value).unwrap();
+//! assert!(csv.starts_with(b"bus_stop_code,road_name,desc,lat,long\n"));
+//!
+//! let read = read_csv::<_, BusStop>(csv.as_slice())
+//!     .unwrap()
+//!     .collect::<Result<Vec<_>, _>>()
+//!     .unwrap();
+//! assert_eq!(read, stops.value);
+//! ```
+
+use csv::StringRecord;
+use lta_utils_commons::chrono::{DateTime, NaiveDate, NaiveTime, Timelike};
+use lta_utils_commons::sg_time::SgDateTime;
+use lta_utils_commons::{Coordinates, Location};
+use std::convert::TryInto;
+use std::fmt::{self, Display, Formatter};
+use std::io::{Read, Write};
+use std::marker::PhantomData;
+
+/// Error writing or reading a CSV
+#[derive(Debug)]
+pub enum CsvError {
+    Csv(csv::Error),
+
+    /// Header row is not the one of the record type
+    Headers {
+        expected: String,
+        found: String,
+    },
+
+    /// Line number starting from 1, column and what is wrong with the cell
+    Field {
+        line: u64,
+        column: String,
+        msg: String,
+    },
+}
+
+impl Display for CsvError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            CsvError::Csv(e) => write!(f, "csv error: {}", e),
+            CsvError::Headers { expected, found } => {
+                write!(f, "expected headers `{}`, found `{}`", expected, found)
+            }
+            CsvError::Field { line, column, msg } => {
+                write!(f, "line {}, column {}: {}", line, column, msg)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CsvError {}
+
+impl From<csv::Error> for CsvError {
+    fn from(e: csv::Error) -> Self {
+        CsvError::Csv(e)
+    }
+}
+
+impl From<std::io::Error> for CsvError {
+    fn from(e: std::io::Error) -> Self {
+        CsvError::Csv(e.into())
+    }
+}
+
+/// Cells of a row being read, in column order
+#[derive(Debug)]
+pub struct Fields<'a> {
+    record: &'a StringRecord,
+    headers: &'a [String],
+    pos: usize,
+}
+
+impl<'a> Fields<'a> {
+    /// Next cell as it is in the file
+    pub fn next_str(&mut self) -> Result<&'a str, CsvError> {
+        let field = self.record.get(self.pos);
+        self.pos += 1;
+        field.ok_or_else(|| self.error("missing column"))
+    }
+
+    /// Next cell parsed with `FromStr`
+    pub fn parse<T>(&mut self) -> Result<T, CsvError>
+    where
+        T: std::str::FromStr,
+        T::Err: Display,
+    {
+        let field = self.next_str()?;
+        field
+            .parse()
+            .map_err(|e| self.error(format!("invalid value `{}`: {}", field, e)))
+    }
+
+    /// Error about the cell that was just read
+    pub fn error<M: Into<String>>(&self, msg: M) -> CsvError {
+        CsvError::Field {
+            line: self.record.position().map_or(0, |p| p.line()),
+            column: self
+                .headers
+                .get(self.pos.saturating_sub(1))
+                .cloned()
+                .unwrap_or_default(),
+            msg: msg.into(),
+        }
+    }
+
+    fn next_empty(&self, n: usize) -> bool {
+        (self.pos..self.pos + n).all(|i| self.record.get(i).is_none_or(str::is_empty))
+    }
+}
+
+/// A value written as one or more CSV columns
+pub trait CsvColumns: Sized {
+    /// Column names, `prefix` is the name of the field holding the value
+    fn headers(prefix: &str, headers: &mut Vec<String>);
+
+    fn write(&self, row: &mut StringRecord);
+
+    fn read(fields: &mut Fields<'_>) -> Result<Self, CsvError>;
+
+    /// Number of columns
+    fn width() -> usize {
+        let mut headers = Vec::new();
+        Self::headers("", &mut headers);
+        headers.len()
+    }
+}
+
+/// Headers of a record written at the top of a file
+pub fn headers<T: CsvColumns>() -> Vec<String> {
+    let mut headers = Vec::new();
+    T::headers("", &mut headers);
+    headers
+}
+
+fn join(prefix: &str, name: &str) -> String {
+    match (prefix.is_empty(), name.is_empty()) {
+        (true, _) => name.to_string(),
+        (_, true) => prefix.to_string(),
+        _ => format!("{}_{}", prefix, name),
+    }
+}
+
+/// Writes `value` with a leading `\` if it is empty or starts with one
+#[cfg(any(feature = "bus", feature = "traffic", feature = "train"))]
+fn push_escaped(row: &mut StringRecord, value: &str) {
+    if value.is_empty() || value.starts_with('\\') {
+        row.push_field(&format!("\\{}", value));
+    } else {
+        row.push_field(value);
+    }
+}
+
+/// Value of a cell written by `push_escaped`, `None` if the cell is empty
+#[cfg(any(feature = "bus", feature = "traffic", feature = "train"))]
+fn unescape(field: &str) -> Option<&str> {
+    if field.is_empty() {
+        return None;
+    }
+    Some(field.strip_prefix('\\').unwrap_or(field))
+}
+
+/// Headers of a field, the closure is only used to name the field's type
+fn field_headers<R, F, G>(_: G, name: &str, headers: &mut Vec<String>)
+where
+    F: CsvColumns,
+    G: Fn(&R) -> &F,
+{
+    F::headers(name, headers)
+}
+
+/// Values written as a single cell with `Display` and read with `FromStr`
+macro_rules! str_columns {
+    ($($value:ty),* $(,)?) => {
+        $(impl CsvColumns for $value {
+            fn headers(prefix: &str, headers: &mut Vec<String>) {
+                headers.push(prefix.to_string());
+            }
+
+            fn write(&self, row: &mut StringRecord) {
+                row.push_field(&self.to_string());
+            }
+
+            fn read(fields: &mut Fields<'_>) -> Result<Self, CsvError> {
+                fields.parse()
+            }
+        })*
+    };
+}
+
+/// Enums generated by `int_enum`, written as their integer value
+#[cfg(any(feature = "traffic", feature = "train"))]
+macro_rules! int_columns {
+    ($($value:ty),* $(,)?) => {
+        $(impl CsvColumns for $value {
+            fn headers(prefix: &str, headers: &mut Vec<String>) {
+                headers.push(prefix.to_string());
+            }
+
+            fn write(&self, row: &mut StringRecord) {
+                row.push_field(&self.value().to_string());
+            }
+
+            fn read(fields: &mut Fields<'_>) -> Result<Self, CsvError> {
+                fields.parse::<u32>().map(<$value>::from)
+            }
+        })*
+    };
+}
+
+/// Lists written as a single cell joined with the `Sep` delimiter of their items,
+/// empty lists as an empty cell
+#[cfg(any(feature = "traffic", feature = "train"))]
+macro_rules! delimited_columns {
+    ($($item:ty),* $(,)?) => {
+        $(impl CsvColumns for Vec<$item> {
+            fn headers(prefix: &str, headers: &mut Vec<String>) {
+                headers.push(prefix.to_string());
+            }
+
+            fn write(&self, row: &mut StringRecord) {
+                let delimiter = <$item as lta_utils_commons::de::Sep>::delimiter();
+                let items: Vec<&str> = self.iter().map(|i| i.as_str()).collect();
+                if items.is_empty() {
+                    row.push_field("");
+                } else {
+                    push_escaped(row, &items.join(delimiter));
+                }
+            }
+
+            fn read(fields: &mut Fields<'_>) -> Result<Self, CsvError> {
+                let delimiter = <$item as lta_utils_commons::de::Sep>::delimiter();
+                let field = fields.next_str()?;
+                let items = match unescape(field) {
+                    Some(items) => items,
+                    None => return Ok(Vec::new()),
+                };
+
+                items
+                    .split(delimiter)
+                    .map(|s| s.parse::<$item>())
+                    .collect::<Result<_, _>>()
+                    .map_err(|e| fields.error(format!("invalid value `{}`: {}", field, e)))
+            }
+        })*
+    };
+}
+
+/// Records written as the columns of their fields, in the order listed.
+/// Every field must be listed, `field as "name"` changes the column prefix and
+/// `field with module` writes and reads it with `module::write` and `module::read`
+macro_rules! record_columns {
+    (@name $field:ident) => {
+        stringify!($field)
+    };
+    (@name $field:ident $name:literal) => {
+        $name
+    };
+    (@write $value:expr, $row:ident) => {
+        $value.write($row)
+    };
+    (@write $value:expr, $row:ident, $with:ident) => {
+        $with::write(&$value, $row)
+    };
+    (@read $fields:ident) => {
+        CsvColumns::read($fields)?
+    };
+    (@read $fields:ident, $with:ident) => {
+        $with::read($fields)?
+    };
+    ($record:ty { $($field:ident $(as $name:literal)? $(with $with:ident)?),* $(,)? }) => {
+        impl CsvColumns for $record {
+            fn headers(prefix: &str, headers: &mut Vec<String>) {
+                $(field_headers(
+                    |r: &$record| &r.$field,
+                    &join(prefix, record_columns!(@name $field $($name)?)),
+                    headers,
+                );)*
+            }
+
+            fn write(&self, row: &mut StringRecord) {
+                $(record_columns!(@write self.$field, row $(, $with)?);)*
+            }
+
+            fn read(fields: &mut Fields<'_>) -> Result<Self, CsvError> {
+                Ok(Self {
+                    $($field: record_columns!(@read fields $(, $with)?),)*
+                })
+            }
+        }
+    };
+}
+
+str_columns!(u8, u32, u64, i32, f32, f64, bool, String);
+
+impl<T: CsvColumns> CsvColumns for Option<T> {
+    fn headers(prefix: &str, headers: &mut Vec<String>) {
+        T::headers(prefix, headers)
+    }
+
+    fn write(&self, row: &mut StringRecord) {
+        match self {
+            Some(v) => v.write(row),
+            None => (0..T::width()).for_each(|_| row.push_field("")),
+        }
+    }
+
+    fn read(fields: &mut Fields<'_>) -> Result<Self, CsvError> {
+        let width = T::width();
+        if fields.next_empty(width) {
+            fields.pos += width;
+            return Ok(None);
+        }
+        T::read(fields).map(Some)
+    }
+}
+
+impl<T: CsvColumns, const N: usize> CsvColumns for [T; N] {
+    fn headers(prefix: &str, headers: &mut Vec<String>) {
+        for i in 1..=N {
+            T::headers(&join(prefix, &i.to_string()), headers);
+        }
+    }
+
+    fn write(&self, row: &mut StringRecord) {
+        self.iter().for_each(|v| v.write(row));
+    }
+
+    fn read(fields: &mut Fields<'_>) -> Result<Self, CsvError> {
+        let items = (0..N)
+            .map(|_| T::read(fields))
+            .collect::<Result<Vec<_>, _>>()?;
+        match items.try_into() {
+            Ok(array) => Ok(array),
+            Err(_) => unreachable!("exactly N items are read"),
+        }
+    }
+}
+
+impl CsvColumns for NaiveTime {
+    fn headers(prefix: &str, headers: &mut Vec<String>) {
+        headers.push(prefix.to_string());
+    }
+
+    fn write(&self, row: &mut StringRecord) {
+        let time = if self.second() == 0 && self.nanosecond() == 0 {
+            self.format("%H:%M")
+        } else {
+            self.format("%H:%M:%S%.f")
+        };
+        row.push_field(&time.to_string());
+    }
+
+    fn read(fields: &mut Fields<'_>) -> Result<Self, CsvError> {
+        let field = fields.next_str()?;
+        NaiveTime::parse_from_str(field, "%H:%M:%S%.f")
+            .or_else(|_| NaiveTime::parse_from_str(field, "%H:%M"))
+            .map_err(|e| fields.error(format!("invalid time `{}`: {}", field, e)))
+    }
+}
+
+impl CsvColumns for NaiveDate {
+    fn headers(prefix: &str, headers: &mut Vec<String>) {
+        headers.push(prefix.to_string());
+    }
+
+    fn write(&self, row: &mut StringRecord) {
+        row.push_field(&self.format("%Y-%m-%d").to_string());
+    }
+
+    fn read(fields: &mut Fields<'_>) -> Result<Self, CsvError> {
+        let field = fields.next_str()?;
+        NaiveDate::parse_from_str(field, "%Y-%m-%d")
+            .map_err(|e| fields.error(format!("invalid date `{}`: {}", field, e)))
+    }
+}
+
+impl CsvColumns for SgDateTime {
+    fn headers(prefix: &str, headers: &mut Vec<String>) {
+        headers.push(prefix.to_string());
+    }
+
+    fn write(&self, row: &mut StringRecord) {
+        row.push_field(&self.to_rfc3339());
+    }
+
+    fn read(fields: &mut Fields<'_>) -> Result<Self, CsvError> {
+        let field = fields.next_str()?;
+        DateTime::parse_from_rfc3339(field)
+            .map_err(|e| fields.error(format!("invalid timestamp `{}`: {}", field, e)))
+    }
+}
+
+record_columns!(Coordinates { lat, long });
+record_columns!(Location { start, end });
+
+/// Writes records to a CSV one at a time, headers first
+#[derive(Debug)]
+pub struct CsvWriter<W: Write, T> {
+    writer: csv::Writer<W>,
+    row: StringRecord,
+    record: PhantomData<T>,
+}
+
+impl<W: Write, T: CsvColumns> CsvWriter<W, T> {
+    /// Writes the headers
+    pub fn new(writer: W) -> Result<Self, CsvError> {
+        let mut writer = csv::Writer::from_writer(writer);
+        writer.write_record(headers::<T>())?;
+        Ok(CsvWriter {
+            writer,
+            row: StringRecord::new(),
+            record: PhantomData,
+        })
+    }
+
+    pub fn write(&mut self, record: &T) -> Result<(), CsvError> {
+        self.row.clear();
+        record.write(&mut self.row);
+        self.writer.write_record(&self.row)?;
+        Ok(())
+    }
+
+    /// Flushes and returns the writer
+    pub fn finish(self) -> Result<W, CsvError> {
+        self.writer
+            .into_inner()
+            .map_err(|e| CsvError::from(e.into_error()))
+    }
+}
+
+/// Writes a CSV of every record
+pub fn write_csv<'a, W, T, I>(writer: W, records: I) -> Result<W, CsvError>
+where
+    W: Write,
+    T: CsvColumns + 'a,
+    I: IntoIterator<Item = &'a T>,
+{
+    let mut writer = CsvWriter::new(writer)?;
+    for record in records {
+        writer.write(record)?;
+    }
+    writer.finish()
+}
+
+/// Records read one at a time from a CSV, see `read_csv`
+pub struct CsvRecords<R, T> {
+    reader: csv::Reader<R>,
+    headers: Vec<String>,
+    row: StringRecord,
+    record: PhantomData<T>,
+}
+
+impl<R: Read, T: CsvColumns> Iterator for CsvRecords<R, T> {
+    type Item = Result<T, CsvError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.reader.read_record(&mut self.row) {
+            Ok(true) => {}
+            Ok(false) => return None,
+            Err(e) => return Some(Err(e.into())),
+        }
+
+        let mut fields = Fields {
+            record: &self.row,
+            headers: &self.headers,
+            pos: 0,
+        };
+        Some(T::read(&mut fields))
+    }
+}
+
+/// Reads a CSV written by `CsvWriter` without loading it into memory.
+/// Fails if the headers are not the ones of `T`
+pub fn read_csv<R, T>(reader: R) -> Result<CsvRecords<R, T>, CsvError>
+where
+    R: Read,
+    T: CsvColumns,
+{
+    let mut reader = csv::Reader::from_reader(reader);
+    let expected = headers::<T>();
+    let found = reader.headers()?;
+    if found.iter().ne(expected.iter()) {
+        return Err(CsvError::Headers {
+            expected: expected.join(","),
+            found: found.iter().collect::<Vec<_>>().join(","),
+        });
+    }
+
+    Ok(CsvRecords {
+        reader,
+        headers: expected,
+        row: StringRecord::new(),
+        record: PhantomData,
+    })
+}
+
+#[cfg(feature = "bus")]
+mod bus {
+    use super::*;
+    use crate::bus::bus_arrival::{ArrivalBusService, BusArrivalResp, NextBus};
+    use crate::bus::bus_routes::BusRoute;
+    use crate::bus::bus_services::{BusFreq, BusService};
+    use crate::bus::bus_stops::BusStop;
+    use crate::bus_enums::{BusCategory, BusFeature, BusLoad, BusType, Operator};
+
+    str_columns!(Operator, BusType, BusLoad, BusFeature, BusCategory);
+
+    record_columns!(NextBus {
+        origin_code,
+        dest_code,
+        est_arrival,
+        lat,
+        long,
+        visit_no,
+        load,
+        feature,
+        bus_type,
+    });
+    record_columns!(ArrivalBusService {
+        service_no,
+        operator,
+        next_bus,
+    });
+    record_columns!(BusArrivalRow {
+        bus_stop_code,
+        service as "",
+    });
+    record_columns!(BusFreq { min, max });
+    record_columns!(BusService {
+        service_no,
+        operator,
+        no_direction,
+        category,
+        origin_code,
+        dest_code,
+        am_peak_freq,
+        am_offpeak_freq,
+        pm_peak_freq,
+        pm_offpeak_freq,
+        loop_desc with loop_desc,
+    });
+    record_columns!(BusRoute {
+        service_no,
+        operator,
+        direction,
+        stop_seq,
+        bus_stop_code,
+        dist,
+        wd_first,
+        wd_last,
+        sat_first,
+        sat_last,
+        sun_first,
+        sun_last,
+    });
+    record_columns!(BusStop {
+        bus_stop_code,
+        road_name,
+        desc,
+        lat,
+        long,
+    });
+
+    /// `None` is an empty cell and `Some("")`, sent for services that do not loop, is `\`
+    mod loop_desc {
+        use super::*;
+
+        pub fn write(loop_desc: &Option<String>, row: &mut StringRecord) {
+            match loop_desc {
+                Some(desc) => push_escaped(row, desc),
+                None => row.push_field(""),
+            }
+        }
+
+        pub fn read(fields: &mut Fields<'_>) -> Result<Option<String>, CsvError> {
+            fields.next_str().map(|s| unescape(s).map(String::from))
+        }
+    }
+
+    /// A service at a bus stop, a stop without services has `None`
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct BusArrivalRow {
+        pub bus_stop_code: u32,
+        pub service: Option<ArrivalBusService>,
+    }
+
+    /// One row per service of every response
+    pub fn bus_arrival_rows(arrivals: &[BusArrivalResp]) -> Vec<BusArrivalRow> {
+        let mut rows = Vec::new();
+        for arrival in arrivals {
+            if arrival.services.is_empty() {
+                rows.push(BusArrivalRow {
+                    bus_stop_code: arrival.bus_stop_code,
+                    service: None,
+                });
+            }
+
+            rows.extend(arrival.services.iter().map(|s| BusArrivalRow {
+                bus_stop_code: arrival.bus_stop_code,
+                service: Some(s.clone()),
+            }));
+        }
+        rows
+    }
+
+    /// Responses from rows, consecutive rows of the same stop are one response
+    pub fn bus_arrivals<I>(rows: I) -> Vec<BusArrivalResp>
+    where
+        I: IntoIterator<Item = BusArrivalRow>,
+    {
+        let mut arrivals: Vec<BusArrivalResp> = Vec::new();
+        for row in rows {
+            match arrivals.last_mut() {
+                Some(last) if last.bus_stop_code == row.bus_stop_code => {
+                    last.services.extend(row.service)
+                }
+                _ => arrivals.push(BusArrivalResp {
+                    bus_stop_code: row.bus_stop_code,
+                    services: row.service.into_iter().collect(),
+                }),
+            }
+        }
+        arrivals
+    }
+}
+
+#[cfg(feature = "bus")]
+pub use self::bus::{bus_arrival_rows, bus_arrivals, BusArrivalRow};
+
+#[cfg(feature = "traffic")]
+mod traffic {
+    use super::*;
+    use crate::traffic::bike_parking::{BikeParking, RackType};
+    use crate::traffic::carpark_avail::{Agency, CarPark, LotType};
+    use crate::traffic::erp_rates::{DayType, ErpRate, VehicleType, ZoneId};
+    use crate::traffic::est_travel_time::{EstTravelTime, Highway, HighwayDirection};
+    use crate::traffic::faulty_traffic_lights::{FaultyTrafficLight, TechnicalAlarmType};
+    use crate::traffic::road::RoadDetails;
+    use crate::traffic::traffic_images::TrafficImage;
+    use crate::traffic::traffic_incidents::{IncidentType, TrafficIncident};
+    use crate::traffic::traffic_speed_bands::{RoadCategory, TrafficSpeedBand};
+    use crate::traffic::vms_emas::VMS;
+
+    str_columns!(
+        VehicleType,
+        DayType,
+        ZoneId,
+        LotType,
+        Agency,
+        Highway,
+        IncidentType,
+        RoadCategory,
+        RackType,
+    );
+    int_columns!(HighwayDirection, TechnicalAlarmType);
+    delimited_columns!(VehicleType);
+
+    record_columns!(ErpRate {
+        vehicle_type,
+        day_type,
+        start_time,
+        end_time,
+        zone_id,
+        charge_amt,
+        effective_date,
+    });
+    record_columns!(CarPark {
+        carpark_id,
+        area,
+        dev,
+        coords as "",
+        avail_lots,
+        lot_type,
+        agency,
+    });
+    record_columns!(EstTravelTime {
+        name,
+        direction,
+        far_end_pt,
+        start_pt,
+        end_pt,
+        est_travel_time,
+    });
+    record_columns!(FaultyTrafficLight {
+        alarm_id,
+        node_id,
+        technical_alarm_type,
+        start_date,
+        end_date,
+        message,
+    });
+    record_columns!(RoadDetails {
+        event_id,
+        start_date,
+        end_date,
+        service_dept,
+        road_name,
+        other,
+    });
+    record_columns!(TrafficImage {
+        camera_id,
+        lat,
+        long,
+        image_link,
+    });
+    record_columns!(TrafficIncident {
+        incident_type,
+        lat,
+        long,
+        msg,
+    });
+    record_columns!(TrafficSpeedBand {
+        link_id,
+        road_name,
+        road_category,
+        speed_band,
+        min_speed,
+        max_speed,
+        coord_start_end as "",
+    });
+    record_columns!(VMS {
+        equipment_id,
+        lat,
+        long,
+        msg,
+    });
+    record_columns!(BikeParking {
+        desc,
+        lat,
+        long,
+        rack_type,
+        rack_count,
+        shelter_indicator,
+    });
+}
+
+#[cfg(feature = "taxi")]
+mod taxi {
+    use super::*;
+    use crate::taxi::taxi_avail::InternalCoordinates;
+    use crate::taxi::taxi_stands::{TaxiStand, TaxiStandOwner, TaxiStandType};
+
+    str_columns!(TaxiStandOwner, TaxiStandType);
+
+    record_columns!(InternalCoordinates { lat, long });
+    record_columns!(TaxiStand {
+        taxi_code,
+        lat,
+        long,
+        is_barrier_free,
+        owner,
+        stand_type,
+        name,
+    });
+}
+
+#[cfg(feature = "train")]
+mod train {
+    use super::*;
+    use crate::train::train_service_alert::{
+        AffectedSegment, MrtLine, StringWrap, TrainServiceAlert, TrainServiceAlertMessage,
+        TrainStatus,
+    };
+
+    str_columns!(MrtLine);
+    int_columns!(TrainStatus);
+    delimited_columns!(StringWrap);
+
+    record_columns!(AffectedSegment {
+        line,
+        direction,
+        stations,
+        free_public_bus,
+        free_mrt_shuttle,
+        mrt_shuttle_dir,
+    });
+    record_columns!(TrainServiceAlertMessage {
+        content,
+        created_date,
+    });
+    record_columns!(TrainAlertRow {
+        status,
+        segment,
+        message,
+    });
+
+    /// An affected segment or a message of an alert, an alert without either has both `None`
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct TrainAlertRow {
+        pub status: TrainStatus,
+        pub segment: Option<AffectedSegment>,
+        pub message: Option<TrainServiceAlertMessage>,
+    }
+
+    /// Segments then messages of the alert
+    pub fn train_alert_rows(alert: &TrainServiceAlert) -> Vec<TrainAlertRow> {
+        let row = |segment, message| TrainAlertRow {
+            status: alert.status.clone(),
+            segment,
+            message,
+        };
+
+        let mut rows: Vec<_> = alert
+            .affected_segments
+            .iter()
+            .map(|s| row(Some(s.clone()), None))
+            .chain(alert.message.iter().map(|m| row(None, Some(m.clone()))))
+            .collect();
+        if rows.is_empty() {
+            rows.push(row(None, None));
+        }
+        rows
+    }
+
+    /// Alert from its rows, `None` if there are none
+    pub fn train_alert<I>(rows: I) -> Option<TrainServiceAlert>
+    where
+        I: IntoIterator<Item = TrainAlertRow>,
+    {
+        let mut alert: Option<TrainServiceAlert> = None;
+        for row in rows {
+            let alert = alert.get_or_insert_with(|| TrainServiceAlert {
+                status: row.status.clone(),
+                affected_segments: Vec::new(),
+                message: Vec::new(),
+            });
+            alert.affected_segments.extend(row.segment);
+            alert.message.extend(row.message);
+        }
+        alert
+    }
+}
+
+#[cfg(feature = "train")]
+pub use self::train::{train_alert, train_alert_rows, TrainAlertRow};
+
+#[cfg(feature = "crowd")]
+mod crowd {
+    use super::*;
+    use crate::crowd::passenger_vol::{
+        DayType, Link, OdPassengerVolRecord, PassengerVolRecord, PtType, YearMonth,
+    };
+
+    str_columns!(YearMonth, DayType, PtType);
+
+    record_columns!(Link { link });
+    record_columns!(PassengerVolRecord {
+        year_month,
+        day_type,
+        hour,
+        pt_type,
+        pt_code,
+        tap_in,
+        tap_out,
+    });
+    record_columns!(OdPassengerVolRecord {
+        year_month,
+        day_type,
+        hour,
+        pt_type,
+        origin_pt_code,
+        destination_pt_code,
+        total_trips,
+    });
+}
@@ -0,0 +1,82 @@
+//! Optional collection of values that lta-rs does not recognise
+//!
+//! Enums in this crate keep unrecognised API values in an `Unknown` variant
+//! instead of failing. Wrapping a deserialisation in `collect_unknowns` lets
+//! you find out when that happens, e.g. when LTA adds a new bus operator.
+//!
+//! ## Example
+//! ```rust
+//! use lta_models::bus_enums::Operator;
+//! use lta_models::diagnostics::collect_unknowns;
+//!
+//! let (op, unknowns) = collect_unknowns(|| serde_json::from_str::<Operator>(r#""NEWCO""#));
+//! assert_eq!(op.unwrap(), Operator::Unknown("NEWCO".to_string()));
+//! assert_eq!(unknowns[0].type_name, "Operator");
+//! ```
+
+use std::cell::RefCell;
+
+/// A value returned by the API that has no matching enum variant
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct UnknownValue {
+    /// Name of the enum the value was parsed into, e.g. `Operator`
+    pub type_name: &'static str,
+
+    /// Value exactly as received
+    pub value: String,
+}
+
+thread_local! {
+    static COLLECTOR: RefCell<Option<Vec<UnknownValue>>> = const { RefCell::new(None) };
+}
+
+/// Restores the previous collector even if the closure panics
+struct Guard(Option<Option<Vec<UnknownValue>>>);
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        if let Some(prev) = self.0.take() {
+            COLLECTOR.with(|c| c.replace(prev));
+        }
+    }
+}
+
+/// Runs `f` and returns its output together with every unknown enum value
+/// parsed on the current thread while `f` was running.
+///
+/// Calls can be nested, values seen by an inner call are reported to the outer one as well.
+pub fn collect_unknowns<T, F>(f: F) -> (T, Vec<UnknownValue>)
+where
+    F: FnOnce() -> T,
+{
+    let mut guard = Guard(Some(COLLECTOR.with(|c| c.replace(Some(Vec::new())))));
+    let out = f();
+    let prev = guard.0.take().unwrap_or_default();
+    let unknowns = COLLECTOR.with(|c| c.replace(prev)).unwrap_or_default();
+
+    COLLECTOR.with(|c| {
+        if let Some(outer) = c.borrow_mut().as_mut() {
+            outer.extend(unknowns.iter().cloned());
+        }
+    });
+
+    (out, unknowns)
+}
+
+/// Records an unknown value if a collector is active. Empty strings are
+/// not reported as the API uses them for missing values.
+#[allow(dead_code)] // unused when every domain feature is disabled
+pub(crate) fn report(type_name: &'static str, value: &str) {
+    if value.is_empty() {
+        return;
+    }
+
+    COLLECTOR.with(|c| {
+        if let Some(unknowns) = c.borrow_mut().as_mut() {
+            unknowns.push(UnknownValue {
+                type_name,
+                value: value.to_string(),
+            });
+        }
+    });
+}
@@ -0,0 +1,306 @@
+//! GeoJSON output for models with a location
+//!
+//! Every field of a model becomes a property of its feature. Points are used for
+//! bus stops, taxi stands, carparks, bike parking, traffic images, incidents and
+//! VMS, lines for traffic speed bands and bus routes.
+//!
+//! `FeatureWriter` writes a `FeatureCollection` one feature at a time for large sets,
+//! `FeatureCollection` holds them in memory.
+//!
+//! ## Example
+//! ```rust
+//! use lta_models::bus::bus_stops::BusStopsResp;
+//! use lta_models::geojson::FeatureWriter;
+//!
+//! let stops: BusStopsResp =
+//!     serde_json::from_str(include_str!("../../dumped_data/bus_stops.json")).unwrap();
+//!
+//! let mut writer = FeatureWriter::new(Vec::new()).unwrap();
+//! for stop in &stops.value {
+//!     writer.write(stop).unwrap();
+//! }
+//! let json: serde_json::Value = serde_json::from_slice(&writer.finish().unwrap()).unwrap();
+//!
+//! assert_eq!(json["type"], "FeatureCollection");
+//! assert_eq!(json["features"][0]["geometry"]["type"], "Point");
+//! assert_eq!(json["features"][0]["properties"]["bus_stop_code"], stops.value[0].bus_stop_code);
+//! ```
+
+use lta_utils_commons::Coordinates;
+use serde::ser::{SerializeMap, SerializeSeq};
+use serde::{Serialize, Serializer};
+use serde_json::{Map, Value};
+use std::fmt::{self, Display, Formatter};
+use std::io::{self, Write};
+
+/// Error writing GeoJSON
+#[derive(Debug)]
+pub enum GeoJsonError {
+    Io(io::Error),
+    Json(serde_json::Error),
+}
+
+impl Display for GeoJsonError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            GeoJsonError::Io(e) => write!(f, "io error: {}", e),
+            GeoJsonError::Json(e) => write!(f, "json error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for GeoJsonError {}
+
+impl From<io::Error> for GeoJsonError {
+    fn from(e: io::Error) -> Self {
+        GeoJsonError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for GeoJsonError {
+    fn from(e: serde_json::Error) -> Self {
+        GeoJsonError::Json(e)
+    }
+}
+
+/// GeoJSON geometry, positions are written as `[long, lat]`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Geometry {
+    Point(Coordinates),
+    LineString(Vec<Coordinates>),
+}
+
+struct Position<'a>(&'a Coordinates);
+
+impl Serialize for Position<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        [self.0.long, self.0.lat].serialize(serializer)
+    }
+}
+
+impl Serialize for Geometry {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(2))?;
+        match self {
+            Geometry::Point(c) => {
+                map.serialize_entry("type", "Point")?;
+                map.serialize_entry("coordinates", &Position(c))?;
+            }
+            Geometry::LineString(line) => {
+                map.serialize_entry("type", "LineString")?;
+                map.serialize_entry("coordinates", &Positions(line))?;
+            }
+        }
+        map.end()
+    }
+}
+
+struct Positions<'a>(&'a [Coordinates]);
+
+impl Serialize for Positions<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.0.len()))?;
+        for c in self.0 {
+            seq.serialize_element(&Position(c))?;
+        }
+        seq.end()
+    }
+}
+
+/// A GeoJSON feature, `geometry` is `null` for records without a location
+#[derive(Debug, Clone, PartialEq)]
+pub struct Feature {
+    pub geometry: Option<Geometry>,
+    pub properties: Map<String, Value>,
+}
+
+impl Serialize for Feature {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(3))?;
+        map.serialize_entry("type", "Feature")?;
+        map.serialize_entry("geometry", &self.geometry)?;
+        map.serialize_entry("properties", &self.properties)?;
+        map.end()
+    }
+}
+
+/// Features held in memory
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct FeatureCollection {
+    pub features: Vec<Feature>,
+}
+
+impl FeatureCollection {
+    /// Features of every record
+    pub fn from_records<'a, T, I>(records: I) -> Result<Self, GeoJsonError>
+    where
+        T: ToFeature + 'a,
+        I: IntoIterator<Item = &'a T>,
+    {
+        let features = records
+            .into_iter()
+            .map(ToFeature::to_feature)
+            .collect::<Result<_, _>>()?;
+        Ok(FeatureCollection { features })
+    }
+}
+
+impl Serialize for FeatureCollection {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(2))?;
+        map.serialize_entry("type", "FeatureCollection")?;
+        map.serialize_entry("features", &self.features)?;
+        map.end()
+    }
+}
+
+/// A record that can be written as a GeoJSON feature
+pub trait ToFeature: Serialize {
+    fn geometry(&self) -> Option<Geometry>;
+
+    /// Feature with every serialised field as a property
+    fn to_feature(&self) -> Result<Feature, GeoJsonError> {
+        let properties = match serde_json::to_value(self)? {
+            Value::Object(map) => map,
+            other => {
+                let mut map = Map::new();
+                map.insert("value".to_string(), other);
+                map
+            }
+        };
+
+        Ok(Feature {
+            geometry: self.geometry(),
+            properties,
+        })
+    }
+}
+
+/// Point features for records with a `HasLocation`
+#[cfg(any(feature = "bus", feature = "taxi", feature = "traffic"))]
+macro_rules! point_features {
+    ($($record:ty),* $(,)?) => {
+        $(impl ToFeature for $record {
+            fn geometry(&self) -> Option<Geometry> {
+                lta_utils_commons::geo::HasLocation::coordinates(self).map(Geometry::Point)
+            }
+        })*
+    };
+}
+
+#[cfg(feature = "bus")]
+point_features!(crate::bus::bus_stops::BusStop);
+
+#[cfg(feature = "taxi")]
+point_features!(crate::taxi::taxi_stands::TaxiStand);
+
+#[cfg(feature = "traffic")]
+point_features!(
+    crate::traffic::carpark_avail::CarPark,
+    crate::traffic::bike_parking::BikeParking,
+    crate::traffic::traffic_images::TrafficImage,
+    crate::traffic::traffic_incidents::TrafficIncident,
+    crate::traffic::vms_emas::VMS,
+);
+
+#[cfg(feature = "traffic")]
+impl ToFeature for crate::traffic::traffic_speed_bands::TrafficSpeedBand {
+    fn geometry(&self) -> Option<Geometry> {
+        self.coord_start_end
+            .as_ref()
+            .map(|l| Geometry::LineString(vec![l.start, l.end]))
+    }
+}
+
+/// A bus route through its stops in order, for `bus_routes`
+#[cfg(feature = "bus")]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RouteLine {
+    pub service_no: String,
+    pub direction: u32,
+    pub operator: crate::bus_enums::Operator,
+
+    /// Bus stop codes in order
+    pub stops: Vec<u32>,
+
+    /// Length of the route in km
+    pub dist: f64,
+
+    /// Positions of the stops in order, stops missing from the network are left out
+    #[serde(skip)]
+    pub line: Vec<Coordinates>,
+}
+
+#[cfg(feature = "bus")]
+impl ToFeature for RouteLine {
+    fn geometry(&self) -> Option<Geometry> {
+        if self.line.len() < 2 {
+            return None;
+        }
+        Some(Geometry::LineString(self.line.clone()))
+    }
+}
+
+/// A line for every service and direction, through the stops that are in the network
+#[cfg(feature = "bus")]
+pub fn bus_routes(network: &crate::bus::network::BusNetwork) -> Vec<RouteLine> {
+    network
+        .service_dirs()
+        .into_iter()
+        .filter_map(|s| {
+            let route = network.route(&s.service_no, s.direction)?;
+            let first = route.first()?;
+            Some(RouteLine {
+                service_no: s.service_no.clone(),
+                direction: s.direction,
+                operator: first.operator.clone(),
+                stops: route.iter().map(|r| r.bus_stop_code).collect(),
+                dist: route.last()?.dist - first.dist,
+                line: route
+                    .iter()
+                    .filter_map(|r| network.stop(r.bus_stop_code))
+                    .map(|s| Coordinates::new(s.lat, s.long))
+                    .collect(),
+            })
+        })
+        .collect()
+}
+
+/// Writes a `FeatureCollection` one feature at a time
+#[derive(Debug)]
+pub struct FeatureWriter<W: Write> {
+    writer: W,
+    empty: bool,
+}
+
+impl<W: Write> FeatureWriter<W> {
+    /// Starts the collection
+    pub fn new(mut writer: W) -> Result<Self, GeoJsonError> {
+        writer.write_all(br#"{"type":"FeatureCollection","features":["#)?;
+        Ok(FeatureWriter {
+            writer,
+            empty: true,
+        })
+    }
+
+    pub fn write<T: ToFeature>(&mut self, record: &T) -> Result<(), GeoJsonError> {
+        self.write_feature(&record.to_feature()?)
+    }
+
+    pub fn write_feature(&mut self, feature: &Feature) -> Result<(), GeoJsonError> {
+        if !self.empty {
+            self.writer.write_all(b",")?;
+        }
+        self.empty = false;
+
+        serde_json::to_writer(&mut self.writer, feature)?;
+        Ok(())
+    }
+
+    /// Ends the collection and returns the writer
+    pub fn finish(mut self) -> Result<W, GeoJsonError> {
+        self.writer.write_all(b"]}")?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
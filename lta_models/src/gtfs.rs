@@ -0,0 +1,838 @@
+//! GTFS static feed from the bus datasets
+//!
+//! `GtfsExport` turns a `BusNetwork` into the tables of a GTFS feed, which can be
+//! checked with `GtfsFeed::validate` and written as a zip or a directory.
+//!
+//! DataMall has no trip timetables, so every service, direction and service day
+//! becomes one frequency based trip:
+//! - `stop_times.txt` follows the first bus of the day, with times taken from the
+//!   first bus timings at each stop and `shape_dist_traveled` from `BusRoute::dist` in km
+//! - `frequencies.txt` runs from the first to the last bus at the first stop, with the
+//!   headway of each `FreqBand` of the `BusService`
+//! - `calendar.txt` has the service ids `WD`, `SAT` and `SUN`, holidays are moved to
+//!   `SUN` in `calendar_dates.txt`
+//!
+//! Routes without a `BusService` are left out as they have no frequency.
+//!
+//! ## Example
+//! ```rust
+//! use lta_models::bus::network::BusNetwork;
+//! use lta_models::gtfs::GtfsExport;
+//! use lta_models::prelude::*;
+//! use lta_utils_commons::chrono::NaiveDate;
+//!
+//! let routes: BusRouteResp =
+//!     serde_json::from_str(include_str!("../../dumped_data/bus_route.json")).unwrap();
+//! let services: BusServiceResp =
+//!     serde_json::from_str(include_str!("../../dumped_data/bus_services.json")).unwrap();
+//! let network = BusNetwork::new(vec![], services.value, routes.value);
+//!
+//! let start = NaiveDate::from_ymd_opt(2019, 11, 1).unwrap();
+//! let end = NaiveDate::from_ymd_opt(2020, 10, 31).unwrap();
+//! let feed = GtfsExport::new(&network, start, end).build();
+//!
+//! // no stops were loaded so every stop time fails validation
+//! assert!(feed.validate().iter().all(|i| i.file == "stop_times.txt"));
+//! let zip = feed.write_zip(std::io::Cursor::new(Vec::new())).unwrap();
+//! ```
+
+use crate::bus::bus_routes::{BusRoute, ServiceDay};
+use crate::bus::bus_services::{BusService, FreqBand};
+use crate::bus::network::BusNetwork;
+use crate::bus_enums::{BusCategory, Operator};
+use lta_utils_commons::chrono::{Datelike, NaiveDate, NaiveTime, Timelike, Weekday};
+use serde::{Serialize, Serializer};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt::{self, Display, Formatter};
+use std::io::{self, Seek, Write};
+use std::path::Path;
+use zip::write::{FileOptions, ZipWriter};
+
+pub mod realtime;
+
+const SECS_PER_DAY: u32 = 24 * 3600;
+
+/// `stop_id` of a bus stop, the 5 digit bus stop code
+pub fn stop_id(bus_stop_code: u32) -> String {
+    format!("{:05}", bus_stop_code)
+}
+
+/// `route_id` of a bus service, shared by both directions
+pub fn route_id(service_no: &str) -> String {
+    service_no.to_string()
+}
+
+/// `service_id` in `calendar.txt` of a service day
+pub fn service_id(day: ServiceDay) -> &'static str {
+    match day {
+        ServiceDay::Weekday => "WD",
+        ServiceDay::Saturday => "SAT",
+        ServiceDay::SundayHoliday => "SUN",
+    }
+}
+
+/// `trip_id` of a service in one direction on a service day, e.g. `10_1_WD`
+pub fn trip_id(service_no: &str, direction: u32, day: ServiceDay) -> String {
+    format!("{}_{}_{}", service_no, direction, service_id(day))
+}
+
+/// Extended GTFS route type of a bus category
+pub fn route_type(category: &BusCategory) -> u16 {
+    match category {
+        BusCategory::Trunk => 700,
+        BusCategory::Express
+        | BusCategory::TwoTierFlatFare
+        | BusCategory::FlatFee
+        | BusCategory::FlatFareTwoDollar
+        | BusCategory::CityLink => 702,
+        BusCategory::Feeder | BusCategory::TownLink => 704,
+        BusCategory::NightService => 705,
+        BusCategory::Industrial | BusCategory::Unknown(_) => 3,
+    }
+}
+
+fn agency(operator: &Operator) -> Agency {
+    let (name, url) = match operator {
+        Operator::SBST => ("SBS Transit", "https://www.sbstransit.com.sg"),
+        Operator::SMRT => ("SMRT Buses", "https://www.smrt.com.sg"),
+        Operator::TTS => ("Tower Transit Singapore", "https://www.towertransit.sg"),
+        Operator::GAS => ("Go-Ahead Singapore", "https://www.go-aheadsingapore.com"),
+        Operator::Unknown(name) => (name.as_str(), "https://datamall.lta.gov.sg"),
+    };
+
+    Agency {
+        agency_id: operator.as_str().to_string(),
+        agency_name: name.to_string(),
+        agency_url: url.to_string(),
+        agency_timezone: "Asia/Singapore".to_string(),
+        agency_lang: Some("en".to_string()),
+    }
+}
+
+/// Time since midnight of the service day, can be past 24:00:00
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct GtfsTime(pub u32);
+
+impl GtfsTime {
+    pub fn from_hms(h: u32, m: u32, s: u32) -> Self {
+        GtfsTime(h * 3600 + m * 60 + s)
+    }
+
+    pub fn secs(self) -> u32 {
+        self.0
+    }
+}
+
+impl From<NaiveTime> for GtfsTime {
+    fn from(t: NaiveTime) -> Self {
+        GtfsTime(t.num_seconds_from_midnight())
+    }
+}
+
+impl Display for GtfsTime {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let s = self.0;
+        write!(f, "{:02}:{:02}:{:02}", s / 3600, s / 60 % 60, s % 60)
+    }
+}
+
+impl Serialize for GtfsTime {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+fn gtfs_date<S: Serializer>(date: &NaiveDate, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(&date.format("%Y%m%d"))
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Agency {
+    pub agency_id: String,
+    pub agency_name: String,
+    pub agency_url: String,
+    pub agency_timezone: String,
+    pub agency_lang: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Stop {
+    pub stop_id: String,
+    pub stop_code: String,
+    pub stop_name: String,
+    pub stop_desc: String,
+    pub stop_lat: f64,
+    pub stop_lon: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Route {
+    pub route_id: String,
+    pub agency_id: String,
+    pub route_short_name: String,
+    pub route_long_name: String,
+    pub route_desc: String,
+    pub route_type: u16,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Trip {
+    pub route_id: String,
+    pub service_id: String,
+    pub trip_id: String,
+    pub trip_headsign: String,
+
+    /// 0 for direction 1, 1 for direction 2
+    pub direction_id: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StopTime {
+    pub trip_id: String,
+    pub arrival_time: GtfsTime,
+    pub departure_time: GtfsTime,
+    pub stop_id: String,
+    pub stop_sequence: u32,
+
+    /// In km
+    pub shape_dist_traveled: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Frequency {
+    pub trip_id: String,
+    pub start_time: GtfsTime,
+    pub end_time: GtfsTime,
+    pub headway_secs: u32,
+    pub exact_times: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Calendar {
+    pub service_id: String,
+    pub monday: u8,
+    pub tuesday: u8,
+    pub wednesday: u8,
+    pub thursday: u8,
+    pub friday: u8,
+    pub saturday: u8,
+    pub sunday: u8,
+    #[serde(serialize_with = "gtfs_date")]
+    pub start_date: NaiveDate,
+    #[serde(serialize_with = "gtfs_date")]
+    pub end_date: NaiveDate,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CalendarDate {
+    pub service_id: String,
+    #[serde(serialize_with = "gtfs_date")]
+    pub date: NaiveDate,
+
+    /// 1 if service is added on `date`, 2 if removed
+    pub exception_type: u8,
+}
+
+/// Problem found by `GtfsFeed::validate`
+#[derive(Debug, Clone, PartialEq)]
+pub struct GtfsIssue {
+    pub file: &'static str,
+
+    /// Id of the offending row, e.g. the `trip_id`
+    pub id: String,
+    pub msg: String,
+}
+
+impl Display for GtfsIssue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} `{}`: {}", self.file, self.id, self.msg)
+    }
+}
+
+#[derive(Debug)]
+pub enum GtfsError {
+    Io(io::Error),
+    Csv(csv::Error),
+    Zip(zip::result::ZipError),
+}
+
+impl Display for GtfsError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            GtfsError::Io(e) => write!(f, "io error: {}", e),
+            GtfsError::Csv(e) => write!(f, "csv error: {}", e),
+            GtfsError::Zip(e) => write!(f, "zip error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for GtfsError {}
+
+impl From<io::Error> for GtfsError {
+    fn from(e: io::Error) -> Self {
+        GtfsError::Io(e)
+    }
+}
+
+impl From<csv::Error> for GtfsError {
+    fn from(e: csv::Error) -> Self {
+        GtfsError::Csv(e)
+    }
+}
+
+impl From<zip::result::ZipError> for GtfsError {
+    fn from(e: zip::result::ZipError) -> Self {
+        GtfsError::Zip(e)
+    }
+}
+
+/// Tables of a GTFS feed
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GtfsFeed {
+    pub agency: Vec<Agency>,
+    pub stops: Vec<Stop>,
+    pub routes: Vec<Route>,
+    pub trips: Vec<Trip>,
+    pub stop_times: Vec<StopTime>,
+    pub frequencies: Vec<Frequency>,
+    pub calendar: Vec<Calendar>,
+    pub calendar_dates: Vec<CalendarDate>,
+}
+
+/// Builds a `GtfsFeed` from a `BusNetwork`
+#[derive(Debug, Clone)]
+pub struct GtfsExport<'a> {
+    network: &'a BusNetwork,
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+    holidays: BTreeSet<NaiveDate>,
+    extended_route_types: bool,
+    bus_speed: f64,
+}
+
+impl<'a> GtfsExport<'a> {
+    /// Feed valid from `start_date` to `end_date`, both inclusive
+    pub fn new(network: &'a BusNetwork, start_date: NaiveDate, end_date: NaiveDate) -> Self {
+        GtfsExport {
+            network,
+            start_date,
+            end_date,
+            holidays: BTreeSet::new(),
+            extended_route_types: true,
+            bus_speed: 20.0,
+        }
+    }
+
+    /// Dates that run on Sunday timings
+    pub fn with_holidays<I>(mut self, holidays: I) -> Self
+    where
+        I: IntoIterator<Item = NaiveDate>,
+    {
+        self.holidays.extend(holidays);
+        self
+    }
+
+    /// Use extended route types from `route_type`, otherwise every route is `3` (bus).
+    /// On by default
+    pub fn extended_route_types(mut self, extended: bool) -> Self {
+        self.extended_route_types = extended;
+        self
+    }
+
+    /// Bus speed in km/h for stops without first bus timings, 20 by default
+    pub fn bus_speed(mut self, kmh: f64) -> Self {
+        self.bus_speed = kmh;
+        self
+    }
+
+    pub fn build(&self) -> GtfsFeed {
+        let mut feed = GtfsFeed::default();
+
+        let mut stops: Vec<_> = self.network.stops().collect();
+        stops.sort_by_key(|s| s.bus_stop_code);
+        feed.stops = stops
+            .into_iter()
+            .map(|s| Stop {
+                stop_id: stop_id(s.bus_stop_code),
+                stop_code: stop_id(s.bus_stop_code),
+                stop_name: if s.desc.is_empty() {
+                    s.road_name.clone()
+                } else {
+                    s.desc.clone()
+                },
+                stop_desc: s.road_name.clone(),
+                stop_lat: s.lat,
+                stop_lon: s.long,
+            })
+            .collect();
+
+        // lowest direction first, it describes the route
+        let mut by_route: BTreeMap<&str, Vec<&BusService>> = BTreeMap::new();
+        for service in self.network.services() {
+            by_route
+                .entry(service.service_no.as_str())
+                .or_default()
+                .push(service);
+        }
+
+        let mut operators = BTreeMap::new();
+        for (service_no, services) in by_route.iter_mut() {
+            services.sort_by_key(|s| s.no_direction);
+            let service = services[0];
+            operators.insert(service.operator.as_str(), agency(&service.operator));
+
+            feed.routes.push(Route {
+                route_id: route_id(service_no),
+                agency_id: service.operator.as_str().to_string(),
+                route_short_name: service_no.to_string(),
+                route_long_name: self.long_name(service),
+                route_desc: service.category.to_string(),
+                route_type: if self.extended_route_types {
+                    route_type(&service.category)
+                } else {
+                    3
+                },
+            });
+
+            for service in services.iter() {
+                for &day in ServiceDay::ALL.iter() {
+                    self.add_trip(&mut feed, service, day);
+                }
+            }
+        }
+        feed.agency = operators.into_values().collect();
+
+        let days: [(ServiceDay, [u8; 7]); 3] = [
+            (ServiceDay::Weekday, [1, 1, 1, 1, 1, 0, 0]),
+            (ServiceDay::Saturday, [0, 0, 0, 0, 0, 1, 0]),
+            (ServiceDay::SundayHoliday, [0, 0, 0, 0, 0, 0, 1]),
+        ];
+        for (day, on) in days.iter() {
+            feed.calendar.push(Calendar {
+                service_id: service_id(*day).to_string(),
+                monday: on[0],
+                tuesday: on[1],
+                wednesday: on[2],
+                thursday: on[3],
+                friday: on[4],
+                saturday: on[5],
+                sunday: on[6],
+                start_date: self.start_date,
+                end_date: self.end_date,
+            });
+        }
+
+        let holidays = self
+            .holidays
+            .range(self.start_date..=self.end_date)
+            .filter(|d| d.weekday() != Weekday::Sun);
+        for &date in holidays {
+            let removed = ServiceDay::of(date);
+            for (day, exception_type) in [(removed, 2), (ServiceDay::SundayHoliday, 1)].iter() {
+                feed.calendar_dates.push(CalendarDate {
+                    service_id: service_id(*day).to_string(),
+                    date,
+                    exception_type: *exception_type,
+                });
+            }
+        }
+
+        feed
+    }
+
+    fn stop_name(&self, bus_stop_code: u32) -> Option<&str> {
+        self.network
+            .stop(bus_stop_code)
+            .map(|s| s.desc.as_str())
+            .filter(|d| !d.is_empty())
+    }
+
+    fn long_name(&self, service: &BusService) -> String {
+        let origin = self.stop_name(service.origin_code);
+        let dest = self.stop_name(service.dest_code);
+        match (origin, dest) {
+            (Some(o), _) if service.origin_code == service.dest_code => match &service.loop_desc {
+                Some(l) if !l.is_empty() => format!("{} loop via {}", o, l),
+                _ => format!("{} loop", o),
+            },
+            (Some(o), Some(d)) => format!("{} - {}", o, d),
+            _ => String::new(),
+        }
+    }
+
+    fn add_trip(&self, feed: &mut GtfsFeed, service: &BusService, day: ServiceDay) {
+        let route = match self
+            .network
+            .route(&service.service_no, service.no_direction)
+        {
+            Some(route) if !route.is_empty() => route,
+            _ => return,
+        };
+
+        let (first, last) = match route[0].timings(day) {
+            (Some(first), Some(last)) => (GtfsTime::from(first), GtfsTime::from(last)),
+            _ => return,
+        };
+        let start = first.secs();
+        let end = if last.secs() < start {
+            last.secs() + SECS_PER_DAY
+        } else {
+            last.secs()
+        };
+
+        let trip_id = trip_id(&service.service_no, service.no_direction, day);
+        let headsign = route
+            .last()
+            .and_then(|r| self.stop_name(r.bus_stop_code))
+            .unwrap_or_default();
+
+        feed.trips.push(Trip {
+            route_id: route_id(&service.service_no),
+            service_id: service_id(day).to_string(),
+            trip_id: trip_id.clone(),
+            trip_headsign: headsign.to_string(),
+            direction_id: if service.no_direction == 2 { 1 } else { 0 },
+        });
+
+        let mut offset = 0;
+        for (i, stop) in route.iter().enumerate() {
+            if i > 0 {
+                offset = offset.max(self.offset(&route[0], stop, day, start));
+            }
+
+            let at = GtfsTime(start + offset);
+            feed.stop_times.push(StopTime {
+                trip_id: trip_id.clone(),
+                arrival_time: at,
+                departure_time: at,
+                stop_id: stop_id(stop.bus_stop_code),
+                stop_sequence: stop.stop_seq,
+                shape_dist_traveled: stop.dist,
+            });
+        }
+
+        let mut frequencies: Vec<Frequency> = Vec::new();
+        for (from, to, band) in bands(start, end) {
+            let headway_secs = match service.freq(band).headway_mins() {
+                Some(mins) if mins > 0 => mins * 60,
+                _ => continue,
+            };
+
+            match frequencies.last_mut() {
+                Some(f) if f.end_time.secs() == from && f.headway_secs == headway_secs => {
+                    f.end_time = GtfsTime(to);
+                }
+                _ => frequencies.push(Frequency {
+                    trip_id: trip_id.clone(),
+                    start_time: GtfsTime(from),
+                    end_time: GtfsTime(to),
+                    headway_secs,
+                    exact_times: 0,
+                }),
+            }
+        }
+        feed.frequencies.extend(frequencies);
+    }
+
+    /// Seconds from the first stop to `stop` on the first bus
+    fn offset(&self, origin: &BusRoute, stop: &BusRoute, day: ServiceDay, start: u32) -> u32 {
+        match stop.timings(day).0 {
+            Some(t) => {
+                let secs = GtfsTime::from(t).secs();
+                if secs >= start {
+                    secs - start
+                } else {
+                    secs + SECS_PER_DAY - start
+                }
+            }
+            None => {
+                let km = (stop.dist - origin.dist).max(0.0);
+                (km / self.bus_speed * 3600.0).round() as u32
+            }
+        }
+    }
+}
+
+/// Splits `start..end` where a `FreqBand` starts
+fn bands(start: u32, end: u32) -> Vec<(u32, u32, FreqBand)> {
+    let mut cuts = vec![start, end];
+    for day in 0..=end / SECS_PER_DAY {
+        for band in FreqBand::ALL.iter() {
+            let t = day * SECS_PER_DAY + GtfsTime::from(band.start()).secs();
+            if t > start && t < end {
+                cuts.push(t);
+            }
+        }
+    }
+    cuts.sort_unstable();
+    cuts.dedup();
+
+    cuts.windows(2)
+        .map(|w| {
+            let t = NaiveTime::from_num_seconds_from_midnight_opt(w[0] % SECS_PER_DAY, 0)
+                .expect("seconds within a day");
+            (w[0], w[1], FreqBand::at(t))
+        })
+        .collect()
+}
+
+fn to_csv<T: Serialize>(rows: &[T]) -> Result<Vec<u8>, GtfsError> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    for row in rows {
+        writer.serialize(row)?;
+    }
+    writer
+        .into_inner()
+        .map_err(|e| GtfsError::Io(e.into_error()))
+}
+
+impl GtfsFeed {
+    /// Contents of each file of the feed, empty optional files are left out
+    pub fn files(&self) -> Result<Vec<(&'static str, Vec<u8>)>, GtfsError> {
+        let mut files = vec![
+            ("agency.txt", to_csv(&self.agency)?),
+            ("stops.txt", to_csv(&self.stops)?),
+            ("routes.txt", to_csv(&self.routes)?),
+            ("trips.txt", to_csv(&self.trips)?),
+            ("stop_times.txt", to_csv(&self.stop_times)?),
+            ("calendar.txt", to_csv(&self.calendar)?),
+        ];
+        if !self.frequencies.is_empty() {
+            files.push(("frequencies.txt", to_csv(&self.frequencies)?));
+        }
+        if !self.calendar_dates.is_empty() {
+            files.push(("calendar_dates.txt", to_csv(&self.calendar_dates)?));
+        }
+
+        Ok(files)
+    }
+
+    /// Writes the feed as a zip and returns the writer
+    pub fn write_zip<W: Write + Seek>(&self, writer: W) -> Result<W, GtfsError> {
+        let mut zip = ZipWriter::new(writer);
+        for (name, data) in self.files()? {
+            zip.start_file(name, FileOptions::default())?;
+            zip.write_all(&data)?;
+        }
+
+        Ok(zip.finish()?)
+    }
+
+    /// Writes each file of the feed into `dir`, creating it if needed
+    pub fn write_dir<P: AsRef<Path>>(&self, dir: P) -> Result<(), GtfsError> {
+        let dir = dir.as_ref();
+        std::fs::create_dir_all(dir)?;
+        for (name, data) in self.files()? {
+            std::fs::write(dir.join(name), data)?;
+        }
+
+        Ok(())
+    }
+
+    /// Checks required fields, references between files and the ordering of stop times
+    pub fn validate(&self) -> Vec<GtfsIssue> {
+        let mut issues = Vec::new();
+        let mut issue = |file, id: &str, msg: String| {
+            issues.push(GtfsIssue {
+                file,
+                id: id.to_string(),
+                msg,
+            })
+        };
+
+        if self.agency.is_empty() {
+            issue("agency.txt", "", "no agency".to_string());
+        }
+        let mut agencies = HashSet::new();
+        for a in &self.agency {
+            if !agencies.insert(a.agency_id.as_str()) {
+                issue(
+                    "agency.txt",
+                    &a.agency_id,
+                    "duplicate agency_id".to_string(),
+                );
+            }
+            if a.agency_name.is_empty() || a.agency_url.is_empty() || a.agency_timezone.is_empty() {
+                issue(
+                    "agency.txt",
+                    &a.agency_id,
+                    "missing required field".to_string(),
+                );
+            }
+        }
+
+        let mut stops = HashSet::new();
+        for s in &self.stops {
+            if !stops.insert(s.stop_id.as_str()) {
+                issue("stops.txt", &s.stop_id, "duplicate stop_id".to_string());
+            }
+            if s.stop_name.is_empty() {
+                issue("stops.txt", &s.stop_id, "missing stop_name".to_string());
+            }
+            if !(-90.0..=90.0).contains(&s.stop_lat) || !(-180.0..=180.0).contains(&s.stop_lon) {
+                issue(
+                    "stops.txt",
+                    &s.stop_id,
+                    format!("invalid coordinates {}, {}", s.stop_lat, s.stop_lon),
+                );
+            }
+        }
+
+        let mut routes = HashSet::new();
+        for r in &self.routes {
+            if !routes.insert(r.route_id.as_str()) {
+                issue("routes.txt", &r.route_id, "duplicate route_id".to_string());
+            }
+            if !agencies.contains(r.agency_id.as_str()) {
+                issue(
+                    "routes.txt",
+                    &r.route_id,
+                    format!("unknown agency_id `{}`", r.agency_id),
+                );
+            }
+            if r.route_short_name.is_empty() && r.route_long_name.is_empty() {
+                issue("routes.txt", &r.route_id, "missing route name".to_string());
+            }
+        }
+
+        let mut services = HashSet::new();
+        for c in &self.calendar {
+            if !services.insert(c.service_id.as_str()) {
+                issue(
+                    "calendar.txt",
+                    &c.service_id,
+                    "duplicate service_id".to_string(),
+                );
+            }
+            if c.start_date > c.end_date {
+                issue(
+                    "calendar.txt",
+                    &c.service_id,
+                    "start_date after end_date".to_string(),
+                );
+            }
+        }
+        for c in &self.calendar_dates {
+            if !services.contains(c.service_id.as_str()) {
+                issue(
+                    "calendar_dates.txt",
+                    &c.service_id,
+                    "unknown service_id".to_string(),
+                );
+            }
+            if c.exception_type != 1 && c.exception_type != 2 {
+                issue(
+                    "calendar_dates.txt",
+                    &c.service_id,
+                    format!("invalid exception_type {}", c.exception_type),
+                );
+            }
+        }
+
+        let mut trips = HashSet::new();
+        for t in &self.trips {
+            if !trips.insert(t.trip_id.as_str()) {
+                issue("trips.txt", &t.trip_id, "duplicate trip_id".to_string());
+            }
+            if !routes.contains(t.route_id.as_str()) {
+                issue(
+                    "trips.txt",
+                    &t.trip_id,
+                    format!("unknown route_id `{}`", t.route_id),
+                );
+            }
+            if !services.contains(t.service_id.as_str()) {
+                issue(
+                    "trips.txt",
+                    &t.trip_id,
+                    format!("unknown service_id `{}`", t.service_id),
+                );
+            }
+        }
+
+        let mut by_trip: HashMap<&str, Vec<&StopTime>> = HashMap::new();
+        for st in &self.stop_times {
+            if !trips.contains(st.trip_id.as_str()) {
+                issue("stop_times.txt", &st.trip_id, "unknown trip_id".to_string());
+            }
+            if !stops.contains(st.stop_id.as_str()) {
+                issue(
+                    "stop_times.txt",
+                    &st.trip_id,
+                    format!("unknown stop_id `{}`", st.stop_id),
+                );
+            }
+            if st.departure_time < st.arrival_time {
+                issue(
+                    "stop_times.txt",
+                    &st.trip_id,
+                    format!("departs before arriving at stop {}", st.stop_sequence),
+                );
+            }
+            by_trip.entry(st.trip_id.as_str()).or_default().push(st);
+        }
+
+        for t in &self.trips {
+            let times = by_trip.remove(t.trip_id.as_str()).unwrap_or_default();
+            if times.len() < 2 {
+                issue(
+                    "stop_times.txt",
+                    &t.trip_id,
+                    "fewer than 2 stop times".to_string(),
+                );
+            }
+
+            for w in times.windows(2) {
+                if w[1].stop_sequence <= w[0].stop_sequence {
+                    issue(
+                        "stop_times.txt",
+                        &t.trip_id,
+                        format!("stop_sequence {} does not increase", w[1].stop_sequence),
+                    );
+                }
+                if w[1].arrival_time < w[0].departure_time {
+                    issue(
+                        "stop_times.txt",
+                        &t.trip_id,
+                        format!("time goes back at stop {}", w[1].stop_sequence),
+                    );
+                }
+                if w[1].shape_dist_traveled < w[0].shape_dist_traveled {
+                    issue(
+                        "stop_times.txt",
+                        &t.trip_id,
+                        format!(
+                            "shape_dist_traveled goes back at stop {}",
+                            w[1].stop_sequence
+                        ),
+                    );
+                }
+            }
+        }
+
+        for f in &self.frequencies {
+            if !trips.contains(f.trip_id.as_str()) {
+                issue("frequencies.txt", &f.trip_id, "unknown trip_id".to_string());
+            }
+            if f.start_time >= f.end_time {
+                issue(
+                    "frequencies.txt",
+                    &f.trip_id,
+                    format!(
+                        "start_time {} is not before end_time {}",
+                        f.start_time, f.end_time
+                    ),
+                );
+            }
+            if f.headway_secs == 0 {
+                issue(
+                    "frequencies.txt",
+                    &f.trip_id,
+                    "headway_secs is 0".to_string(),
+                );
+            }
+        }
+
+        issues
+    }
+}
@@ -0,0 +1,574 @@
+//! GTFS-Realtime TripUpdates and VehiclePositions from bus arrivals
+//!
+//! `RealtimeFeed` matches every `NextBus` in a set of `BusArrivalResp` to a
+//! frequency based trip of a `GtfsFeed`, so the realtime feed uses the same
+//! `trip_id`, `route_id` and `stop_id` as the static feed exported from the same data.
+//!
+//! A bus is matched on its service, origin and destination. The start time of
+//! the trip is estimated from the arrival and the stop times of the static trip,
+//! and arrivals of the same bus at several polled stops are merged into one trip
+//! update. Buses that match no trip are left out.
+//!
+//! Messages can be encoded with `prost::Message`, re-exported here as `Message`.
+//!
+//! ## Example
+//! ```rust
+//! use lta_models::bus::network::BusNetwork;
+//! use lta_models::gtfs::realtime::{Message, RealtimeFeed};
+//! use lta_models::gtfs::GtfsExport;
+//! use lta_models::prelude::*;
+//! use lta_utils_commons::chrono::NaiveDate;
+//! use lta_utils_commons::sg_time;
+//!
+//! let routes: BusRouteResp =
+//!     serde_json::from_str(include_str!("../../../dumped_data/bus_route.json")).unwrap();
+//! let services: BusServiceResp =
+//!     serde_json::from_str(include_str!("../../../dumped_data/bus_services.json")).unwrap();
+//! let arrivals: RawBusArrivalResp =
+//!     serde_json::from_str(include_str!("../../../dumped_data/bus_arrival.json")).unwrap();
+//!
+//! let network = BusNetwork::new(vec![], services.value, routes.value);
+//! let start = NaiveDate::from_ymd_opt(2019, 11, 1).unwrap();
+//! let end = NaiveDate::from_ymd_opt(2020, 10, 31).unwrap();
+//! let feed = GtfsExport::new(&network, start, end).build();
+//!
+//! let realtime = RealtimeFeed::new(&feed);
+//! let msg = realtime.trip_updates(&[arrivals.into()], sg_time::now());
+//! let bytes = msg.encode_to_vec();
+//! assert_eq!(msg, lta_models::gtfs::realtime::FeedMessage::decode(&bytes[..]).unwrap());
+//! ```
+
+use crate::bus::bus_arrival::{BusArrivalResp, NextBus};
+use crate::bus::bus_routes::ServiceDay;
+use crate::bus_enums::BusLoad;
+use crate::gtfs::{route_id, service_id, stop_id, GtfsFeed, GtfsTime};
+use lta_utils_commons::chrono::{NaiveDate, NaiveTime};
+use lta_utils_commons::sg_time::{self, SgDateTime};
+use std::collections::{HashMap, HashSet};
+
+pub use prost::Message;
+
+/// Version of the GTFS-Realtime spec of the messages
+pub const GTFS_REALTIME_VERSION: &str = "2.0";
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct FeedMessage {
+    #[prost(message, required, tag = "1")]
+    pub header: FeedHeader,
+    #[prost(message, repeated, tag = "2")]
+    pub entity: Vec<FeedEntity>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct FeedHeader {
+    #[prost(string, required, tag = "1")]
+    pub gtfs_realtime_version: String,
+    #[prost(enumeration = "Incrementality", optional, tag = "2")]
+    pub incrementality: Option<i32>,
+
+    /// POSIX time
+    #[prost(uint64, optional, tag = "3")]
+    pub timestamp: Option<u64>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+#[repr(i32)]
+pub enum Incrementality {
+    FullDataset = 0,
+    Differential = 1,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct FeedEntity {
+    #[prost(string, required, tag = "1")]
+    pub id: String,
+    #[prost(bool, optional, tag = "2")]
+    pub is_deleted: Option<bool>,
+    #[prost(message, optional, tag = "3")]
+    pub trip_update: Option<TripUpdate>,
+    #[prost(message, optional, tag = "4")]
+    pub vehicle: Option<VehiclePosition>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct TripUpdate {
+    #[prost(message, required, tag = "1")]
+    pub trip: TripDescriptor,
+    #[prost(message, repeated, tag = "2")]
+    pub stop_time_update: Vec<StopTimeUpdate>,
+    #[prost(message, optional, tag = "3")]
+    pub vehicle: Option<VehicleDescriptor>,
+    #[prost(uint64, optional, tag = "4")]
+    pub timestamp: Option<u64>,
+    #[prost(int32, optional, tag = "5")]
+    pub delay: Option<i32>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct StopTimeEvent {
+    #[prost(int32, optional, tag = "1")]
+    pub delay: Option<i32>,
+
+    /// POSIX time
+    #[prost(int64, optional, tag = "2")]
+    pub time: Option<i64>,
+    #[prost(int32, optional, tag = "3")]
+    pub uncertainty: Option<i32>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct StopTimeUpdate {
+    #[prost(uint32, optional, tag = "1")]
+    pub stop_sequence: Option<u32>,
+    #[prost(message, optional, tag = "2")]
+    pub arrival: Option<StopTimeEvent>,
+    #[prost(message, optional, tag = "3")]
+    pub departure: Option<StopTimeEvent>,
+    #[prost(string, optional, tag = "4")]
+    pub stop_id: Option<String>,
+    #[prost(enumeration = "StopScheduleRelationship", optional, tag = "5")]
+    pub schedule_relationship: Option<i32>,
+}
+
+/// `StopTimeUpdate.ScheduleRelationship`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+#[repr(i32)]
+pub enum StopScheduleRelationship {
+    Scheduled = 0,
+    Skipped = 1,
+    NoData = 2,
+    Unscheduled = 3,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct TripDescriptor {
+    #[prost(string, optional, tag = "1")]
+    pub trip_id: Option<String>,
+
+    /// `HH:MM:SS`
+    #[prost(string, optional, tag = "2")]
+    pub start_time: Option<String>,
+
+    /// `YYYYMMDD`
+    #[prost(string, optional, tag = "3")]
+    pub start_date: Option<String>,
+    #[prost(enumeration = "TripScheduleRelationship", optional, tag = "4")]
+    pub schedule_relationship: Option<i32>,
+    #[prost(string, optional, tag = "5")]
+    pub route_id: Option<String>,
+    #[prost(uint32, optional, tag = "6")]
+    pub direction_id: Option<u32>,
+}
+
+/// `TripDescriptor.ScheduleRelationship`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+#[repr(i32)]
+pub enum TripScheduleRelationship {
+    Scheduled = 0,
+    Added = 1,
+    Unscheduled = 2,
+    Canceled = 3,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct VehicleDescriptor {
+    #[prost(string, optional, tag = "1")]
+    pub id: Option<String>,
+    #[prost(string, optional, tag = "2")]
+    pub label: Option<String>,
+    #[prost(string, optional, tag = "3")]
+    pub license_plate: Option<String>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct VehiclePosition {
+    #[prost(message, optional, tag = "1")]
+    pub trip: Option<TripDescriptor>,
+    #[prost(message, optional, tag = "2")]
+    pub position: Option<Position>,
+    #[prost(uint32, optional, tag = "3")]
+    pub current_stop_sequence: Option<u32>,
+    #[prost(uint64, optional, tag = "5")]
+    pub timestamp: Option<u64>,
+    #[prost(string, optional, tag = "7")]
+    pub stop_id: Option<String>,
+    #[prost(message, optional, tag = "8")]
+    pub vehicle: Option<VehicleDescriptor>,
+    #[prost(enumeration = "OccupancyStatus", optional, tag = "9")]
+    pub occupancy_status: Option<i32>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Position {
+    #[prost(float, required, tag = "1")]
+    pub latitude: f32,
+    #[prost(float, required, tag = "2")]
+    pub longitude: f32,
+    #[prost(float, optional, tag = "3")]
+    pub bearing: Option<f32>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+#[repr(i32)]
+pub enum OccupancyStatus {
+    Empty = 0,
+    ManySeatsAvailable = 1,
+    FewSeatsAvailable = 2,
+    StandingRoomOnly = 3,
+    CrushedStandingRoomOnly = 4,
+    Full = 5,
+    NotAcceptingPassengers = 6,
+    NoDataAvailable = 7,
+    NotBoardable = 8,
+}
+
+impl From<&BusLoad> for OccupancyStatus {
+    fn from(load: &BusLoad) -> Self {
+        match load {
+            BusLoad::SeatsAvailable => OccupancyStatus::ManySeatsAvailable,
+            BusLoad::StandingAvailable => OccupancyStatus::StandingRoomOnly,
+            BusLoad::LimitedStanding => OccupancyStatus::CrushedStandingRoomOnly,
+            BusLoad::Unknown(_) => OccupancyStatus::NoDataAvailable,
+        }
+    }
+}
+
+/// Stops of a static trip
+#[derive(Debug, Clone)]
+struct Pattern {
+    trip_id: String,
+    direction_id: u8,
+    origin: String,
+    dest: String,
+
+    /// `stop_id`, `stop_sequence` and seconds from the first stop
+    stops: Vec<(String, u32, u32)>,
+
+    /// First and last departure from the first stop
+    window: (u32, u32),
+}
+
+/// A bus on one run of a trip
+#[derive(Debug, Clone)]
+struct Instance<'p> {
+    pattern: &'p Pattern,
+    route_id: String,
+    date: NaiveDate,
+
+    /// Estimated departure from the first stop, seconds from midnight of `date`
+    start: i64,
+
+    /// `stop_sequence`, `stop_id` and arrival
+    arrivals: Vec<(u32, String, SgDateTime)>,
+    position: Option<(f64, f64)>,
+    load: Option<BusLoad>,
+}
+
+impl Instance<'_> {
+    fn id(&self) -> String {
+        format!(
+            "{}_{}_{}",
+            self.pattern.trip_id,
+            self.date.format("%Y%m%d"),
+            GtfsTime(self.start.max(0) as u32)
+                .to_string()
+                .replace(':', "")
+        )
+    }
+
+    fn descriptor(&self) -> TripDescriptor {
+        TripDescriptor {
+            trip_id: Some(self.pattern.trip_id.clone()),
+            start_time: Some(GtfsTime(self.start.max(0) as u32).to_string()),
+            start_date: Some(self.date.format("%Y%m%d").to_string()),
+            schedule_relationship: Some(TripScheduleRelationship::Scheduled as i32),
+            route_id: Some(self.route_id.clone()),
+            direction_id: Some(u32::from(self.pattern.direction_id)),
+        }
+    }
+}
+
+/// Converts bus arrivals into GTFS-Realtime messages lined up with a static `GtfsFeed`
+#[derive(Debug, Clone)]
+pub struct RealtimeFeed {
+    /// Keyed by `route_id` and `service_id`
+    patterns: HashMap<(String, String), Vec<Pattern>>,
+    holidays: HashSet<NaiveDate>,
+    tolerance: i64,
+    slack: i64,
+}
+
+impl RealtimeFeed {
+    pub fn new(feed: &GtfsFeed) -> Self {
+        let mut stop_times: HashMap<&str, Vec<_>> = HashMap::new();
+        for st in &feed.stop_times {
+            stop_times.entry(st.trip_id.as_str()).or_default().push(st);
+        }
+
+        let mut windows: HashMap<&str, (u32, u32)> = HashMap::new();
+        for f in &feed.frequencies {
+            let w = windows
+                .entry(f.trip_id.as_str())
+                .or_insert((f.start_time.secs(), f.end_time.secs()));
+            w.0 = w.0.min(f.start_time.secs());
+            w.1 = w.1.max(f.end_time.secs());
+        }
+
+        let mut patterns: HashMap<_, Vec<_>> = HashMap::new();
+        for trip in &feed.trips {
+            let mut times = match stop_times.remove(trip.trip_id.as_str()) {
+                Some(times) if !times.is_empty() => times,
+                _ => continue,
+            };
+            times.sort_by_key(|st| st.stop_sequence);
+
+            let first = times[0].arrival_time.secs();
+            let window = windows
+                .get(trip.trip_id.as_str())
+                .copied()
+                .unwrap_or((first, first));
+
+            patterns
+                .entry((trip.route_id.clone(), trip.service_id.clone()))
+                .or_default()
+                .push(Pattern {
+                    trip_id: trip.trip_id.clone(),
+                    direction_id: trip.direction_id,
+                    origin: times[0].stop_id.clone(),
+                    dest: times[times.len() - 1].stop_id.clone(),
+                    stops: times
+                        .iter()
+                        .map(|st| {
+                            let offset = st.arrival_time.secs().saturating_sub(first);
+                            (st.stop_id.clone(), st.stop_sequence, offset)
+                        })
+                        .collect(),
+                    window,
+                });
+        }
+
+        RealtimeFeed {
+            patterns,
+            holidays: HashSet::new(),
+            tolerance: 180,
+            slack: 1800,
+        }
+    }
+
+    /// Dates that run on Sunday timings, same as given to `GtfsExport::with_holidays`
+    pub fn with_holidays<I>(mut self, holidays: I) -> Self
+    where
+        I: IntoIterator<Item = NaiveDate>,
+    {
+        self.holidays.extend(holidays);
+        self
+    }
+
+    /// Arrivals whose estimated start times are this many seconds apart are taken to be
+    /// the same bus, 180 by default
+    pub fn tolerance(mut self, secs: u32) -> Self {
+        self.tolerance = i64::from(secs);
+        self
+    }
+
+    /// TripUpdates with the predicted arrival of each bus at each polled stop
+    pub fn trip_updates(&self, arrivals: &[BusArrivalResp], timestamp: SgDateTime) -> FeedMessage {
+        let entity = self
+            .instances(arrivals)
+            .into_iter()
+            .map(|i| {
+                let stop_time_update = i
+                    .arrivals
+                    .iter()
+                    .map(|(seq, stop, at)| StopTimeUpdate {
+                        stop_sequence: Some(*seq),
+                        arrival: Some(StopTimeEvent {
+                            time: Some(at.timestamp()),
+                            ..StopTimeEvent::default()
+                        }),
+                        departure: None,
+                        stop_id: Some(stop.clone()),
+                        schedule_relationship: Some(StopScheduleRelationship::Scheduled as i32),
+                    })
+                    .collect();
+
+                FeedEntity {
+                    id: i.id(),
+                    is_deleted: None,
+                    trip_update: Some(TripUpdate {
+                        trip: i.descriptor(),
+                        stop_time_update,
+                        vehicle: None,
+                        timestamp: Some(timestamp.timestamp() as u64),
+                        delay: None,
+                    }),
+                    vehicle: None,
+                }
+            })
+            .collect();
+
+        message(entity, timestamp)
+    }
+
+    /// VehiclePositions of buses with a known position, with occupancy from `BusLoad`
+    pub fn vehicle_positions(
+        &self,
+        arrivals: &[BusArrivalResp],
+        timestamp: SgDateTime,
+    ) -> FeedMessage {
+        let entity = self
+            .instances(arrivals)
+            .into_iter()
+            .filter_map(|i| {
+                let (lat, long) = i.position?;
+                Some(FeedEntity {
+                    id: i.id(),
+                    is_deleted: None,
+                    trip_update: None,
+                    vehicle: Some(VehiclePosition {
+                        trip: Some(i.descriptor()),
+                        position: Some(Position {
+                            latitude: lat as f32,
+                            longitude: long as f32,
+                            bearing: None,
+                        }),
+                        current_stop_sequence: None,
+                        timestamp: Some(timestamp.timestamp() as u64),
+                        stop_id: None,
+                        vehicle: None,
+                        occupancy_status: i.load.as_ref().map(|l| OccupancyStatus::from(l) as i32),
+                    }),
+                })
+            })
+            .collect();
+
+        message(entity, timestamp)
+    }
+
+    fn service_day(&self, date: NaiveDate) -> ServiceDay {
+        if self.holidays.contains(&date) {
+            ServiceDay::SundayHoliday
+        } else {
+            ServiceDay::of(date)
+        }
+    }
+
+    /// Trip, service date, estimated start and `stop_sequence` of a bus arriving at a stop
+    fn find(
+        &self,
+        service_no: &str,
+        bus_stop: &str,
+        bus: &NextBus,
+    ) -> Option<(&Pattern, NaiveDate, i64, u32)> {
+        let origin = stop_id(bus.origin_code);
+        let dest = stop_id(bus.dest_code);
+        let arrival_date = bus.est_arrival.date_naive();
+
+        // buses after midnight can still be on the previous service day
+        let dates = [Some(arrival_date), arrival_date.pred_opt()];
+        for date in dates.iter().flatten() {
+            let key = (
+                route_id(service_no),
+                service_id(self.service_day(*date)).to_string(),
+            );
+            let midnight = sg_time::at(*date, NaiveTime::MIN);
+            let secs = (bus.est_arrival - midnight).num_seconds();
+
+            for pattern in self.patterns.get(&key).into_iter().flatten() {
+                if pattern.origin != origin || pattern.dest != dest {
+                    continue;
+                }
+
+                let visit = bus.visit_no.max(1) as usize;
+                let stop = pattern
+                    .stops
+                    .iter()
+                    .filter(|(id, _, _)| id == bus_stop)
+                    .nth(visit - 1);
+                let (_, seq, offset) = match stop {
+                    Some(stop) => stop,
+                    None => continue,
+                };
+
+                let start = secs - i64::from(*offset);
+                let (first, last) = pattern.window;
+                if start >= i64::from(first) - self.slack && start <= i64::from(last) + self.slack {
+                    return Some((pattern, *date, start, *seq));
+                }
+            }
+        }
+
+        None
+    }
+
+    fn instances(&self, arrivals: &[BusArrivalResp]) -> Vec<Instance<'_>> {
+        let mut instances: Vec<Instance> = Vec::new();
+
+        for resp in arrivals {
+            let bus_stop = stop_id(resp.bus_stop_code);
+            for service in &resp.services {
+                for bus in service.next_bus.iter().flatten() {
+                    let (pattern, date, start, seq) =
+                        match self.find(&service.service_no, &bus_stop, bus) {
+                            Some(found) => found,
+                            None => continue,
+                        };
+
+                    let existing = instances.iter_mut().find(|i| {
+                        i.pattern.trip_id == pattern.trip_id
+                            && i.date == date
+                            && (i.start - start).abs() <= self.tolerance
+                    });
+                    let instance = match existing {
+                        Some(i) => {
+                            i.start = i.start.min(start);
+                            i
+                        }
+                        None => {
+                            instances.push(Instance {
+                                pattern,
+                                route_id: route_id(&service.service_no),
+                                date,
+                                start,
+                                arrivals: Vec::new(),
+                                position: None,
+                                load: None,
+                            });
+                            instances.last_mut().expect("just pushed")
+                        }
+                    };
+
+                    if !instance.arrivals.iter().any(|(s, _, _)| *s == seq) {
+                        instance
+                            .arrivals
+                            .push((seq, bus_stop.clone(), bus.est_arrival));
+                    }
+                    // 0, 0 when the position is not known
+                    if instance.position.is_none() && (bus.lat != 0.0 || bus.long != 0.0) {
+                        instance.position = Some((bus.lat, bus.long));
+                    }
+                    if instance.load.is_none() {
+                        instance.load = Some(bus.load.clone());
+                    }
+                }
+            }
+        }
+
+        for i in instances.iter_mut() {
+            i.arrivals.sort_by_key(|(seq, _, _)| *seq);
+        }
+        instances.sort_by_key(|i| i.id());
+        instances
+    }
+}
+
+fn message(entity: Vec<FeedEntity>, timestamp: SgDateTime) -> FeedMessage {
+    FeedMessage {
+        header: FeedHeader {
+            gtfs_realtime_version: GTFS_REALTIME_VERSION.to_string(),
+            incrementality: Some(Incrementality::FullDataset as i32),
+            timestamp: Some(timestamp.timestamp() as u64),
+        },
+        entity,
+    }
+}
@@ -1,21 +1,78 @@
 //! Data Structures for lta-rs
+//!
+//! Each domain is behind a feature of the same name, all enabled by default:
+//! `bus`, `traffic`, `taxi`, `train`, `crowd`
+//!
+//! The `calendar` feature adds Singapore public holidays and day types.
+//! The `spatial` feature adds an R-tree over records with a location, `bus` enables it.
+//! The `sync` feature adds diffing two downloads of the static datasets.
+//! The `gtfs` feature adds exporting the bus datasets as a GTFS feed.
+//! The `geo-types` feature adds conversions to `geo_types`, e.g. bus stops to a `MultiPoint`.
+//! The `geojson` feature adds GeoJSON output for models with a location.
+//! The `csv-io` feature adds CSV output and input for every model.
+//! The `arrow` feature adds Arrow record batches and Parquet files of every model.
+//! The `sqlite` feature adds a local SQLite snapshot of the static and realtime datasets,
+//! it enables `sync`.
 
+#[macro_use]
+mod macros;
+
+#[cfg(feature = "arrow")]
+pub mod arrow;
+#[cfg(feature = "bus")]
 pub mod bus;
+#[cfg(feature = "bus")]
 pub mod bus_enums;
+#[cfg(feature = "calendar")]
+pub mod calendar;
+#[cfg(feature = "crowd")]
 pub mod crowd;
+#[cfg(feature = "csv-io")]
+pub mod csv_io;
+pub mod diagnostics;
+#[cfg(feature = "geojson")]
+pub mod geojson;
+pub use lta_utils_commons::geo;
+#[cfg(feature = "gtfs")]
+pub mod gtfs;
+pub use lta_utils_commons::parse;
+#[cfg(feature = "spatial")]
+pub mod spatial;
+#[cfg(feature = "sqlite")]
+pub mod storage;
+#[cfg(feature = "sync")]
+pub mod sync;
+#[cfg(feature = "taxi")]
 pub mod taxi;
+#[cfg(feature = "traffic")]
 pub mod traffic;
+#[cfg(feature = "train")]
 pub mod train;
 
 /// Data structures for all data
 pub mod prelude {
-    pub use {
-        crate::bus::prelude::*, crate::crowd::prelude::*, crate::taxi::prelude::*,
-        crate::traffic::prelude::*, crate::train::prelude::*,
-    };
+    #[cfg(feature = "bus")]
+    pub use crate::bus::prelude::*;
+    #[cfg(feature = "crowd")]
+    pub use crate::crowd::prelude::*;
+    #[cfg(feature = "taxi")]
+    pub use crate::taxi::prelude::*;
+    #[cfg(feature = "traffic")]
+    pub use crate::traffic::prelude::*;
+    #[cfg(feature = "train")]
+    pub use crate::train::prelude::*;
 }
 
-#[cfg(test)]
+#[cfg(all(
+    test,
+    any(
+        feature = "bus",
+        feature = "traffic",
+        feature = "taxi",
+        feature = "train",
+        feature = "crowd"
+    )
+))]
 mod tests {
     use crate::prelude::*;
     use serde::{Deserialize, Serialize};
@@ -42,6 +99,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "traffic")]
     fn bike_parking() {
         gen_test!(
             BikeParkingResp,
@@ -51,6 +109,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "bus")]
     fn bus_arrival() {
         gen_test!(
             RawBusArrivalResp,
@@ -60,6 +119,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "bus")]
     fn bus_routes() {
         gen_test!(
             BusRouteResp,
@@ -69,6 +129,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "bus")]
     fn bus_service() {
         gen_test!(
             BusServiceResp,
@@ -78,6 +139,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "bus")]
     fn bus_stops() {
         gen_test!(
             BusStopsResp,
@@ -87,6 +149,1680 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "bus")]
+    fn bus_network() {
+        use crate::bus::network::{BusNetwork, NetworkIssue, ServiceDir};
+
+        let stops: BusStopsResp =
+            serde_json::from_str(include_str!("../../dumped_data/bus_stops.json")).unwrap();
+        let services: BusServiceResp =
+            serde_json::from_str(include_str!("../../dumped_data/bus_services.json")).unwrap();
+        let routes: BusRouteResp =
+            serde_json::from_str(include_str!("../../dumped_data/bus_route.json")).unwrap();
+
+        // shuffle the routes to check they are put back in order
+        let mut shuffled = routes.value.clone();
+        shuffled.reverse();
+        let network = BusNetwork::new(stops.value.clone(), services.value, shuffled);
+
+        assert_eq!(
+            network.stop_codes("10", 1)[..4],
+            [75009, 76059, 76069, 96289]
+        );
+        let dist = network.distance("10", 1, 76059, 96289).unwrap();
+        assert!((dist - 1.7).abs() < 1e-9);
+        assert_eq!(network.distance("10", 1, 96289, 76059), None);
+
+        let hops = network.next_stops(76059);
+        assert!(hops
+            .iter()
+            .any(|h| h.service == ServiceDir::new("10", 1) && h.to == 76069));
+        assert!(network.neighbours(76059).contains(&75009));
+        assert!(network.neighbours(76059).contains(&76069));
+
+        for service in network.services_at(76059) {
+            assert!(network
+                .stop_codes(&service.service_no, service.direction)
+                .contains(&76059));
+        }
+
+        // the dumps are the first page of each dataset so most route stops are missing
+        let issues = network.validate();
+        assert!(issues.iter().any(|i| match i {
+            NetworkIssue::MissingStop { bus_stop_code, .. } =>
+                network.stop(*bus_stop_code).is_none(),
+            _ => false,
+        }));
+
+        // a complete network for one route validates clean
+        let route_10: Vec<_> = routes
+            .value
+            .into_iter()
+            .filter(|r| r.service_no == "10" && r.direction == 1)
+            .collect();
+        let codes: Vec<_> = route_10.iter().map(|r| r.bus_stop_code).collect();
+        let stops: Vec<_> = codes
+            .iter()
+            .map(|&code| BusStop {
+                bus_stop_code: code,
+                road_name: String::new(),
+                desc: String::new(),
+                lat: 0.0,
+                long: 0.0,
+            })
+            .collect();
+        let network = BusNetwork::new(stops, vec![], route_10);
+        assert_eq!(
+            network.validate(),
+            vec![NetworkIssue::MissingService(ServiceDir::new("10", 1))]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "bus")]
+    fn journey_planner() {
+        use crate::bus::bus_services::BusFreq;
+        use crate::bus::network::{BusNetwork, ServiceDir};
+        use crate::bus::planner::{JourneyPlanner, Leg, Place};
+        use crate::bus_enums::{BusCategory, Operator};
+        use lta_utils_commons::chrono::{Duration, NaiveDate, NaiveTime};
+        use lta_utils_commons::sg_time;
+        use lta_utils_commons::Coordinates;
+
+        let time = |h, m| NaiveTime::from_hms_opt(h, m, 0).unwrap();
+        let stop = |code, lat, long| BusStop {
+            bus_stop_code: code,
+            road_name: String::new(),
+            desc: String::new(),
+            lat,
+            long,
+        };
+        let route = |service: &str, seq, code, dist, first: NaiveTime| BusRoute {
+            service_no: service.into(),
+            operator: Operator::SBST,
+            direction: 1,
+            stop_seq: seq,
+            bus_stop_code: code,
+            dist,
+            wd_first: Some(first),
+            wd_last: Some(first + Duration::hours(17)),
+            sat_first: Some(first),
+            sat_last: Some(first + Duration::hours(17)),
+            sun_first: None,
+            sun_last: None,
+        };
+
+        let stops = vec![
+            stop(1, 1.300, 103.800),
+            stop(2, 1.300, 103.810),
+            stop(3, 1.300, 103.820),
+            stop(4, 1.3005, 103.820),
+            stop(5, 1.300, 103.830),
+            stop(6, 1.310, 103.830),
+        ];
+        let routes = vec![
+            route("A", 1, 1, 0.0, time(6, 0)),
+            route("A", 2, 2, 1.1, time(6, 5)),
+            route("A", 3, 3, 2.2, time(6, 10)),
+            route("B", 1, 4, 0.0, time(6, 0)),
+            route("B", 2, 5, 1.1, time(6, 6)),
+            route("C", 1, 1, 0.0, time(6, 0)),
+            route("C", 2, 6, 2.0, time(6, 30)),
+            route("C", 3, 5, 4.0, time(7, 0)),
+        ];
+        let services = vec![BusService {
+            service_no: "A".into(),
+            operator: Operator::SBST,
+            no_direction: 1,
+            category: BusCategory::Trunk,
+            origin_code: 1,
+            dest_code: 3,
+            am_peak_freq: BusFreq::new(5, 8),
+            am_offpeak_freq: BusFreq::new(10, 12),
+            pm_peak_freq: BusFreq::new(5, 8),
+            pm_offpeak_freq: BusFreq::new(10, 12),
+            loop_desc: None,
+        }];
+        let network = BusNetwork::new(stops, services, routes);
+        let planner = JourneyPlanner::new(&network);
+
+        let tuesday = NaiveDate::from_ymd_opt(2019, 11, 19).unwrap();
+        let depart = sg_time::at(tuesday, time(9, 0));
+        let plans = planner.plan(Place::Stop(1), Place::Stop(5), depart);
+        assert_eq!(plans.len(), 2);
+
+        // A, walk across to B beats the slow direct C
+        let best = &plans[0];
+        assert_eq!(best.transfers(), 1);
+        assert_eq!(best.legs.len(), 3);
+        match &best.legs[0] {
+            Leg::Bus {
+                service,
+                board,
+                alight,
+                stops,
+                wait,
+                depart,
+                arrive,
+            } => {
+                assert_eq!(service, &ServiceDir::new("A", 1));
+                assert_eq!((*board, *alight, *stops), (1, 3, 2));
+                // half of the 10-12 minute off-peak headway
+                assert_eq!(*wait, Duration::seconds(330));
+                assert_eq!(*arrive - *depart, Duration::minutes(10));
+            }
+            leg => panic!("{:?}", leg),
+        }
+        match &best.legs[1] {
+            Leg::Walk {
+                from, to, dist_m, ..
+            } => {
+                assert_eq!((from, to), (&Place::Stop(3), &Place::Stop(4)));
+                assert!((*dist_m - 55.6).abs() < 1.0);
+            }
+            leg => panic!("{:?}", leg),
+        }
+        assert!(best.walk_m() > 0.0);
+        for w in best.legs.windows(2) {
+            assert!(w[0].arrive() <= w[1].depart());
+        }
+
+        let direct = &plans[1];
+        assert_eq!(direct.transfers(), 0);
+        assert!(direct.arrive() > best.arrive());
+        assert_eq!(
+            planner
+                .clone()
+                .max_transfers(0)
+                .plan(Place::Stop(1), Place::Stop(5), depart),
+            vec![direct.clone()]
+        );
+
+        // waits for the first bus and stops after the last
+        let early = sg_time::at(tuesday, time(5, 0));
+        let plans = planner.plan(Place::Stop(1), Place::Stop(3), early);
+        assert_eq!(plans[0].legs[0].depart(), sg_time::at(tuesday, time(6, 0)));
+        let late = sg_time::at(tuesday, time(23, 30));
+        assert!(planner
+            .plan(Place::Stop(1), Place::Stop(3), late)
+            .is_empty());
+
+        // no Sunday service
+        let sunday = NaiveDate::from_ymd_opt(2019, 11, 24).unwrap();
+        assert!(planner
+            .plan(
+                Place::Stop(1),
+                Place::Stop(3),
+                sg_time::at(sunday, time(9, 0))
+            )
+            .is_empty());
+
+        // from coordinates walks to the first stop, and nearby places are walked
+        let from = Place::Coords(Coordinates::new(1.301, 103.800));
+        let plans = planner.plan(from.clone(), Place::Stop(3), depart);
+        assert!(
+            matches!(&plans[0].legs[0], Leg::Walk { from: f, to: Place::Stop(1), .. } if f == &from)
+        );
+        let plans = planner.plan(Place::Stop(3), Place::Stop(4), depart);
+        assert_eq!(plans.len(), 1);
+        assert_eq!(plans[0].legs.len(), 1);
+        assert_eq!(plans[0].duration(), Duration::seconds(46));
+    }
+
+    #[test]
+    #[cfg(feature = "bus")]
+    fn timetable() {
+        use crate::bus::bus_routes::ServiceDay;
+        use crate::bus::bus_services::{BusFreq, FreqBand};
+        use crate::bus::timetable::Timetable;
+        use lta_utils_commons::chrono::{NaiveDate, NaiveTime};
+        use lta_utils_commons::sg_time;
+
+        let network = sample_network();
+        let christmas = NaiveDate::from_ymd_opt(2019, 12, 25).unwrap();
+        let timetable = Timetable::new(&network).with_holidays(vec![christmas]);
+        let at = |y, m, d, h, min| {
+            sg_time::at(
+                NaiveDate::from_ymd_opt(y, m, d).unwrap(),
+                NaiveTime::from_hms_opt(h, min, 0).unwrap(),
+            )
+        };
+
+        // the Monday service runs past midnight
+        assert!(timetable.is_running("A", 1, 1002, at(2019, 11, 19, 0, 15)));
+        assert_eq!(
+            timetable.last_bus("A", 1, 1002, at(2019, 11, 19, 0, 15)),
+            Some(at(2019, 11, 19, 0, 30))
+        );
+        assert!(!timetable.is_running("A", 1, 1002, at(2019, 11, 19, 0, 45)));
+        assert_eq!(
+            timetable.next_first_bus("A", 1, 1002, at(2019, 11, 19, 0, 45)),
+            Some(at(2019, 11, 19, 6, 5))
+        );
+        assert_eq!(
+            timetable.window("A", 1, 1002, NaiveDate::from_ymd_opt(2019, 11, 19).unwrap()),
+            Some((at(2019, 11, 19, 6, 5), at(2019, 11, 20, 0, 30)))
+        );
+
+        // Saturday buses stop at 23:00 and nothing runs on Sundays and holidays
+        assert!(!timetable.is_running("A", 1, 1002, at(2019, 11, 23, 23, 30)));
+        assert!(!timetable.is_running("A", 1, 1002, at(2019, 11, 24, 10, 0)));
+        assert_eq!(
+            timetable.next_first_bus("A", 1, 1002, at(2019, 11, 24, 10, 0)),
+            Some(at(2019, 11, 25, 6, 5))
+        );
+        assert_eq!(timetable.service_day(christmas), ServiceDay::SundayHoliday);
+        assert!(!timetable.is_running("A", 1, 1002, at(2019, 12, 25, 10, 0)));
+        assert_eq!(
+            timetable.next_first_bus("A", 1, 1002, at(2019, 12, 25, 10, 0)),
+            Some(at(2019, 12, 26, 6, 5))
+        );
+
+        let bands: Vec<_> = [(7, 0), (8, 31), (19, 0), (19, 1), (3, 0)]
+            .iter()
+            .map(|&(h, m)| timetable.headway("A", 1, at(2019, 11, 19, h, m)).unwrap())
+            .collect();
+        assert_eq!(
+            bands,
+            vec![
+                (FreqBand::AmPeak, &BusFreq::new(5, 8)),
+                (FreqBand::AmOffpeak, &BusFreq::new(10, 12)),
+                (FreqBand::PmPeak, &BusFreq::new(5, 8)),
+                (FreqBand::PmOffpeak, &BusFreq::new(10, 12)),
+                (FreqBand::PmOffpeak, &BusFreq::new(10, 12)),
+            ]
+        );
+
+        assert_eq!(timetable.headway("B", 1, at(2019, 11, 19, 7, 0)), None);
+        assert!(!timetable.is_running("A", 1, 9999, at(2019, 11, 19, 10, 0)));
+        assert_eq!(
+            timetable.next_first_bus("A", 2, 1002, at(2019, 11, 19, 10, 0)),
+            None
+        );
+    }
+
+    #[test]
+    #[cfg(all(
+        feature = "calendar",
+        feature = "bus",
+        feature = "traffic",
+        feature = "crowd"
+    ))]
+    fn calendar() {
+        use crate::bus::bus_routes::ServiceDay;
+        use crate::calendar::{Calendar, DayKind};
+        use crate::crowd::passenger_vol::DayType as VolDayType;
+        use crate::traffic::erp_rates::DayType as ErpDayType;
+        use lta_utils_commons::chrono::NaiveDate;
+
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        let mut calendar = Calendar::sg();
+
+        // Chinese New Year 2023 was on Sunday and Monday, the day in lieu is Tuesday
+        let cny = calendar.holiday(date(2023, 1, 24)).unwrap();
+        assert_eq!(cny.name, "Chinese New Year");
+        assert!(cny.in_lieu);
+        assert!(!calendar.holiday(date(2023, 1, 23)).unwrap().in_lieu);
+        assert!(!calendar.is_holiday(date(2023, 1, 25)));
+
+        // a holiday on a Saturday has no day in lieu
+        assert_eq!(calendar.day_kind(date(2025, 8, 9)), DayKind::Holiday);
+        assert!(!calendar.is_holiday(date(2025, 8, 11)));
+
+        let days = [
+            date(2019, 11, 19),
+            date(2019, 11, 23),
+            date(2019, 11, 24),
+            date(2019, 12, 25),
+        ];
+        let kinds: Vec<_> = days
+            .iter()
+            .map(|&d| {
+                (
+                    calendar.day_kind(d),
+                    calendar.service_day(d),
+                    calendar.erp_day_type(d),
+                    calendar.volume_day_type(d),
+                )
+            })
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (
+                    DayKind::Weekday,
+                    ServiceDay::Weekday,
+                    Some(ErpDayType::Weekdays),
+                    VolDayType::Weekday
+                ),
+                (
+                    DayKind::Saturday,
+                    ServiceDay::Saturday,
+                    Some(ErpDayType::Saturday),
+                    VolDayType::WeekendsHoliday
+                ),
+                (
+                    DayKind::Sunday,
+                    ServiceDay::SundayHoliday,
+                    None,
+                    VolDayType::WeekendsHoliday
+                ),
+                (
+                    DayKind::Holiday,
+                    ServiceDay::SundayHoliday,
+                    None,
+                    VolDayType::WeekendsHoliday
+                ),
+            ]
+        );
+
+        // a newer file replaces the years it covers
+        let file = "# corrected\ndate,name\n2026-01-01,New Year's Day\n2027-01-01,New Year's Day\n2027-02-07,Chinese New Year\n";
+        calendar.update(Calendar::from_reader(file.as_bytes()).unwrap());
+        assert!(calendar.is_holiday(date(2025, 12, 25)));
+        assert!(!calendar.is_holiday(date(2026, 12, 25)));
+        assert!(calendar.holiday(date(2027, 2, 8)).unwrap().in_lieu);
+        assert_eq!(calendar.years().iter().next_back(), Some(&2027));
+
+        let err = Calendar::from_reader("2027-02-30,Nope".as_bytes()).unwrap_err();
+        assert!(err.to_string().starts_with("line 1: bad date 2027-02-30"));
+        assert!(Calendar::from_reader("2027-02-07".as_bytes()).is_err());
+    }
+
+    #[test]
+    #[cfg(all(feature = "bus", feature = "traffic"))]
+    fn geo() {
+        use crate::geo::{BoundingBox, HasLocation, Polygon};
+        use lta_utils_commons::Coordinates;
+
+        let stops: BusStopsResp =
+            serde_json::from_str(include_str!("../../dumped_data/bus_stops.json")).unwrap();
+        let stop = &stops.value[0];
+        let here = stop.coordinates().unwrap();
+        assert_eq!(here, Coordinates::new(stop.lat, stop.long));
+        assert_eq!(stop.distance_m(&here), Some(0.0));
+
+        // one minute of latitude is a nautical mile
+        let north = here.destination(0.0, 1852.0);
+        assert!((north.lat - here.lat - 1.0 / 60.0).abs() < 1e-4);
+        assert!((here.distance_m(&north) - 1852.0).abs() < 1e-6);
+        assert!(here.bearing(&north) < 1e-9);
+
+        let east = here.destination(90.0, 500.0);
+        assert!((here.bearing(&east) - 90.0).abs() < 0.01);
+        assert!((east.bearing(&here) - 270.0).abs() < 0.01);
+        assert!((here.distance_m(&east) - 500.0).abs() < 1e-6);
+
+        let bbox = BoundingBox::around(&here, 500.0);
+        assert!(bbox.contains(&east));
+        assert!(!bbox.contains(&north));
+        assert_eq!(
+            BoundingBox::from_points(vec![&here, &north]),
+            Some(BoundingBox::new(north, here))
+        );
+        assert_eq!(BoundingBox::from_points(Vec::<&Coordinates>::new()), None);
+        assert!(bbox.intersects(&BoundingBox::around(&north, 1500.0)));
+        assert!(!bbox.intersects(&BoundingBox::around(&north, 1000.0)));
+
+        let square = |c: Coordinates, m: f64| {
+            let b = BoundingBox::around(&c, m);
+            vec![
+                b.min,
+                Coordinates::new(b.min.lat, b.max.long),
+                b.max,
+                Coordinates::new(b.max.lat, b.min.long),
+            ]
+        };
+        let ring = Polygon::new(square(here, 1000.0)).with_hole(square(here, 100.0));
+        assert!(!ring.contains(&here));
+        assert!(ring.contains(&east));
+        assert!(!ring.contains(&north));
+        assert_eq!(ring.bbox(), Some(BoundingBox::around(&here, 1000.0)));
+
+        let carparks: CarparkAvailResp =
+            serde_json::from_str(include_str!("../../dumped_data/carpark_avail.json")).unwrap();
+        assert!(carparks.value.iter().all(|c| c.coordinates() == c.coords));
+    }
+
+    #[test]
+    #[cfg(feature = "spatial")]
+    fn spatial_index() {
+        use crate::geo::BoundingBox;
+        use crate::spatial::SpatialIndex;
+        use lta_utils_commons::Coordinates;
+
+        let mut seed = 7u64;
+        let mut next = move || {
+            seed = seed
+                .wrapping_mul(6_364_136_223_846_793_005)
+                .wrapping_add(1_442_695_040_888_963_407);
+            (seed >> 11) as f64 / (1u64 << 53) as f64
+        };
+        let points: Vec<_> = (0..3000)
+            .map(|_| Coordinates::new(1.24 + next() * 0.23, 103.62 + next() * 0.4))
+            .collect();
+        let index: SpatialIndex<_> = points.iter().collect();
+        assert_eq!(index.len(), points.len());
+
+        let brute_force = |here: &Coordinates| {
+            let mut all: Vec<_> = points.iter().map(|p| here.distance_m(p)).collect();
+            all.sort_by(|a, b| a.total_cmp(b));
+            all
+        };
+
+        for _ in 0..50 {
+            let here = Coordinates::new(1.2 + next() * 0.3, 103.6 + next() * 0.45);
+            let expected = brute_force(&here);
+
+            let nearest: Vec<_> = index.nearest(&here, 10).iter().map(|(_, d)| *d).collect();
+            assert_eq!(nearest, expected[..10]);
+
+            let radius = next() * 3000.0;
+            let within: Vec<_> = index
+                .within_radius(&here, radius)
+                .iter()
+                .map(|(p, d)| {
+                    assert_eq!(here.distance_m(p), *d);
+                    *d
+                })
+                .collect();
+            let expected: Vec<_> = expected.into_iter().filter(|d| *d <= radius).collect();
+            assert_eq!(within, expected);
+
+            let bbox = BoundingBox::around(&here, radius);
+            let mut inside: Vec<_> = index.within_bbox(&bbox).into_iter().map(|p| **p).collect();
+            let mut expected: Vec<_> = points
+                .iter()
+                .filter(|p| bbox.contains(p))
+                .copied()
+                .collect();
+            inside.sort_by(|a, b| a.lat.total_cmp(&b.lat));
+            expected.sort_by(|a, b| a.lat.total_cmp(&b.lat));
+            assert_eq!(inside, expected);
+        }
+
+        let here = points[0];
+        assert_eq!(index.nearest(&here, 0), vec![]);
+        assert_eq!(index.nearest(&here, 5000).len(), points.len());
+        assert_eq!(index.nearest(&here, 1)[0], (&&here, 0.0));
+
+        let empty: SpatialIndex<Coordinates> = SpatialIndex::new(vec![]);
+        assert!(empty.is_empty());
+        assert_eq!(empty.nearest(&here, 3), vec![]);
+        assert_eq!(empty.within_radius(&here, 1000.0), vec![]);
+    }
+
+    #[test]
+    fn svy21() {
+        use lta_utils_commons::de::{wgs84_or_svy21, wgs84_or_svy21_option};
+        use lta_utils_commons::svy21::Svy21Coordinates;
+        use lta_utils_commons::Coordinates;
+
+        let close = |a: &Coordinates, b: &Coordinates| a.distance_m(b) < 0.001;
+
+        // EPSG:3414 grid values, computed independently with the Krueger series
+        let points = [
+            ("Changi", 1.3644, 103.9915, 38_494.514_048, 45_603.738_688),
+            ("Tuas", 1.3200, 103.6400, 33_585.246_891, 6_485.505_752),
+            (
+                "Woodlands",
+                1.4360,
+                103.7865,
+                46_411.154_555,
+                22_789.804_975,
+            ),
+            ("Sentosa", 1.2494, 103.8303, 25_777.827_555, 27_664.052_620),
+        ];
+        for (name, lat, long, northing, easting) in points.iter() {
+            let svy21 = Coordinates::new(*lat, *long).to_svy21();
+            assert!(
+                (svy21.northing - northing).abs() < 1e-3,
+                "{} {:?}",
+                name,
+                svy21
+            );
+            assert!(
+                (svy21.easting - easting).abs() < 1e-3,
+                "{} {:?}",
+                name,
+                svy21
+            );
+            let back = Svy21Coordinates::new(*northing, *easting).to_wgs84();
+            assert!(close(&back, &Coordinates::new(*lat, *long)), "{}", name);
+        }
+
+        let nus = Coordinates::new(1.294_919_268_848_527_8, 103.773_674_368_858_34);
+        let svy21 = nus.to_svy21();
+        assert!((svy21.northing - 30_811.190_581).abs() < 1e-3);
+        assert!((svy21.easting - 21_362.119_947).abs() < 1e-3);
+
+        for lat in 0..=10 {
+            for long in 0..=10 {
+                let wgs84 = Coordinates::new(1.2 + lat as f64 * 0.03, 103.6 + long as f64 * 0.04);
+                let svy21 = Svy21Coordinates::from(wgs84);
+                assert!(close(&Coordinates::from(svy21), &wgs84));
+            }
+        }
+
+        #[derive(Deserialize)]
+        struct Place {
+            #[serde(deserialize_with = "wgs84_or_svy21")]
+            at: Coordinates,
+
+            #[serde(default, deserialize_with = "wgs84_or_svy21_option")]
+            near: Option<Coordinates>,
+        }
+
+        let forms = [
+            r#"{"at": {"lat": 1.2949192688485278, "long": 103.77367436885834}}"#,
+            r#"{"at": {"Latitude": 1.2949192688485278, "Longitude": 103.77367436885834}}"#,
+            r#"{"at": {"northing": 30811.190581, "easting": 21362.119947}}"#,
+            r#"{"at": {"x": 21362.119947, "y": 30811.190581}, "near": null}"#,
+            r#"{"at": "1.2949192688485278 103.77367436885834", "near": ""}"#,
+            r#"{"at": "21362.119947,30811.190581"}"#,
+        ];
+        for json in forms.iter() {
+            let place: Place = serde_json::from_str(json).unwrap();
+            assert!(close(&place.at, &nus), "{}", json);
+            assert_eq!(place.near, None);
+        }
+
+        let place: Place =
+            serde_json::from_str(r#"{"at": "1.3 103.8", "near": "28001.642,38744.572"}"#).unwrap();
+        let origin = Coordinates::new(1.0 + 22.0 / 60.0, 103.0 + 50.0 / 60.0);
+        assert!(close(&place.near.unwrap(), &origin));
+        assert!(serde_json::from_str::<Place>(r#"{"at": "1.3"}"#).is_err());
+
+        // out of the SVY21 extent
+        for json in [
+            r#"{"at": "900000 900000"}"#,
+            r#"{"at": "-50000,30000"}"#,
+            r#"{"at": {"x": 21362.1, "y": 130811.2}}"#,
+        ]
+        .iter()
+        {
+            assert!(serde_json::from_str::<Place>(json).is_err(), "{}", json);
+        }
+        assert!(!Svy21Coordinates::new(900_000.0, 900_000.0).in_extent());
+    }
+
+    #[test]
+    #[cfg(all(feature = "geo-types", feature = "bus", feature = "traffic"))]
+    fn geo_types_interop() {
+        use crate::geo::{BoundingBox, Polygon};
+        use geo_types::{Coord, Line, MultiLineString, MultiPoint, Point, Rect};
+        use lta_utils_commons::{Coordinates, Location};
+
+        let here = Coordinates::new(1.3, 103.8);
+        let point = Point::from(here);
+        assert_eq!((point.x(), point.y()), (103.8, 1.3));
+        assert_eq!(Coordinates::from(point), here);
+
+        let location = Location::new(1.3, 103.8, 1.31, 103.82);
+        let line = Line::from(location.clone());
+        assert_eq!(line.end, Coord { x: 103.82, y: 1.31 });
+        assert_eq!(Location::from(line), location);
+
+        let bbox = BoundingBox::around(&here, 500.0);
+        assert_eq!(BoundingBox::from(Rect::from(bbox)), bbox);
+
+        let square = Polygon::new(vec![
+            bbox.min,
+            Coordinates::new(bbox.min.lat, bbox.max.long),
+            bbox.max,
+            Coordinates::new(bbox.max.lat, bbox.min.long),
+        ]);
+        let polygon = geo_types::Polygon::from(square.clone());
+        assert_eq!(polygon.exterior().0.len(), 5);
+        assert_eq!(Polygon::from(polygon), square);
+
+        let stops: BusStopsResp =
+            serde_json::from_str(include_str!("../../dumped_data/bus_stops.json")).unwrap();
+        let first = Point::new(stops.value[0].long, stops.value[0].lat);
+        let count = stops.value.len();
+        let points = MultiPoint::from(stops);
+        assert_eq!(points.0.len(), count);
+        assert_eq!(points.0[0], first);
+
+        let bands: TrafficSpeedBandResp = serde_json::from_str(
+            r#"{"value": [
+                {"LinkID": "103000000", "RoadName": "KENT ROAD", "RoadCategory": "E",
+                 "SpeedBand": 2, "MinimumSpeed": "10", "MaximumSpeed": "19",
+                 "Location": "1.3170142376560023 103.85298052044503 1.3166840028663076 103.85259882242372"},
+                {"LinkID": "103000010", "RoadName": "BUKIT TIMAH ROAD", "RoadCategory": "C",
+                 "SpeedBand": 3, "MinimumSpeed": "20", "MaximumSpeed": "29", "Location": ""}
+            ]}"#,
+        )
+        .unwrap();
+        let lines = MultiLineString::from(bands);
+        assert_eq!(lines.0.len(), 1);
+        assert_eq!(
+            lines.0[0].0[0],
+            Coord {
+                x: 103.85298052044503,
+                y: 1.3170142376560023
+            }
+        );
+    }
+
+    #[test]
+    #[cfg(all(feature = "geojson", feature = "bus", feature = "traffic"))]
+    fn geojson() {
+        use crate::bus::network::BusNetwork;
+        use crate::geojson::{bus_routes, FeatureCollection, FeatureWriter, Geometry, ToFeature};
+        use serde_json::{json, Value};
+
+        let carparks: CarparkAvailResp =
+            serde_json::from_str(include_str!("../../dumped_data/carpark_avail.json")).unwrap();
+        let carpark = &carparks.value[0];
+        let feature = carpark.to_feature().unwrap();
+        let coords = carpark.coords.unwrap();
+        assert_eq!(feature.geometry, Some(Geometry::Point(coords)));
+        assert_eq!(feature.properties["carpark_id"], json!(carpark.carpark_id));
+        assert_eq!(feature.properties["avail_lots"], json!(carpark.avail_lots));
+        assert_eq!(
+            serde_json::to_value(&feature).unwrap()["geometry"],
+            json!({"type": "Point", "coordinates": [coords.long, coords.lat]})
+        );
+
+        let mut no_location = carpark.clone();
+        no_location.coords = None;
+        let value = serde_json::to_value(no_location.to_feature().unwrap()).unwrap();
+        assert_eq!(value["type"], "Feature");
+        assert_eq!(value["geometry"], Value::Null);
+
+        let band: TrafficSpeedBand = serde_json::from_value(json!({
+            "LinkID": "103000000", "RoadName": "KENT ROAD", "RoadCategory": "E",
+            "SpeedBand": 2, "MinimumSpeed": "10", "MaximumSpeed": "19",
+            "Location": "1.317 103.852 1.316 103.851"
+        }))
+        .unwrap();
+        let value = serde_json::to_value(band.to_feature().unwrap()).unwrap();
+        assert_eq!(
+            value["geometry"],
+            json!({"type": "LineString", "coordinates": [[103.852, 1.317], [103.851, 1.316]]})
+        );
+        assert_eq!(value["properties"]["road_name"], "KENT ROAD");
+
+        let stops: BusStopsResp =
+            serde_json::from_str(include_str!("../../dumped_data/bus_stops.json")).unwrap();
+        let routes: BusRouteResp =
+            serde_json::from_str(include_str!("../../dumped_data/bus_route.json")).unwrap();
+        let network = BusNetwork::new(stops.value.clone(), vec![], routes.value);
+        let lines = bus_routes(&network);
+        assert_eq!(lines.len(), network.service_dirs().len());
+        let line = &lines[0];
+        assert_eq!(
+            line.stops,
+            network.stop_codes(&line.service_no, line.direction)
+        );
+        let known = line
+            .stops
+            .iter()
+            .filter(|s| network.stop(**s).is_some())
+            .count();
+        assert_eq!(line.line.len(), known);
+
+        let collection = FeatureCollection::from_records(&stops.value).unwrap();
+        let mut writer = FeatureWriter::new(Vec::new()).unwrap();
+        for stop in &stops.value {
+            writer.write(stop).unwrap();
+        }
+        let written: Value = serde_json::from_slice(&writer.finish().unwrap()).unwrap();
+        assert_eq!(written, serde_json::to_value(&collection).unwrap());
+        assert_eq!(
+            written["features"].as_array().unwrap().len(),
+            stops.value.len()
+        );
+
+        let empty = FeatureWriter::new(Vec::new()).unwrap().finish().unwrap();
+        assert_eq!(
+            serde_json::from_slice::<Value>(&empty).unwrap(),
+            json!({"type": "FeatureCollection", "features": []})
+        );
+    }
+
+    #[test]
+    #[cfg(all(
+        feature = "bus",
+        feature = "traffic",
+        feature = "taxi",
+        feature = "train",
+        feature = "crowd",
+        feature = "csv-io"
+    ))]
+    fn csv_round_trip() {
+        use crate::csv_io::{self, CsvColumns, CsvError, TrainAlertRow};
+        use serde_json::json;
+
+        fn round_trip<T: CsvColumns + PartialEq + Debug>(records: &[T]) -> String {
+            let csv = csv_io::write_csv(Vec::new(), records).unwrap();
+            let read = csv_io::read_csv::<_, T>(csv.as_slice())
+                .unwrap()
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap();
+            assert_eq!(read, records);
+            String::from_utf8(csv).unwrap()
+        }
+
+        let arrival: RawBusArrivalResp =
+            serde_json::from_str(include_str!("../../dumped_data/bus_arrival.json")).unwrap();
+        let empty_stop = BusArrivalResp {
+            bus_stop_code: 1012,
+            services: vec![],
+        };
+        let arrivals = vec![arrival.into(), empty_stop];
+        let rows = csv_io::bus_arrival_rows(&arrivals);
+        let csv = round_trip(&rows);
+        assert!(csv.starts_with("bus_stop_code,service_no,operator,next_bus_1_origin_code,"));
+        let width = csv_io::headers::<csv_io::BusArrivalRow>().len();
+        assert_eq!(width, 3 + 3 * 9);
+        assert!(csv.ends_with(&format!("\n1012{}\n", ",".repeat(width - 1))));
+        assert_eq!(csv_io::bus_arrivals(rows), arrivals);
+
+        let services: BusServiceResp =
+            serde_json::from_str(include_str!("../../dumped_data/bus_services.json")).unwrap();
+        let csv = round_trip(&services.value);
+        assert!(csv.contains(",am_peak_freq_min,am_peak_freq_max,"));
+        assert!(services
+            .value
+            .iter()
+            .any(|s| s.loop_desc.as_deref() == Some("")));
+        let loops: Vec<_> = [None, Some(""), Some("\\"), Some("Loop")]
+            .iter()
+            .map(|desc| BusService {
+                loop_desc: desc.map(String::from),
+                ..services.value[0].clone()
+            })
+            .collect();
+        let csv = round_trip(&loops);
+        let cells: Vec<_> = csv.lines().skip(1).map(|l| l.rsplit(',').next()).collect();
+        assert_eq!(cells, [Some(""), Some("\\"), Some("\\\\"), Some("Loop")]);
+
+        let routes: BusRouteResp =
+            serde_json::from_str(include_str!("../../dumped_data/bus_route.json")).unwrap();
+        let csv = round_trip(&routes.value);
+        assert!(csv.lines().nth(1).unwrap().contains(",05:00,"));
+
+        let stops: BusStopsResp =
+            serde_json::from_str(include_str!("../../dumped_data/bus_stops.json")).unwrap();
+        round_trip(&stops.value);
+
+        let rates: ErpRatesResp =
+            serde_json::from_str(include_str!("../../dumped_data/erp_rates.json")).unwrap();
+        let csv = round_trip(&rates.value);
+        assert!(csv.contains("\nPassengerCars/LightGoodsVehicles/Taxis,"));
+
+        // the API reads an empty `VehicleType` as one empty item
+        let rate: ErpRate = serde_json::from_value(json!({
+            "VehicleType": "", "DayType": "Weekdays", "StartTime": "07:30",
+            "EndTime": "07:35", "ZoneID": "AY1", "ChargeAmount": 0.0,
+            "EffectiveDate": "2019-08-05"
+        }))
+        .unwrap();
+        assert_eq!(
+            rate.vehicle_type,
+            vec![VehicleType::Unknown("".to_string())]
+        );
+        let no_vehicle = ErpRate {
+            vehicle_type: vec![],
+            ..rate.clone()
+        };
+        let escaped = ErpRate {
+            vehicle_type: vec![VehicleType::Unknown("\\".into()), VehicleType::Taxis],
+            ..rate.clone()
+        };
+        let csv = round_trip(&[rate, no_vehicle, escaped]);
+        let cells: Vec<_> = csv.lines().skip(1).map(|l| l.split(',').next()).collect();
+        assert_eq!(cells, [Some("\\"), Some(""), Some("\\\\/Taxis")]);
+
+        let carparks: CarparkAvailResp =
+            serde_json::from_str(include_str!("../../dumped_data/carpark_avail.json")).unwrap();
+        let csv = round_trip(&carparks.value);
+        assert!(csv.starts_with("carpark_id,area,dev,lat,long,avail_lots,lot_type,agency\n"));
+
+        let times: EstTravelTimeResp =
+            serde_json::from_str(include_str!("../../dumped_data/est_travel_time.json")).unwrap();
+        round_trip(&times.value);
+
+        let lights: FaultyTrafficLightResp =
+            serde_json::from_str(include_str!("../../dumped_data/faulty_traffic_lights.json"))
+                .unwrap();
+        round_trip(&lights.value);
+
+        let parking: BikeParkingResp =
+            serde_json::from_str(include_str!("../../dumped_data/bike_parking.json")).unwrap();
+        round_trip(&parking.value);
+
+        let band: TrafficSpeedBand = serde_json::from_value(json!({
+            "LinkID": "103000000", "RoadName": "KENT ROAD", "RoadCategory": "E",
+            "SpeedBand": 2, "MinimumSpeed": "10", "MaximumSpeed": "19",
+            "Location": "1.317 103.852 1.316 103.851"
+        }))
+        .unwrap();
+        let mut no_location = band.clone();
+        no_location.coord_start_end = None;
+        let csv = round_trip(&[band, no_location]);
+        assert!(csv.contains(",start_lat,start_long,end_lat,end_long\n"));
+        assert!(csv.ends_with(",19,,,,\n"));
+
+        let taxis: TaxiAvailResp =
+            serde_json::from_str(include_str!("../../dumped_data/taxi_avail.json")).unwrap();
+        round_trip(&taxis.value);
+
+        let stands: TaxiStandsResp =
+            serde_json::from_str(include_str!("../../dumped_data/taxi_stands.json")).unwrap();
+        let stands: Vec<TaxiStand> = stands.into();
+        round_trip(&stands);
+
+        let alert: TrainServiceAlertResp =
+            serde_json::from_str(include_str!("../../dumped_data/train_service_alert.json"))
+                .unwrap();
+        let alert: TrainServiceAlert = alert.into();
+        let rows = csv_io::train_alert_rows(&alert);
+        round_trip(&rows);
+        assert_eq!(csv_io::train_alert(rows), Some(alert));
+        assert_eq!(csv_io::train_alert(Vec::<TrainAlertRow>::new()), None);
+
+        let roads: RoadDetailsResp = serde_json::from_value(json!({"value": [{
+            "EventID": "RMAPP-201603-0900", "StartDate": "2016-03-31", "EndDate": "2016-09-30",
+            "SvcDept": "MAINTENANCE", "RoadName": "ALEXANDRA ROAD", "Other": "For details, go to"
+        }]}))
+        .unwrap();
+        let csv = round_trip(&roads.value);
+        assert!(csv.starts_with("event_id,start_date,end_date,service_dept,road_name,other\n"));
+
+        let images: TrafficImageResp = serde_json::from_value(json!({"value": [{
+            "CameraID": "1001", "Latitude": 1.29531332, "Longitude": 103.871146,
+            "ImageLink": "https://dm-traffic-camera-itsc.s3.amazonaws.com/1001.jpg?a=b&c=d"
+        }]}))
+        .unwrap();
+        round_trip(&images.value);
+
+        let incidents: crate::traffic::traffic_incidents::TrafficIncidentResp =
+            serde_json::from_value(json!({"value": [
+                {"Type": "Vehicle breakdown", "Latitude": 1.3, "Longitude": 103.8,
+                 "Message": "(1/1)12:34 Vehicle breakdown on PIE (towards Tuas), \"lane 1\""},
+                {"Type": "Misc.", "Latitude": 1.31, "Longitude": 103.81, "Message": "Line\nbreak"}
+            ]}))
+            .unwrap();
+        let csv = round_trip(&incidents.value);
+        assert!(csv.contains("\nVehicleBreakdown,"));
+
+        let vms: VMSResp = serde_json::from_value(json!({"value": [
+            {"EquipmentID": "amvms_v9104", "Latitude": 1.3, "Longitude": 103.8, "Message": ""}
+        ]}))
+        .unwrap();
+        round_trip(&vms.value);
+
+        let links: PassengerVolRawResp =
+            serde_json::from_str(include_str!("../../dumped_data/passenger_vol_train.json"))
+                .unwrap();
+        let csv = round_trip(&links.value);
+        assert!(csv.starts_with("link\n"));
+
+        let csv = include_str!("../../dumped_data/origin_destination_train_201911.csv");
+        let trips =
+            crate::crowd::passenger_vol::read_csv::<_, OdPassengerVolRecord>(csv.as_bytes())
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap();
+        assert!(!trips.is_empty());
+        round_trip(&trips);
+
+        let csv = include_str!("../../dumped_data/transport_node_bus_201911.csv");
+        let volumes =
+            crate::crowd::passenger_vol::read_csv::<_, PassengerVolRecord>(csv.as_bytes())
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap();
+        let csv = round_trip(&volumes);
+        assert!(csv.starts_with("year_month,day_type,hour,pt_type,pt_code,tap_in,tap_out\n"));
+
+        let err = csv_io::read_csv::<_, BusStop>("code,lat\n1,2".as_bytes()).err();
+        assert!(matches!(err, Some(CsvError::Headers { .. })));
+
+        let bad = "bus_stop_code,road_name,desc,lat,long\n1012,Road,Desc,north,103.8\n";
+        let err = csv_io::read_csv::<_, BusStop>(bad.as_bytes())
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap_err();
+        match err {
+            CsvError::Field { line, column, .. } => assert_eq!((line, column.as_str()), (2, "lat")),
+            e => panic!("unexpected error {}", e),
+        }
+    }
+
+    #[test]
+    #[cfg(all(
+        feature = "arrow",
+        feature = "bus",
+        feature = "traffic",
+        feature = "taxi",
+        feature = "train",
+        feature = "crowd"
+    ))]
+    fn arrow_round_trip() {
+        use crate::arrow::{
+            from_record_batch, read_parquet, schema, to_record_batch, write_parquet, ArrowError,
+            ArrowRecord,
+        };
+        use arrow_array::RecordBatch;
+        use arrow_schema::{DataType, TimeUnit};
+        use std::fs::{self, File};
+
+        fn round_trip<T: ArrowRecord + PartialEq + Debug>(
+            name: &str,
+            records: &[T],
+        ) -> RecordBatch {
+            let batch = to_record_batch(records).unwrap();
+            assert_eq!(from_record_batch::<T>(&batch).unwrap(), records);
+
+            let path = std::env::temp_dir().join(format!(
+                "lta_models_{}_{}.parquet",
+                name,
+                std::process::id()
+            ));
+            write_parquet(File::create(&path).unwrap(), records).unwrap();
+            let read: Vec<T> = read_parquet(File::open(&path).unwrap()).unwrap();
+            fs::remove_file(&path).unwrap();
+            assert_eq!(read, records);
+            batch
+        }
+
+        let dictionary = DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8));
+
+        let arrival: RawBusArrivalResp =
+            serde_json::from_str(include_str!("../../dumped_data/bus_arrival.json")).unwrap();
+        let empty_stop = BusArrivalResp {
+            bus_stop_code: 1012,
+            services: vec![],
+        };
+        let batch = round_trip("bus_arrival", &[arrival.into(), empty_stop]);
+        let next_bus = match batch.schema().field(1).data_type() {
+            DataType::List(service) => match service.data_type() {
+                DataType::Struct(fields) => fields[2].data_type().clone(),
+                other => panic!("unexpected service type {}", other),
+            },
+            other => panic!("unexpected services type {}", other),
+        };
+        assert!(matches!(next_bus, DataType::FixedSizeList(bus, 3) if bus.is_nullable()));
+
+        let services: BusServiceResp =
+            serde_json::from_str(include_str!("../../dumped_data/bus_services.json")).unwrap();
+        let batch = round_trip("bus_services", &services.value);
+        assert_eq!(batch.schema().field(1).data_type(), &dictionary);
+        assert!(batch.schema().field(10).is_nullable());
+
+        let routes: BusRouteResp =
+            serde_json::from_str(include_str!("../../dumped_data/bus_route.json")).unwrap();
+        let batch = round_trip("bus_routes", &routes.value);
+        let wd_first = batch.schema().field_with_name("wd_first").unwrap().clone();
+        assert_eq!(
+            wd_first.data_type(),
+            &DataType::Time64(TimeUnit::Microsecond)
+        );
+        assert!(wd_first.is_nullable());
+
+        let stops: BusStopsResp =
+            serde_json::from_str(include_str!("../../dumped_data/bus_stops.json")).unwrap();
+        round_trip("bus_stops", &stops.value);
+
+        let rates: ErpRatesResp =
+            serde_json::from_str(include_str!("../../dumped_data/erp_rates.json")).unwrap();
+        let batch = round_trip("erp_rates", &rates.value);
+        assert_eq!(batch.schema().field(6).data_type(), &DataType::Date32);
+
+        let carparks: CarparkAvailResp =
+            serde_json::from_str(include_str!("../../dumped_data/carpark_avail.json")).unwrap();
+        let mut carparks = carparks.value;
+        carparks[0].coords = None;
+        let batch = round_trip("carparks", &carparks);
+        let coords = batch.schema().field_with_name("coords").unwrap().clone();
+        assert!(matches!(coords.data_type(), DataType::Struct(f) if f.len() == 2));
+        assert!(batch.column(3).is_null(0));
+
+        let times: EstTravelTimeResp =
+            serde_json::from_str(include_str!("../../dumped_data/est_travel_time.json")).unwrap();
+        round_trip("est_travel_time", &times.value);
+
+        let lights: FaultyTrafficLightResp =
+            serde_json::from_str(include_str!("../../dumped_data/faulty_traffic_lights.json"))
+                .unwrap();
+        let batch = round_trip("faulty_traffic_lights", &lights.value);
+        assert!(matches!(
+            batch.schema().field(3).data_type(),
+            DataType::Timestamp(TimeUnit::Microsecond, Some(tz)) if &**tz == "+08:00"
+        ));
+
+        let parking: BikeParkingResp =
+            serde_json::from_str(include_str!("../../dumped_data/bike_parking.json")).unwrap();
+        round_trip("bike_parking", &parking.value);
+
+        let taxis: TaxiAvailResp =
+            serde_json::from_str(include_str!("../../dumped_data/taxi_avail.json")).unwrap();
+        round_trip("taxi_avail", &taxis.value);
+
+        let stands: TaxiStandsResp =
+            serde_json::from_str(include_str!("../../dumped_data/taxi_stands.json")).unwrap();
+        let stands: Vec<TaxiStand> = stands.into();
+        round_trip("taxi_stands", &stands);
+
+        let alert: TrainServiceAlertResp =
+            serde_json::from_str(include_str!("../../dumped_data/train_service_alert.json"))
+                .unwrap();
+        let alert: TrainServiceAlert = alert.into();
+        round_trip("train_service_alert", &[alert]);
+
+        let csv = include_str!("../../dumped_data/transport_node_bus_201911.csv");
+        let volumes =
+            crate::crowd::passenger_vol::read_csv::<_, PassengerVolRecord>(csv.as_bytes())
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap();
+        round_trip("passenger_vol", &volumes);
+
+        let empty = to_record_batch::<BusStop>(&[]).unwrap();
+        assert_eq!(empty.num_rows(), 0);
+        assert_eq!(empty.schema(), schema::<BusStop>());
+
+        let err = from_record_batch::<TaxiStand>(&to_record_batch(&stops.value).unwrap());
+        assert!(matches!(err, Err(ArrowError::Column { column, .. }) if column == "taxi_code"));
+    }
+
+    #[test]
+    #[cfg(feature = "sqlite")]
+    fn storage() {
+        use crate::storage::{Dataset, Store};
+        use crate::traffic::traffic_incidents::{IncidentType, TrafficIncident};
+        use crate::traffic::traffic_speed_bands::{RoadCategory, TrafficSpeedBand};
+        use lta_utils_commons::chrono::{DateTime, Duration};
+        use lta_utils_commons::{sg_time, Location};
+        use std::collections::BTreeMap;
+
+        let mut store = Store::open_in_memory().unwrap();
+        let t0 = sg_time::to_sg(&DateTime::from_timestamp(1_600_000_000, 0).unwrap());
+        let t1 = t0 + Duration::minutes(5);
+        assert_eq!(store.last_fetch(Dataset::BusStops).unwrap(), None);
+
+        let stops: BusStopsResp =
+            serde_json::from_str(include_str!("../../dumped_data/bus_stops.json")).unwrap();
+        let mut stops = stops.value;
+        store.upsert_bus_stops(&stops, t0).unwrap();
+        stops[0].road_name = "Renamed Rd".to_string();
+        store.upsert_bus_stops(&stops[..1], t1).unwrap();
+        stops.sort_by_key(|s| s.bus_stop_code);
+        assert_eq!(store.bus_stops().unwrap(), stops);
+        let first = store.bus_stop(stops[0].bus_stop_code).unwrap();
+        assert_eq!(first.as_ref(), Some(&stops[0]));
+        assert_eq!(store.bus_stop(0).unwrap(), None);
+
+        let services: BusServiceResp =
+            serde_json::from_str(include_str!("../../dumped_data/bus_services.json")).unwrap();
+        let mut services = services.value;
+        store.upsert_bus_services(&services, t0).unwrap();
+        services
+            .sort_by(|a, b| (&a.service_no, a.no_direction).cmp(&(&b.service_no, b.no_direction)));
+        assert_eq!(store.bus_services().unwrap(), services);
+
+        let routes: BusRouteResp =
+            serde_json::from_str(include_str!("../../dumped_data/bus_route.json")).unwrap();
+        let mut routes = routes.value;
+        store.upsert_bus_routes(&routes, t0).unwrap();
+        routes.sort_by(|a, b| {
+            (&a.service_no, a.direction, a.stop_seq).cmp(&(&b.service_no, b.direction, b.stop_seq))
+        });
+        assert_eq!(store.bus_routes().unwrap(), routes);
+        let route = store
+            .route(&routes[0].service_no, routes[0].direction)
+            .unwrap();
+        assert!(route.windows(2).all(|w| w[0].stop_seq < w[1].stop_seq));
+        assert!(store
+            .routes_at(routes[0].bus_stop_code)
+            .unwrap()
+            .contains(&routes[0]));
+        let network = store.bus_network().unwrap();
+        let from_network = network.route(&routes[0].service_no, routes[0].direction);
+        assert_eq!(from_network, Some(&route[..]));
+
+        let stands: TaxiStandsResp =
+            serde_json::from_str(include_str!("../../dumped_data/taxi_stands.json")).unwrap();
+        let mut stands: Vec<TaxiStand> = stands.into();
+        store.upsert_taxi_stands(&stands, t0).unwrap();
+        stands.sort_by(|a, b| a.taxi_code.cmp(&b.taxi_code));
+        assert_eq!(store.taxi_stands().unwrap(), stands);
+
+        let parking: BikeParkingResp =
+            serde_json::from_str(include_str!("../../dumped_data/bike_parking.json")).unwrap();
+        store.upsert_bike_parking(&parking.value, t0).unwrap();
+        assert_eq!(store.bike_parking().unwrap().len(), parking.value.len());
+
+        // one row per vehicle type, the last rate of a key wins
+        fn flatten(rates: &[ErpRate]) -> BTreeMap<String, String> {
+            let mut rows = BTreeMap::new();
+            for r in rates {
+                for v in &r.vehicle_type {
+                    let key = (&r.zone_id, v, &r.day_type, r.start_time, r.effective_date);
+                    let value = (r.end_time, r.charge_amt);
+                    rows.insert(format!("{:?}", key), format!("{:?}", value));
+                }
+            }
+            rows
+        }
+        let rates: ErpRatesResp =
+            serde_json::from_str(include_str!("../../dumped_data/erp_rates.json")).unwrap();
+        store.upsert_erp_rates(&rates.value, t0).unwrap();
+        let stored = store.erp_rates().unwrap();
+        assert!(stored.len() <= rates.value.len());
+        assert_eq!(flatten(&stored), flatten(&rates.value));
+        let zone = rates.value[0].zone_id.clone();
+        let in_zone = store.erp_rates_in_zone(&zone).unwrap();
+        assert!(!in_zone.is_empty() && in_zone.iter().all(|r| r.zone_id == zone));
+
+        let carparks: CarparkAvailResp =
+            serde_json::from_str(include_str!("../../dumped_data/carpark_avail.json")).unwrap();
+        let mut carparks = carparks.value;
+        carparks[0].coords = None;
+        store.insert_carpark_avail(&carparks, t0).unwrap();
+        let mut later = carparks[0].clone();
+        later.avail_lots += 1;
+        store
+            .insert_carpark_avail(std::slice::from_ref(&later), t1)
+            .unwrap();
+        let latest = store.latest_carpark_avail().unwrap();
+        assert_eq!(latest.len(), carparks.len());
+        assert!(latest
+            .iter()
+            .any(|o| o.fetched_at == t1 && o.record == later));
+        let history = store.carpark_history(&later.carpark_id, t0, t1).unwrap();
+        let lots: Vec<u32> = history
+            .iter()
+            .filter(|o| o.record.lot_type == later.lot_type && o.record.agency == later.agency)
+            .map(|o| o.record.avail_lots)
+            .collect();
+        assert_eq!(lots, vec![carparks[0].avail_lots, later.avail_lots]);
+
+        let band = TrafficSpeedBand {
+            link_id: 103000000,
+            road_name: "KENT ROAD".to_string(),
+            road_category: RoadCategory::MinorArterialRoads,
+            speed_band: 3,
+            min_speed: 20,
+            max_speed: 29,
+            coord_start_end: Some(Location::new(1.3170, 103.8525, 1.3166, 103.8523)),
+        };
+        let unlocated = TrafficSpeedBand {
+            link_id: 103000001,
+            coord_start_end: None,
+            ..band.clone()
+        };
+        store
+            .insert_speed_bands(&[band.clone(), unlocated.clone()], t0)
+            .unwrap();
+        let faster = TrafficSpeedBand {
+            speed_band: 5,
+            ..band.clone()
+        };
+        store
+            .insert_speed_bands(std::slice::from_ref(&faster), t1)
+            .unwrap();
+        let latest: Vec<_> = store
+            .latest_speed_bands()
+            .unwrap()
+            .into_iter()
+            .map(|o| o.record)
+            .collect();
+        assert_eq!(latest, vec![unlocated, faster.clone()]);
+        let history = store.speed_band_history(band.link_id, t0, t1).unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!((history[0].fetched_at, &history[1].record), (t0, &faster));
+
+        let incident = TrafficIncident {
+            incident_type: IncidentType::RoadWorks,
+            lat: 1.3,
+            long: 103.8,
+            msg: "(1/1)09:00 Roadworks on PIE".to_string(),
+        };
+        store
+            .insert_incidents(std::slice::from_ref(&incident), t0)
+            .unwrap();
+        store
+            .insert_incidents(std::slice::from_ref(&incident), t0)
+            .unwrap();
+        let incidents = store.incidents(t0, t1).unwrap();
+        assert_eq!(incidents.len(), 1);
+        assert_eq!(incidents[0].record, incident);
+        assert!(store.incidents(t1, t1).unwrap().is_empty());
+
+        assert_eq!(store.last_fetch(Dataset::BusStops).unwrap(), Some(t1));
+        assert_eq!(store.last_fetch(Dataset::ErpRates).unwrap(), Some(t0));
+        assert_eq!(
+            store.last_fetch(Dataset::TrafficSpeedBands).unwrap(),
+            Some(t1)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "sqlite")]
+    fn sync_snapshots() {
+        use crate::storage::{Dataset, Store};
+        use crate::sync::{self, FieldChange};
+        use lta_utils_commons::chrono::{DateTime, Duration};
+        use lta_utils_commons::sg_time;
+
+        let mut store = Store::open_in_memory().unwrap();
+        let t0 = sg_time::to_sg(&DateTime::from_timestamp(1_600_000_000, 0).unwrap());
+        let t1 = t0 + Duration::days(1);
+
+        let stops: BusStopsResp =
+            serde_json::from_str(include_str!("../../dumped_data/bus_stops.json")).unwrap();
+        let stops = stops.value;
+        let report = store.sync_bus_stops(stops.clone(), t0).unwrap();
+        assert_eq!(
+            (report.added.len(), report.len()),
+            (stops.len(), stops.len())
+        );
+        assert!(store.sync_bus_stops(stops.clone(), t1).unwrap().is_empty());
+
+        let mut new = stops[1..].to_vec();
+        new[0].desc = "Opp Blk 1".to_string();
+        let mut added = stops[0].clone();
+        added.bus_stop_code = 99999;
+        new.push(added.clone());
+        let report = store.sync_bus_stops(new, t1).unwrap();
+        assert_eq!(report.added, vec![added]);
+        assert_eq!(report.removed, vec![stops[0].clone()]);
+        assert_eq!(report.modified.len(), 1);
+        assert_eq!(report.modified[0].key, stops[1].bus_stop_code);
+        assert_eq!(
+            report.modified[0].fields,
+            vec![FieldChange::new(
+                "desc",
+                &stops[1].desc,
+                &"Opp Blk 1".to_string()
+            )]
+        );
+        assert_eq!(report.unchanged, stops.len() - 2);
+        assert_eq!(store.bus_stop(stops[0].bus_stop_code).unwrap(), None);
+        assert_eq!(
+            store.bus_stop(99999).unwrap().unwrap().road_name,
+            stops[0].road_name
+        );
+        assert_eq!(store.last_fetch(Dataset::BusStops).unwrap(), Some(t1));
+
+        let routes: BusRouteResp =
+            serde_json::from_str(include_str!("../../dumped_data/bus_route.json")).unwrap();
+        let routes = routes.value;
+        store.sync_bus_routes(routes.clone(), t0).unwrap();
+        let mut new = routes.clone();
+        new[0].wd_first = None;
+        new[0].dist += 0.5;
+        let report = store.sync_bus_routes(new, t1).unwrap();
+        let fields: Vec<_> = report.modified[0].fields.iter().map(|f| f.field).collect();
+        assert_eq!(fields, vec!["dist", "wd_first"]);
+        let key = (
+            routes[0].service_no.clone(),
+            routes[0].direction,
+            routes[0].stop_seq,
+        );
+        assert_eq!(report.modified[0].key, key);
+        let stored = store.route(&key.0, key.1).unwrap();
+        assert_eq!(
+            stored
+                .iter()
+                .find(|r| r.stop_seq == key.2)
+                .unwrap()
+                .wd_first,
+            None
+        );
+
+        let services: BusServiceResp =
+            serde_json::from_str(include_str!("../../dumped_data/bus_services.json")).unwrap();
+        let report = store.sync_bus_services(services.value.clone(), t0).unwrap();
+        assert_eq!(report.added.len(), services.value.len());
+        let report = store.sync_bus_services(vec![], t1).unwrap();
+        assert_eq!(report.removed.len(), services.value.len());
+        assert!(store.bus_services().unwrap().is_empty());
+
+        let stands: TaxiStandsResp =
+            serde_json::from_str(include_str!("../../dumped_data/taxi_stands.json")).unwrap();
+        let stands: Vec<TaxiStand> = stands.into();
+        store.sync_taxi_stands(stands.clone(), t0).unwrap();
+        let mut new = stands.clone();
+        new[0].is_barrier_free = !new[0].is_barrier_free;
+        let report = store.sync_taxi_stands(new, t1).unwrap();
+        assert_eq!(report.modified[0].key, stands[0].taxi_code);
+        assert_eq!(report.modified[0].fields[0].field, "is_barrier_free");
+
+        // rates are compared per vehicle type
+        let rates: ErpRatesResp =
+            serde_json::from_str(include_str!("../../dumped_data/erp_rates.json")).unwrap();
+        let rates = rates.value;
+        let report = store.sync_erp_rates(rates.clone(), t0).unwrap();
+        assert!(report.added.iter().all(|r| r.vehicle_type.len() == 1));
+        assert!(store.sync_erp_rates(rates.clone(), t1).unwrap().is_empty());
+        let grouped = rates.iter().position(|r| r.vehicle_type.len() > 1).unwrap();
+        let mut new = rates.clone();
+        new[grouped].charge_amt += 1.0;
+        let report = store.sync_erp_rates(new.clone(), t1).unwrap();
+        assert!(report.modified.len() >= rates[grouped].vehicle_type.len());
+        assert!(report
+            .modified
+            .iter()
+            .all(|m| m.fields[0].field == "charge_amt"));
+        assert!(sync::diff(store.erp_rates().unwrap(), new).is_empty());
+
+        // keys cover every vehicle type, rates without any are not synced
+        use crate::sync::SyncRecord;
+        let cars = VehicleType::PassengerCars;
+        let taxis = VehicleType::Taxis;
+        let rate = |vehicle_type: Vec<VehicleType>| ErpRate {
+            vehicle_type,
+            ..rates[0].clone()
+        };
+        let none = rate(vec![]);
+        let both = rate(vec![cars.clone(), taxis.clone()]);
+        assert!(none.key().vehicle_types.is_empty());
+        assert_eq!(both.key().vehicle_types, vec![cars.clone(), taxis.clone()]);
+        assert_ne!(both.key(), rate(vec![cars.clone()]).key());
+        assert_eq!(ErpRate::split(vec![none.clone()]), vec![]);
+
+        let report = sync::diff(
+            vec![both.clone()],
+            vec![rate(vec![cars.clone()]), none.clone()],
+        );
+        assert_eq!(report.removed, vec![rate(vec![taxis.clone()])]);
+        assert_eq!((report.added.len(), report.unchanged), (0, 1));
+
+        let mut store = Store::open_in_memory().unwrap();
+        store.sync_erp_rates(vec![both, none.clone()], t0).unwrap();
+        assert_eq!(store.erp_rates().unwrap().len(), 1);
+        let report = store.sync_erp_rates(vec![none], t1).unwrap();
+        assert_eq!(report.removed.len(), 2);
+        assert!(store.erp_rates().unwrap().is_empty());
+    }
+
+    /// Service A from 1001 to 1003 and back, weekday buses run until 00:30
+    #[cfg(feature = "bus")]
+    fn sample_network() -> crate::bus::network::BusNetwork {
+        use crate::bus::bus_services::BusFreq;
+        use crate::bus::network::BusNetwork;
+        use crate::bus_enums::{BusCategory, Operator};
+        use lta_utils_commons::chrono::NaiveTime;
+
+        let time = |h, m| NaiveTime::from_hms_opt(h, m, 0).unwrap();
+        let stop = |code, desc: &str| BusStop {
+            bus_stop_code: code,
+            road_name: "Road".into(),
+            desc: desc.into(),
+            lat: 1.3,
+            long: 103.8,
+        };
+        let route = |direction, seq, code, dist, first| BusRoute {
+            service_no: "A".into(),
+            operator: Operator::SBST,
+            direction,
+            stop_seq: seq,
+            bus_stop_code: code,
+            dist,
+            wd_first: Some(first),
+            wd_last: Some(time(0, 30)),
+            sat_first: Some(first),
+            sat_last: Some(time(23, 0)),
+            sun_first: None,
+            sun_last: None,
+        };
+        let service = |direction| BusService {
+            service_no: "A".into(),
+            operator: Operator::SBST,
+            no_direction: direction,
+            category: BusCategory::Feeder,
+            origin_code: 1001,
+            dest_code: 1003,
+            am_peak_freq: BusFreq::new(5, 8),
+            am_offpeak_freq: BusFreq::new(10, 12),
+            pm_peak_freq: BusFreq::new(5, 8),
+            pm_offpeak_freq: BusFreq::new(10, 12),
+            loop_desc: None,
+        };
+
+        BusNetwork::new(
+            vec![stop(1001, "Start"), stop(1002, ""), stop(1003, "End")],
+            vec![service(1), service(2)],
+            vec![
+                route(1, 1, 1001, 0.0, time(6, 0)),
+                route(1, 2, 1002, 1.2, time(6, 5)),
+                route(1, 3, 1003, 2.0, time(6, 9)),
+                route(2, 1, 1003, 0.0, time(6, 0)),
+                route(2, 2, 1001, 2.1, time(6, 10)),
+            ],
+        )
+    }
+
+    #[test]
+    #[cfg(feature = "gtfs")]
+    fn gtfs_export() {
+        use crate::gtfs::{GtfsExport, GtfsTime, StopTime};
+        use lta_utils_commons::chrono::NaiveDate;
+        use std::io::{Cursor, Read};
+
+        let network = sample_network();
+        let start = NaiveDate::from_ymd_opt(2019, 11, 1).unwrap();
+        let end = NaiveDate::from_ymd_opt(2019, 12, 31).unwrap();
+        let christmas = NaiveDate::from_ymd_opt(2019, 12, 25).unwrap();
+        let feed = GtfsExport::new(&network, start, end)
+            .with_holidays(vec![christmas])
+            .build();
+
+        assert_eq!(feed.validate(), vec![]);
+        assert_eq!(feed.agency.len(), 1);
+        assert_eq!(feed.stops[1].stop_name, "Road");
+        assert_eq!(feed.routes[0].route_long_name, "Start - End");
+        assert_eq!(feed.routes[0].route_type, 704);
+
+        // no Sunday timings, so no Sunday trips
+        let trip_ids: Vec<_> = feed.trips.iter().map(|t| t.trip_id.as_str()).collect();
+        assert_eq!(trip_ids, vec!["A_1_WD", "A_1_SAT", "A_2_WD", "A_2_SAT"]);
+        assert_eq!(feed.trips[2].direction_id, 1);
+        assert_eq!(feed.trips[0].trip_headsign, "End");
+
+        let times: Vec<_> = feed.stop_times[..3]
+            .iter()
+            .map(|st| st.arrival_time.to_string())
+            .collect();
+        assert_eq!(times, vec!["06:00:00", "06:05:00", "06:09:00"]);
+        assert_eq!(feed.stop_times[2].stop_id, "01003");
+
+        // weekday buses run past midnight, split by headway band
+        let wd: Vec<_> = feed
+            .frequencies
+            .iter()
+            .filter(|f| f.trip_id == "A_1_WD")
+            .map(|f| {
+                (
+                    f.start_time.to_string(),
+                    f.end_time.to_string(),
+                    f.headway_secs,
+                )
+            })
+            .collect();
+        assert_eq!(
+            wd,
+            vec![
+                ("06:00:00".to_string(), "06:30:00".to_string(), 660),
+                ("06:30:00".to_string(), "08:31:00".to_string(), 360),
+                ("08:31:00".to_string(), "17:00:00".to_string(), 660),
+                ("17:00:00".to_string(), "19:01:00".to_string(), 360),
+                ("19:01:00".to_string(), "24:30:00".to_string(), 660),
+            ]
+        );
+
+        let dates: Vec<_> = feed
+            .calendar_dates
+            .iter()
+            .map(|c| (c.service_id.as_str(), c.exception_type))
+            .collect();
+        assert_eq!(dates, vec![("WD", 2), ("SUN", 1)]);
+
+        let zip = feed.write_zip(Cursor::new(Vec::new())).unwrap();
+        let mut archive = zip::ZipArchive::new(zip).unwrap();
+        assert_eq!(archive.len(), 8);
+        let mut stop_times = String::new();
+        archive
+            .by_name("stop_times.txt")
+            .unwrap()
+            .read_to_string(&mut stop_times)
+            .unwrap();
+        assert!(stop_times.starts_with(
+            "trip_id,arrival_time,departure_time,stop_id,stop_sequence,shape_dist_traveled\n\
+             A_1_WD,06:00:00,06:00:00,01001,1,0.0\n"
+        ));
+
+        let mut broken = feed.clone();
+        broken.stop_times.push(StopTime {
+            trip_id: "A_1_WD".into(),
+            arrival_time: GtfsTime::from_hms(5, 0, 0),
+            departure_time: GtfsTime::from_hms(5, 0, 0),
+            stop_id: "99999".into(),
+            stop_sequence: 4,
+            shape_dist_traveled: 1.0,
+        });
+        let issues: Vec<_> = broken.validate().iter().map(|i| i.to_string()).collect();
+        assert_eq!(
+            issues,
+            vec![
+                "stop_times.txt `A_1_WD`: unknown stop_id `99999`",
+                "stop_times.txt `A_1_WD`: time goes back at stop 4",
+                "stop_times.txt `A_1_WD`: shape_dist_traveled goes back at stop 4",
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "gtfs")]
+    fn gtfs_realtime() {
+        use crate::gtfs::realtime::{FeedMessage, Message, OccupancyStatus, RealtimeFeed};
+        use crate::gtfs::GtfsExport;
+        use lta_utils_commons::chrono::NaiveDate;
+        use lta_utils_commons::sg_time;
+
+        let network = sample_network();
+        let start = NaiveDate::from_ymd_opt(2019, 11, 1).unwrap();
+        let end = NaiveDate::from_ymd_opt(2019, 12, 31).unwrap();
+        let feed = GtfsExport::new(&network, start, end).build();
+        let realtime = RealtimeFeed::new(&feed);
+
+        let bus = |origin, arrival: &str, lat| {
+            format!(
+                r#"{{"OriginCode": "{}", "DestinationCode": "01003",
+                "EstimatedArrival": "{}", "Latitude": "{}", "Longitude": "{}",
+                "VisitNumber": "1", "Load": "SDA", "Feature": "WAB", "Type": "SD"}}"#,
+                origin,
+                arrival,
+                lat,
+                if lat == "0" { "0" } else { "103.8" }
+            )
+        };
+        let arrivals = |stop, buses: [String; 3]| -> BusArrivalResp {
+            let json = format!(
+                r#"{{"BusStopCode": "{}", "Services": [{{"ServiceNo": "A", "Operator": "SBST",
+                "NextBus": {}, "NextBus2": {}, "NextBus3": {}}}]}}"#,
+                stop, buses[0], buses[1], buses[2]
+            );
+            serde_json::from_str::<RawBusArrivalResp>(&json)
+                .unwrap()
+                .into()
+        };
+
+        let polled = vec![
+            arrivals(
+                "01002",
+                [
+                    bus("01001", "2019-11-19T09:05:00+08:00", "1.3"),
+                    bus("01001", "2019-11-19T09:16:00+08:00", "0"),
+                    // unknown origin, does not match any trip
+                    bus("09999", "2019-11-19T09:20:00+08:00", "1.3"),
+                ],
+            ),
+            arrivals(
+                "01003",
+                [
+                    // the 09:05 bus at the next stop
+                    bus("01001", "2019-11-19T09:09:30+08:00", "1.3"),
+                    // after midnight on the Tuesday service day
+                    bus("01001", "2019-11-20T00:24:00+08:00", "1.3"),
+                    "{}".to_string(),
+                ],
+            ),
+        ];
+
+        let now = sg_time::at(
+            NaiveDate::from_ymd_opt(2019, 11, 19).unwrap(),
+            lta_utils_commons::chrono::NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+        );
+        let msg = realtime.trip_updates(&polled, now);
+        assert_eq!(msg.header.gtfs_realtime_version, "2.0");
+        assert_eq!(FeedMessage::decode(&msg.encode_to_vec()[..]).unwrap(), msg);
+
+        let trips: Vec<_> = msg
+            .entity
+            .iter()
+            .map(|e| {
+                let update = e.trip_update.as_ref().unwrap();
+                let stops: Vec<_> = update
+                    .stop_time_update
+                    .iter()
+                    .map(|s| (s.stop_sequence.unwrap(), s.stop_id.clone().unwrap()))
+                    .collect();
+                (
+                    update.trip.trip_id.clone().unwrap(),
+                    update.trip.start_date.clone().unwrap(),
+                    update.trip.start_time.clone().unwrap(),
+                    stops,
+                )
+            })
+            .collect();
+        assert_eq!(
+            trips,
+            vec![
+                (
+                    "A_1_WD".to_string(),
+                    "20191119".to_string(),
+                    "09:00:00".to_string(),
+                    vec![(2, "01002".to_string()), (3, "01003".to_string())]
+                ),
+                (
+                    "A_1_WD".to_string(),
+                    "20191119".to_string(),
+                    "09:11:00".to_string(),
+                    vec![(2, "01002".to_string())]
+                ),
+                (
+                    "A_1_WD".to_string(),
+                    "20191119".to_string(),
+                    "24:15:00".to_string(),
+                    vec![(3, "01003".to_string())]
+                ),
+            ]
+        );
+        let arrival = msg.entity[0].trip_update.as_ref().unwrap().stop_time_update[1]
+            .arrival
+            .as_ref()
+            .unwrap();
+        assert_eq!(arrival.time, Some(1574125770));
+
+        // the 09:16 bus has no position
+        let msg = realtime.vehicle_positions(&polled, now);
+        assert_eq!(msg.entity.len(), 2);
+        let vehicle = msg.entity[0].vehicle.as_ref().unwrap();
+        assert_eq!(vehicle.position.as_ref().unwrap().latitude, 1.3);
+        assert_eq!(
+            vehicle.occupancy_status,
+            Some(OccupancyStatus::StandingRoomOnly as i32)
+        );
+        assert_eq!(msg.entity[0].id, "A_1_WD_20191119_090000");
+    }
+
+    #[test]
+    #[cfg(feature = "traffic")]
     fn carpark_avail() {
         gen_test!(
             CarparkAvailResp,
@@ -96,6 +1832,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "traffic")]
     fn erp_rates() {
         gen_test!(
             ErpRatesResp,
@@ -105,6 +1842,139 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "traffic")]
+    fn erp_charges() {
+        use crate::traffic::erp_charges::ErpRateTable;
+        use lta_utils_commons::chrono::{NaiveDate, NaiveTime, TimeZone, Utc};
+        use lta_utils_commons::sg_time;
+
+        let rates: ErpRatesResp =
+            serde_json::from_str(include_str!("../../dumped_data/erp_rates.json")).unwrap();
+        let mut rates = rates.value;
+        let raise = r#"{"VehicleType": "Taxis", "DayType": "Weekdays", "StartTime": "08:05",
+            "EndTime": "08:55", "ZoneID": "AY1", "ChargeAmount": 1.5, "EffectiveDate": "2020-02-03"}"#;
+        rates.push(serde_json::from_str(raise).unwrap());
+
+        let christmas = NaiveDate::from_ymd_opt(2019, 12, 25).unwrap();
+        let table = ErpRateTable::new(rates).with_holidays(vec![christmas]);
+        let time = |h, m| NaiveTime::from_hms_opt(h, m, 0).unwrap();
+        let tuesday = NaiveDate::from_ymd_opt(2019, 11, 19).unwrap();
+
+        let at = sg_time::at(tuesday, time(8, 47));
+        assert_eq!(table.charge(&ZoneId::AY1, &VehicleType::Taxis, &at), 1.0);
+        assert_eq!(
+            table.charge(&ZoneId::AY1, &VehicleType::Motorcycles, &at),
+            0.5
+        );
+        let at = sg_time::at(tuesday, time(8, 55));
+        assert_eq!(table.charge(&ZoneId::AY1, &VehicleType::Taxis, &at), 0.5);
+
+        // 00:47 UTC is 08:47 in Singapore
+        let utc = Utc.with_ymd_and_hms(2019, 11, 19, 0, 47, 0).unwrap();
+        assert_eq!(table.charge(&ZoneId::AY1, &VehicleType::Taxis, &utc), 1.0);
+
+        let sunday = NaiveDate::from_ymd_opt(2019, 11, 24).unwrap();
+        assert!(table
+            .schedule(&ZoneId::AY1, &VehicleType::Taxis, sunday)
+            .is_empty());
+        let at = sg_time::at(christmas, time(8, 47));
+        assert_eq!(table.charge(&ZoneId::AY1, &VehicleType::Taxis, &at), 0.0);
+        let before = NaiveDate::from_ymd_opt(2019, 11, 15).unwrap();
+        assert!(table
+            .schedule(&ZoneId::AY1, &VehicleType::Taxis, before)
+            .is_empty());
+
+        let schedule = table.schedule(&ZoneId::AY1, &VehicleType::Taxis, tuesday);
+        let starts: Vec<_> = schedule.iter().map(|s| s.start).collect();
+        assert_eq!(
+            starts,
+            vec![time(7, 0), time(8, 0), time(8, 5), time(8, 55), time(9, 0)]
+        );
+
+        // the raised rate replaces the whole weekday schedule from its effective date
+        let later = NaiveDate::from_ymd_opt(2020, 2, 4).unwrap();
+        let at = sg_time::at(later, time(8, 47));
+        assert_eq!(table.charge(&ZoneId::AY1, &VehicleType::Taxis, &at), 1.5);
+        assert_eq!(
+            table
+                .schedule(&ZoneId::AY1, &VehicleType::Taxis, later)
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "traffic")]
+    fn erp_zones() {
+        use crate::traffic::erp_zones::{Gantry, GantryError, GantryTable};
+
+        let file = "# exported gantries
+zone,road,location,lat,long
+
+CT1, Central Expressway, southbound, after Braddell Road, 1.3400, 103.8480
+XX1,New Road,,1.30,103.80
+CT1,Central Expressway,southbound slip road,1.3401,103.8481";
+        let loaded = GantryTable::from_reader(file.as_bytes()).unwrap();
+        assert_eq!(
+            loaded.gantries()[0],
+            Gantry::new(
+                ZoneId::CT1,
+                "Central Expressway",
+                "southbound, after Braddell Road",
+                1.34,
+                103.848
+            )
+        );
+        assert_eq!(loaded.gantries()[1].zone, ZoneId::Unknown("XX1".into()));
+        assert_eq!(loaded.gantries()[1].location, "");
+        assert_eq!(loaded.roads(&ZoneId::CT1), vec!["Central Expressway"]);
+        assert!(loaded.roads(&ZoneId::CBD).is_empty());
+        match GantryTable::from_reader("CT1,Central Expressway,1.34".as_bytes()) {
+            Err(GantryError::Parse(1, _)) => {}
+            other => panic!("{:?}", other),
+        }
+        match GantryTable::from_reader("\nCT1,CTE,here,north,103.85".as_bytes()) {
+            Err(GantryError::Parse(2, msg)) => assert!(msg.contains("north"), "{}", msg),
+            other => panic!("{:?}", other),
+        }
+
+        let table: GantryTable = serde_json::from_str::<Vec<Gantry>>(
+            r#"[
+                {"zone": "CT1", "road": "Central Expressway", "location": "southbound",
+                 "lat": 1.3400, "long": 103.8480},
+                {"zone": "CT6", "road": "Central Expressway", "location": "northbound",
+                 "lat": 1.3400, "long": 103.8490},
+                {"zone": "PE1", "road": "Pan Island Expressway", "location": "eastbound",
+                 "lat": 1.3270, "long": 103.8150}
+            ]"#,
+        )
+        .unwrap()
+        .into_iter()
+        .collect();
+        assert_eq!(table.in_zone(&ZoneId::CT6).count(), 1);
+        assert_eq!(table.in_zone(&ZoneId::CBD).count(), 0);
+        assert_eq!(GantryTable::default().nearest(1.34, 103.848), None);
+
+        // Points on the road, not on a gantry
+        let ct1 = table.gantries()[0].coordinates();
+        for (bearing, dist_m) in &[(0.0, 30.0), (180.0, 45.0), (270.0, 20.0)] {
+            let car = ct1.destination(*bearing, *dist_m);
+            let (gantry, dist) = table.nearest(car.lat, car.long).unwrap();
+            assert_eq!(gantry.zone, ZoneId::CT1);
+            assert!((dist - dist_m).abs() < 0.1, "{} {}", dist, dist_m);
+        }
+
+        // Halfway between the two CTE gantries, 55.6m from each
+        let both = table.within(1.3400, 103.8485, 100.0);
+        assert_eq!(both.len(), 2);
+        assert!((both[0].1 - both[1].1).abs() < 0.01);
+        assert!((both[0].1 - 55.6).abs() < 0.1, "{}", both[0].1);
+        assert_eq!(table.within(1.3400, 103.8485, 50.0), vec![]);
+        assert_eq!(table.nearest(1.3270, 103.8160).unwrap().0.zone, ZoneId::PE1);
+    }
+
+    #[test]
+    #[cfg(feature = "traffic")]
     fn faulty_traffic_lights() {
         gen_test!(
             FaultyTrafficLightResp,
@@ -114,6 +1984,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "crowd")]
     fn passenger_vol_bus_stops() {
         gen_test!(
             PassengerVolRawResp,
@@ -123,6 +1994,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "crowd")]
     fn passenger_vol_od_bus_stops() {
         gen_test!(
             PassengerVolRawResp,
@@ -132,6 +2004,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "crowd")]
     fn passenger_vol_od_train() {
         gen_test!(
             PassengerVolRawResp,
@@ -141,6 +2014,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "crowd")]
     fn passenger_vol_train() {
         gen_test!(
             PassengerVolRawResp,
@@ -150,6 +2024,127 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "crowd")]
+    fn passenger_vol_records() {
+        use crate::crowd::passenger_vol::{read_csv, DayType, PtType, VolZip};
+        use std::io::Cursor;
+
+        let csv = include_str!("../../dumped_data/transport_node_bus_201911.csv");
+        let records = read_csv::<_, PassengerVolRecord>(csv.as_bytes())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(records.len(), 8);
+        assert_eq!(records[4].pt_code, "01012");
+        assert_eq!(records[4].year_month, YearMonth::new(2019, 11).unwrap());
+        assert_eq!(records[1].day_type, DayType::WeekendsHoliday);
+        assert_eq!(
+            (records[2].hour, records[2].tap_in, records[2].tap_out),
+            (13, 2163, 1733)
+        );
+
+        let zip = include_bytes!("../../dumped_data/origin_destination_train_201911.zip");
+        let mut zip = VolZip::new(Cursor::new(&zip[..])).unwrap();
+        assert_eq!(
+            zip.file_name().unwrap(),
+            "origin_destination_train_201911.csv"
+        );
+        let od = zip
+            .records::<OdPassengerVolRecord>()
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(od.len(), 8);
+        assert_eq!(od[0].pt_type, PtType::Train);
+        assert_eq!(od[0].origin_pt_code, "NS1-EW24");
+        assert_eq!(od[0].destination_pt_code, "NS24-NE6-CC1");
+        assert_eq!(od[0].total_trips, 1420);
+
+        let bad = "YEAR_MONTH,DAY_TYPE,TIME_PER_HOUR,PT_TYPE,PT_CODE,TOTAL_TAP_IN_VOLUME,TOTAL_TAP_OUT_VOLUME
+2019-11,WEEKDAY,17,BUS,45379,48,29
+2019-13,WEEKDAY,17,BUS,45379,48,29";
+        let err = read_csv::<_, PassengerVolRecord>(bad.as_bytes())
+            .nth(1)
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(err.path, "line 3");
+        assert!(VolZip::new(Cursor::new(&b"not a zip"[..])).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "crowd")]
+    fn passenger_vol_analytics() {
+        use crate::crowd::analytics::*;
+        use crate::crowd::passenger_vol::{read_csv, DayType};
+
+        let csv = include_str!("../../dumped_data/origin_destination_train_201911.csv");
+        let matrix =
+            OdMatrix::try_from_records(read_csv::<_, OdPassengerVolRecord>(csv.as_bytes()))
+                .unwrap();
+        assert_eq!(matrix.len(), 6);
+        assert_eq!(matrix.trips("NS1-EW24", "NS24-NE6-CC1"), 1732);
+        assert_eq!(matrix.trips("NS24-NE6-CC1", "NS1-EW24"), 1689);
+        assert_eq!(matrix.trips("NS1-EW24", "BP1-NS4"), 0);
+        assert_eq!(matrix.trips_to("EW13-NS25"), 263);
+        assert_eq!(matrix.total_trips(), 4707);
+        assert_eq!(
+            matrix.top_origins(2),
+            vec![("NS1-EW24", 1732), ("NS24-NE6-CC1", 1689)]
+        );
+        assert_eq!(matrix.top_destinations(1), vec![("NS24-NE6-CC1", 1732)]);
+        assert_eq!(
+            matrix.top_pairs(1),
+            vec![("NS1-EW24", "NS24-NE6-CC1", 1732)]
+        );
+
+        let profile =
+            od_hourly_profile(read_csv::<_, OdPassengerVolRecord>(csv.as_bytes())).unwrap();
+        assert_eq!(profile.weekday[8], 2355);
+        assert_eq!(profile.weekends_holiday[18], 401);
+        assert_eq!(profile.peak_hour(&DayType::Weekday), Some(8));
+        assert_eq!(profile.total(), 4707);
+        let od: Vec<OdPassengerVolRecord> = read_csv(csv.as_bytes()).map(Result::unwrap).collect();
+        let mut extended = HourlyProfile::new();
+        extended.extend(&od);
+        assert_eq!(extended, profile);
+        assert_eq!(od.into_iter().collect::<HourlyProfile>(), profile);
+        let header = csv.lines().next().unwrap();
+        let bad = format!("{}\n2019-11,WEEKDAY,x,TRAIN,A,B,1\n", header);
+        assert!(od_hourly_profile(read_csv::<_, OdPassengerVolRecord>(bad.as_bytes())).is_err());
+
+        let nov = include_str!("../../dumped_data/transport_node_bus_201911.csv");
+        let nov = TapProfiles::try_from_records(read_csv::<_, PassengerVolRecord>(nov.as_bytes()))
+            .unwrap();
+        let stop = nov.get("80051").unwrap();
+        assert_eq!(stop.tap_in.weekday[13], 2163);
+        assert_eq!(stop.tap_out.weekends_holiday[13], 1167);
+        assert_eq!(nov.network().tap_in.peak_hour(&DayType::Weekday), Some(13));
+
+        let oct = "YEAR_MONTH,DAY_TYPE,TIME_PER_HOUR,PT_TYPE,PT_CODE,TOTAL_TAP_IN_VOLUME,TOTAL_TAP_OUT_VOLUME
+2019-10,WEEKDAY,13,BUS,80051,2000,1700
+2019-10,WEEKDAY,7,BUS,01012,2056,1457
+2019-10,WEEKDAY,7,BUS,99009,50,50";
+        let oct = TapProfiles::try_from_records(read_csv::<_, PassengerVolRecord>(oct.as_bytes()))
+            .unwrap();
+        let cmp = compare_months(
+            YearMonth::new(2019, 10).unwrap(),
+            &oct.totals(),
+            YearMonth::new(2019, 11).unwrap(),
+            &nov.totals(),
+        );
+        assert_eq!(cmp.changes[0].pt_code, "80051");
+        assert_eq!(cmp.changes[0].diff(), 2582);
+        assert_eq!(cmp.added().count(), 2);
+        assert_eq!(
+            cmp.removed()
+                .map(|c| c.pt_code.as_str())
+                .collect::<Vec<_>>(),
+            vec!["99009"]
+        );
+        assert_eq!(cmp.curr_total(), nov.totals().total());
+    }
+
+    #[test]
+    #[cfg(feature = "taxi")]
     fn taxi_avail() {
         gen_test!(
             TaxiAvailResp,
@@ -159,6 +2154,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "taxi")]
     fn taxi_stands() {
         gen_test!(
             TaxiStandsResp,
@@ -168,6 +2164,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "train")]
     fn train_service_alert() {
         gen_test!(
             TrainServiceAlertResp,
@@ -177,6 +2174,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "traffic")]
     fn est_travel_time() {
         gen_test!(
             EstTravelTimeResp,
@@ -184,4 +2182,119 @@ mod tests {
             "../../dumped_data/est_travel_time.json"
         );
     }
+
+    #[test]
+    #[cfg(all(feature = "bus", feature = "train"))]
+    fn unknown_enum_values() {
+        use crate::bus_enums::{BusCategory, Operator};
+        use crate::diagnostics::{collect_unknowns, UnknownValue};
+
+        let (parsed, unknowns) = collect_unknowns(|| {
+            let ops: Vec<Operator> = serde_json::from_str(r#"["SBST", "NEWCO"]"#).unwrap();
+            let cats: Vec<BusCategory> =
+                serde_json::from_str(r#"["FLAT FARE $2.00", "SHUTTLE"]"#).unwrap();
+            let status: TrainStatus = serde_json::from_str("3").unwrap();
+            (ops, cats, status)
+        });
+
+        assert_eq!(
+            parsed.0,
+            vec![Operator::SBST, Operator::Unknown("NEWCO".into())]
+        );
+        assert_eq!(
+            parsed.1,
+            vec![
+                BusCategory::FlatFareTwoDollar,
+                BusCategory::Unknown("SHUTTLE".into())
+            ]
+        );
+        assert_eq!(parsed.2, TrainStatus::Unknown(3));
+        assert_eq!(
+            serde_json::to_string(&parsed.1).unwrap(),
+            r#"["FlatFareTwoDollar","SHUTTLE"]"#
+        );
+        assert_eq!(
+            unknowns,
+            vec![
+                UnknownValue {
+                    type_name: "Operator",
+                    value: "NEWCO".into()
+                },
+                UnknownValue {
+                    type_name: "BusCategory",
+                    value: "SHUTTLE".into()
+                },
+                UnknownValue {
+                    type_name: "TrainStatus",
+                    value: "3".into()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "bus")]
+    fn parse_modes() {
+        use crate::parse::{parse_page, ParseMode};
+
+        let page = br#"{"value": [
+            {"ServiceNo": "10", "Operator": "SBST", "Direction": 1, "Category": "TRUNK",
+             "OriginCode": "75009", "DestinationCode": "16009", "AM_Peak_Freq": "08-12",
+             "AM_Offpeak_Freq": "-", "PM_Peak_Freq": "10-12", "PM_Offpeak_Freq": "11-15",
+             "LoopDesc": ""},
+            {"ServiceNo": "11", "Operator": "SBST", "Direction": 1, "Category": "TRUNK",
+             "OriginCode": "75009", "DestinationCode": "16009", "AM_Peak_Freq": "abc",
+             "AM_Offpeak_Freq": "-", "PM_Peak_Freq": "-", "PM_Offpeak_Freq": "-",
+             "LoopDesc": ""}
+        ]}"#;
+
+        let err =
+            parse_page::<BusServiceResp, Vec<BusService>>(page, ParseMode::Strict).unwrap_err();
+        assert_eq!(err.path, "value[1].AM_Peak_Freq");
+
+        let parsed =
+            parse_page::<BusServiceResp, Vec<BusService>>(page, ParseMode::Lenient).unwrap();
+        assert_eq!(parsed.data.len(), 1);
+        assert_eq!(parsed.data[0].service_no, "10");
+        assert_eq!(parsed.errors.len(), 1);
+        assert_eq!(parsed.errors[0].index, 1);
+        assert_eq!(parsed.errors[0].error.path, "value[1].AM_Peak_Freq");
+    }
+
+    #[test]
+    #[cfg(all(feature = "bus", feature = "traffic"))]
+    fn sg_timestamps() {
+        use lta_utils_commons::chrono::{NaiveDate, NaiveTime};
+
+        let data: FaultyTrafficLightResp = serde_json::from_str(
+            r#"{"value": [{"AlarmID": "GL703034136", "NodeID": "703034136", "Type": 4,
+                "StartDate": "2014-04-12 01:58:00.0", "EndDate": "", "Message": "(12/4)1:58 Flashing Yellow"}]}"#,
+        )
+        .unwrap();
+        let start = data.value[0].start_date.unwrap();
+        assert_eq!(start.to_rfc3339(), "2014-04-12T01:58:00+08:00");
+        assert_eq!(data.value[0].end_date, None);
+
+        let arrival: RawBusArrivalResp =
+            serde_json::from_str(include_str!("../../dumped_data/bus_arrival.json")).unwrap();
+        let next = arrival.services[0].next_bus.as_ref().unwrap();
+        assert_eq!(next.est_arrival.offset().local_minus_utc(), 8 * 3600);
+
+        let routes: Vec<BusRoute> =
+            serde_json::from_str::<BusRouteResp>(include_str!("../../dumped_data/bus_route.json"))
+                .unwrap()
+                .into();
+        let mut route = routes[0].clone();
+        route.wd_first = NaiveTime::from_hms_opt(5, 30, 0);
+        route.wd_last = NaiveTime::from_hms_opt(0, 15, 0);
+        let tuesday = NaiveDate::from_ymd_opt(2019, 12, 31).unwrap();
+        assert_eq!(
+            route.first_bus_on(tuesday).unwrap().to_rfc3339(),
+            "2019-12-31T05:30:00+08:00"
+        );
+        assert_eq!(
+            route.last_bus_on(tuesday).unwrap().to_rfc3339(),
+            "2020-01-01T00:15:00+08:00"
+        );
+    }
 }
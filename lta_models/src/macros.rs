@@ -0,0 +1,203 @@
+//! Macros for enums that keep values they do not recognise
+
+/// Generates a string based enum with an extra `Unknown(String)` variant.
+///
+/// Each variant is parsed from its own name and any aliases listed after `|`.
+/// Known variants serialise to their name while `Unknown` serialises to the
+/// value it was created from, so data round trips unchanged.
+#[cfg(any(
+    feature = "bus",
+    feature = "traffic",
+    feature = "taxi",
+    feature = "train",
+    feature = "crowd"
+))]
+macro_rules! str_enum {
+    (
+        $(#[$meta:meta])*
+        $vis:vis enum $name:ident {
+            $(
+                $(#[$vmeta:meta])*
+                $variant:ident $(| $alias:literal)*
+            ),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        $vis enum $name {
+            $(
+                $(#[$vmeta])*
+                $variant,
+            )*
+
+            /// Value not recognised by lta-rs, kept as received
+            Unknown(String),
+        }
+
+        impl $name {
+            /// String representation used when serialising
+            pub fn as_str(&self) -> &str {
+                match self {
+                    $($name::$variant => stringify!($variant),)*
+                    $name::Unknown(s) => s.as_str(),
+                }
+            }
+
+            /// Returns `true` if the value was not recognised
+            pub fn is_unknown(&self) -> bool {
+                matches!(self, $name::Unknown(_))
+            }
+        }
+
+        impl std::str::FromStr for $name {
+            type Err = std::convert::Infallible;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                let res = match s {
+                    $(stringify!($variant) $(| $alias)* => $name::$variant,)*
+                    _ => {
+                        $crate::diagnostics::report(stringify!($name), s);
+                        $name::Unknown(s.to_string())
+                    }
+                };
+
+                Ok(res)
+            }
+        }
+
+        impl std::fmt::Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str(self.as_str())
+            }
+        }
+
+        impl serde::Serialize for $name {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: serde::Serializer,
+            {
+                serializer.serialize_str(self.as_str())
+            }
+        }
+
+        impl<'de> serde::Deserialize<'de> for $name {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+            {
+                let s = String::deserialize(deserializer)?;
+                match s.parse() {
+                    Ok(v) => Ok(v),
+                    Err(e) => match e {},
+                }
+            }
+        }
+    };
+}
+
+/// Same as `str_enum` but for enums the API sends as integers.
+/// Unrecognised values are kept in `Unknown(u32)`.
+#[cfg(any(feature = "traffic", feature = "train"))]
+macro_rules! int_enum {
+    (
+        $(#[$meta:meta])*
+        $vis:vis enum $name:ident {
+            $(
+                $(#[$vmeta:meta])*
+                $variant:ident = $val:literal
+            ),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        $vis enum $name {
+            $(
+                $(#[$vmeta])*
+                $variant,
+            )*
+
+            /// Value not recognised by lta-rs, kept as received
+            Unknown(u32),
+        }
+
+        impl $name {
+            /// Integer representation used when serialising
+            pub fn value(&self) -> u32 {
+                match self {
+                    $($name::$variant => $val,)*
+                    $name::Unknown(v) => *v,
+                }
+            }
+
+            /// Returns `true` if the value was not recognised
+            pub fn is_unknown(&self) -> bool {
+                matches!(self, $name::Unknown(_))
+            }
+        }
+
+        impl From<u32> for $name {
+            fn from(v: u32) -> Self {
+                match v {
+                    $($val => $name::$variant,)*
+                    _ => {
+                        $crate::diagnostics::report(stringify!($name), &v.to_string());
+                        $name::Unknown(v)
+                    }
+                }
+            }
+        }
+
+        impl serde::Serialize for $name {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: serde::Serializer,
+            {
+                serializer.serialize_u32(self.value())
+            }
+        }
+
+        impl<'de> serde::Deserialize<'de> for $name {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+            {
+                u32::deserialize(deserializer).map($name::from)
+            }
+        }
+    };
+}
+
+/// Implements `Page` for a response that holds its records in a `Vec` field,
+/// `value` unless stated otherwise
+#[cfg(any(
+    feature = "bus",
+    feature = "traffic",
+    feature = "taxi",
+    feature = "crowd"
+))]
+macro_rules! impl_page {
+    ($resp:ty, $record:ty) => {
+        impl_page!($resp, $record, value, "value");
+    };
+    ($resp:ty, $record:ty, $field:ident, $key:literal) => {
+        impl lta_utils_commons::parse::Page for $resp {
+            type Record = $record;
+
+            const RECORDS_KEY: &'static str = $key;
+
+            fn set_records(&mut self, records: Vec<$record>) {
+                self.$field = records;
+            }
+        }
+    };
+}
+
+/// Implements `geo::HasLocation` for records with `lat` and `long` fields
+#[cfg(any(feature = "bus", feature = "traffic", feature = "taxi"))]
+macro_rules! impl_has_location {
+    ($($record:ty),* $(,)?) => {
+        $(impl lta_utils_commons::geo::HasLocation for $record {
+            fn coordinates(&self) -> Option<lta_utils_commons::Coordinates> {
+                Some(lta_utils_commons::Coordinates::new(self.lat, self.long))
+            }
+        })*
+    };
+}
@@ -0,0 +1,289 @@
+//! Nearest neighbour, radius and bounding box queries on located records
+//!
+//! `SpatialIndex` is a packed R-tree built once from any collection of
+//! `HasLocation` records, e.g. bus stops, taxi stands or carparks. Records
+//! without a location are left out. Distances are great-circle distances in metres.
+//!
+//! ## Example
+//! ```rust
+//! use lta_models::bus::bus_stops::BusStopsResp;
+//! use lta_models::spatial::SpatialIndex;
+//! use lta_utils_commons::Coordinates;
+//!
+//! let stops: BusStopsResp =
+//!     serde_json::from_str(include_str!("../../dumped_data/bus_stops.json")).unwrap();
+//! let index: SpatialIndex<_> = stops.value.iter().collect();
+//!
+//! let here = Coordinates::new(1.2966, 103.8526);
+//! let nearest = index.nearest(&here, 3);
+//! assert_eq!(nearest.len(), 3);
+//! assert!(nearest[0].1 <= nearest[1].1);
+//! assert!(index.within_radius(&here, 300.0).iter().all(|(_, d)| *d <= 300.0));
+//! ```
+
+use lta_utils_commons::geo::{BoundingBox, HasLocation};
+use lta_utils_commons::Coordinates;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::iter::FromIterator;
+use std::ops::Range;
+
+/// Children per node
+const NODE_SIZE: usize = 16;
+
+/// Distances to boxes are computed to the closest point in latitude and longitude,
+/// which can be slightly further than the closest point on the sphere
+const BOUND_SLACK: f64 = 0.999;
+
+#[derive(Debug, Clone)]
+struct Node {
+    bbox: BoundingBox,
+
+    /// Indices into `items` for leaves, into `nodes` otherwise
+    children: Range<usize>,
+    leaf: bool,
+}
+
+/// Packed R-tree over records with a location
+#[derive(Debug, Clone)]
+pub struct SpatialIndex<T> {
+    items: Vec<(Coordinates, T)>,
+    nodes: Vec<Node>,
+}
+
+impl<T: HasLocation> SpatialIndex<T> {
+    pub fn new<I: IntoIterator<Item = T>>(items: I) -> Self {
+        let mut items: Vec<_> = items
+            .into_iter()
+            .filter_map(|t| t.coordinates().map(|c| (c, t)))
+            .collect();
+
+        if items.is_empty() {
+            return SpatialIndex {
+                items,
+                nodes: Vec::new(),
+            };
+        }
+
+        sort_tiles(&mut items, |(c, _)| *c);
+        let mut nodes: Vec<Node> = (0..items.len())
+            .step_by(NODE_SIZE)
+            .map(|start| {
+                let children = start..(start + NODE_SIZE).min(items.len());
+                Node {
+                    bbox: BoundingBox::from_points(items[children.clone()].iter().map(|(c, _)| c))
+                        .expect("leaves are not empty"),
+                    children,
+                    leaf: true,
+                }
+            })
+            .collect();
+
+        // parents are appended level by level until there is one root at the end
+        let mut level = 0..nodes.len();
+        while level.len() > 1 {
+            sort_tiles(&mut nodes[level.clone()], |n| n.bbox.center());
+
+            let parents: Vec<_> = level
+                .clone()
+                .step_by(NODE_SIZE)
+                .map(|start| {
+                    let children = start..(start + NODE_SIZE).min(level.end);
+                    let mut bbox = nodes[children.start].bbox;
+                    for n in &nodes[children.clone()] {
+                        bbox.extend(&n.bbox.min);
+                        bbox.extend(&n.bbox.max);
+                    }
+                    Node {
+                        bbox,
+                        children,
+                        leaf: false,
+                    }
+                })
+                .collect();
+
+            level = nodes.len()..nodes.len() + parents.len();
+            nodes.extend(parents);
+        }
+
+        SpatialIndex { items, nodes }
+    }
+}
+
+impl<T> SpatialIndex<T> {
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Indexed records in no particular order
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.items.iter().map(|(_, t)| t)
+    }
+
+    /// The `k` records closest to `point` and their distance, closest first
+    pub fn nearest(&self, point: &Coordinates, k: usize) -> Vec<(&T, f64)> {
+        let mut found = Vec::with_capacity(k.min(self.len()));
+        if self.nodes.is_empty() || k == 0 {
+            return found;
+        }
+
+        let mut queue = BinaryHeap::new();
+        queue.push(Entry {
+            dist: 0.0,
+            node: true,
+            index: self.nodes.len() - 1,
+        });
+
+        while let Some(entry) = queue.pop() {
+            if !entry.node {
+                found.push((&self.items[entry.index].1, entry.dist));
+                if found.len() == k {
+                    break;
+                }
+                continue;
+            }
+
+            let node = &self.nodes[entry.index];
+            for i in node.children.clone() {
+                let (dist, node) = if node.leaf {
+                    (point.distance_m(&self.items[i].0), false)
+                } else {
+                    (bound_m(point, &self.nodes[i].bbox), true)
+                };
+                queue.push(Entry {
+                    dist,
+                    node,
+                    index: i,
+                });
+            }
+        }
+
+        found
+    }
+
+    /// Records within `radius_m` metres of `point` and their distance, closest first
+    pub fn within_radius(&self, point: &Coordinates, radius_m: f64) -> Vec<(&T, f64)> {
+        let mut found = Vec::new();
+        self.visit(
+            |bbox| bound_m(point, bbox) <= radius_m,
+            |c, t| {
+                let dist = point.distance_m(c);
+                if dist <= radius_m {
+                    found.push((t, dist));
+                }
+            },
+        );
+
+        found.sort_by(|a, b| a.1.total_cmp(&b.1));
+        found
+    }
+
+    /// Records inside `bbox`, in no particular order
+    pub fn within_bbox(&self, bbox: &BoundingBox) -> Vec<&T> {
+        let mut found = Vec::new();
+        self.visit(
+            |b| b.intersects(bbox),
+            |c, t| {
+                if bbox.contains(c) {
+                    found.push(t);
+                }
+            },
+        );
+
+        found
+    }
+
+    /// Calls `f` on every record in a leaf whose path only has boxes accepted by `enter`
+    fn visit<'a, E, F>(&'a self, enter: E, mut f: F)
+    where
+        E: Fn(&BoundingBox) -> bool,
+        F: FnMut(&Coordinates, &'a T),
+    {
+        if self.nodes.is_empty() {
+            return;
+        }
+
+        let mut stack = vec![self.nodes.len() - 1];
+        while let Some(i) = stack.pop() {
+            let node = &self.nodes[i];
+            if !enter(&node.bbox) {
+                continue;
+            }
+
+            if node.leaf {
+                for (c, t) in &self.items[node.children.clone()] {
+                    f(c, t);
+                }
+            } else {
+                stack.extend(node.children.clone());
+            }
+        }
+    }
+}
+
+impl<T: HasLocation> FromIterator<T> for SpatialIndex<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        SpatialIndex::new(iter)
+    }
+}
+
+/// Lower bound of the distance in metres from `point` to anything in `bbox`
+fn bound_m(point: &Coordinates, bbox: &BoundingBox) -> f64 {
+    let closest = Coordinates::new(
+        point.lat.clamp(bbox.min.lat, bbox.max.lat),
+        point.long.clamp(bbox.min.long, bbox.max.long),
+    );
+    point.distance_m(&closest) * BOUND_SLACK
+}
+
+/// Sort-Tile-Recursive order: vertical slices by longitude, each sorted by latitude,
+/// so that consecutive runs of `NODE_SIZE` are close together
+fn sort_tiles<E, F>(entries: &mut [E], coords: F)
+where
+    F: Fn(&E) -> Coordinates,
+{
+    let leaves = entries.len().div_ceil(NODE_SIZE);
+    let slices = (leaves as f64).sqrt().ceil() as usize;
+    let slice_len = slices * NODE_SIZE;
+
+    entries.sort_by(|a, b| coords(a).long.total_cmp(&coords(b).long));
+    for slice in entries.chunks_mut(slice_len) {
+        slice.sort_by(|a, b| coords(a).lat.total_cmp(&coords(b).lat));
+    }
+}
+
+/// Node or record in the nearest neighbour queue, closest first
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    dist: f64,
+    node: bool,
+    index: usize,
+}
+
+impl PartialEq for Entry {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Entry {}
+
+impl PartialOrd for Entry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Entry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // records before nodes at the same distance so ties are resolved early
+        other
+            .dist
+            .total_cmp(&self.dist)
+            .then_with(|| other.node.cmp(&self.node))
+    }
+}
@@ -0,0 +1,1216 @@
+//! Local SQLite snapshot of the static and realtime datasets
+//!
+//! `Store` keeps bus stops, services, routes, taxi stands, ERP rates and bike
+//! parking as one row per record, keyed by their natural keys so that loading a
+//! newer download updates the rows in place. Carpark availability, speed bands and
+//! traffic incidents are kept as time series, one row per record and fetch.
+//! Every load is recorded with its fetch time, see `Store::last_fetch`.
+//!
+//! Details shared by every observation, e.g. the location of a carpark or the road
+//! of a speed band link, are kept once in their own table.
+//!
+//! ERP rates are stored per vehicle type and grouped back by zone, day, times,
+//! charge and effective date when read.
+//!
+//! `Store::sync_bus_stops`, `sync_bus_services`, `sync_bus_routes`, `sync_taxi_stands`
+//! and `sync_erp_rates` compare a full download with the stored snapshot, write only
+//! the changes in one transaction and return a `sync::ChangeReport`.
+//!
+//! ## Example
+//! ```rust
+//! use lta_models::bus::bus_routes::BusRouteResp;
+//! use lta_models::storage::{Dataset, Store};
+//! use lta_utils_commons::sg_time;
+//!
+//! let routes: BusRouteResp =
+//!     serde_json::from_str(include_str!("../../dumped_data/bus_route.json")).unwrap();
+//!
+//! let mut store = Store::open_in_memory().unwrap();
+//! let fetched_at = sg_time::now();
+//! store.upsert_bus_routes(&routes.value, fetched_at).unwrap();
+//!
+//! let network = store.bus_network().unwrap();
+//! assert_eq!(network.route("10", 1).unwrap()[0].stop_seq, 1);
+//! assert!(store.last_fetch(Dataset::BusRoutes).unwrap().is_some());
+//! ```
+
+use crate::bus::bus_routes::BusRoute;
+use crate::bus::bus_services::{BusFreq, BusService};
+use crate::bus::bus_stops::BusStop;
+use crate::bus::network::BusNetwork;
+use crate::sync::{self, ChangeReport, ErpRateKey, SyncRecord};
+use crate::taxi::taxi_stands::TaxiStand;
+use crate::traffic::bike_parking::BikeParking;
+use crate::traffic::carpark_avail::CarPark;
+use crate::traffic::erp_rates::{ErpRate, VehicleType, ZoneId};
+use crate::traffic::traffic_incidents::TrafficIncident;
+use crate::traffic::traffic_speed_bands::TrafficSpeedBand;
+use lta_utils_commons::chrono::{DateTime, NaiveDate, NaiveTime, Timelike};
+use lta_utils_commons::sg_time::{self, SgDateTime};
+use lta_utils_commons::{Coordinates, Location};
+use rusqlite::{params, Connection, OptionalExtension, Row, Transaction};
+use std::convert::Infallible;
+use std::fmt::{self, Display, Formatter};
+use std::path::Path;
+use std::str::FromStr;
+
+/// Version of the schema in `PRAGMA user_version`
+pub const SCHEMA_VERSION: i32 = 1;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS fetches (
+    dataset TEXT NOT NULL,
+    fetched_at INTEGER NOT NULL,
+    records INTEGER NOT NULL,
+    PRIMARY KEY (dataset, fetched_at)
+);
+
+CREATE TABLE IF NOT EXISTS bus_stops (
+    bus_stop_code INTEGER PRIMARY KEY,
+    road_name TEXT NOT NULL,
+    description TEXT NOT NULL,
+    lat REAL NOT NULL,
+    long REAL NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS bus_services (
+    service_no TEXT NOT NULL,
+    direction INTEGER NOT NULL,
+    operator TEXT NOT NULL,
+    category TEXT NOT NULL,
+    origin_code INTEGER NOT NULL,
+    dest_code INTEGER NOT NULL,
+    am_peak_min INTEGER,
+    am_peak_max INTEGER,
+    am_offpeak_min INTEGER,
+    am_offpeak_max INTEGER,
+    pm_peak_min INTEGER,
+    pm_peak_max INTEGER,
+    pm_offpeak_min INTEGER,
+    pm_offpeak_max INTEGER,
+    loop_desc TEXT,
+    PRIMARY KEY (service_no, direction)
+);
+
+CREATE TABLE IF NOT EXISTS bus_routes (
+    service_no TEXT NOT NULL,
+    direction INTEGER NOT NULL,
+    stop_seq INTEGER NOT NULL,
+    operator TEXT NOT NULL,
+    bus_stop_code INTEGER NOT NULL,
+    dist REAL NOT NULL,
+    wd_first TEXT,
+    wd_last TEXT,
+    sat_first TEXT,
+    sat_last TEXT,
+    sun_first TEXT,
+    sun_last TEXT,
+    PRIMARY KEY (service_no, direction, stop_seq)
+);
+CREATE INDEX IF NOT EXISTS bus_routes_stop ON bus_routes (bus_stop_code);
+
+CREATE TABLE IF NOT EXISTS taxi_stands (
+    taxi_code TEXT PRIMARY KEY,
+    lat REAL NOT NULL,
+    long REAL NOT NULL,
+    is_barrier_free INTEGER NOT NULL,
+    owner TEXT NOT NULL,
+    stand_type TEXT NOT NULL,
+    name TEXT NOT NULL
+);
+
+-- times are '' when there is no charge period
+CREATE TABLE IF NOT EXISTS erp_rates (
+    zone_id TEXT NOT NULL,
+    vehicle_type TEXT NOT NULL,
+    day_type TEXT NOT NULL,
+    start_time TEXT NOT NULL,
+    end_time TEXT NOT NULL,
+    effective_date TEXT NOT NULL,
+    charge_amt REAL NOT NULL,
+    PRIMARY KEY (zone_id, vehicle_type, day_type, start_time, effective_date)
+);
+
+CREATE TABLE IF NOT EXISTS bike_parking (
+    description TEXT NOT NULL,
+    lat REAL NOT NULL,
+    long REAL NOT NULL,
+    rack_type TEXT NOT NULL,
+    rack_count INTEGER NOT NULL,
+    shelter_indicator INTEGER NOT NULL,
+    PRIMARY KEY (description, lat, long)
+);
+
+CREATE TABLE IF NOT EXISTS carparks (
+    carpark_id TEXT NOT NULL,
+    agency TEXT NOT NULL,
+    area TEXT NOT NULL,
+    development TEXT NOT NULL,
+    lat REAL,
+    long REAL,
+    PRIMARY KEY (carpark_id, agency)
+);
+
+CREATE TABLE IF NOT EXISTS carpark_avail (
+    carpark_id TEXT NOT NULL,
+    agency TEXT NOT NULL,
+    lot_type TEXT NOT NULL,
+    fetched_at INTEGER NOT NULL,
+    avail_lots INTEGER NOT NULL,
+    PRIMARY KEY (carpark_id, agency, lot_type, fetched_at),
+    FOREIGN KEY (carpark_id, agency) REFERENCES carparks (carpark_id, agency)
+);
+CREATE INDEX IF NOT EXISTS carpark_avail_fetched ON carpark_avail (fetched_at);
+
+CREATE TABLE IF NOT EXISTS speed_band_links (
+    link_id INTEGER PRIMARY KEY,
+    road_name TEXT NOT NULL,
+    road_category TEXT NOT NULL,
+    start_lat REAL,
+    start_long REAL,
+    end_lat REAL,
+    end_long REAL
+);
+
+CREATE TABLE IF NOT EXISTS speed_bands (
+    link_id INTEGER NOT NULL REFERENCES speed_band_links (link_id),
+    fetched_at INTEGER NOT NULL,
+    speed_band INTEGER NOT NULL,
+    min_speed INTEGER NOT NULL,
+    max_speed INTEGER NOT NULL,
+    PRIMARY KEY (link_id, fetched_at)
+);
+CREATE INDEX IF NOT EXISTS speed_bands_fetched ON speed_bands (fetched_at);
+
+CREATE TABLE IF NOT EXISTS traffic_incidents (
+    fetched_at INTEGER NOT NULL,
+    incident_type TEXT NOT NULL,
+    lat REAL NOT NULL,
+    long REAL NOT NULL,
+    msg TEXT NOT NULL,
+    PRIMARY KEY (fetched_at, incident_type, lat, long, msg)
+);
+";
+
+/// Error opening or using a `Store`
+#[derive(Debug)]
+pub enum StorageError {
+    Sqlite(rusqlite::Error),
+
+    /// The database was created by a newer version of lta-rs
+    UnsupportedVersion(i32),
+
+    /// Table, column and the stored value that could not be read
+    Value {
+        table: &'static str,
+        column: &'static str,
+        value: String,
+    },
+}
+
+impl Display for StorageError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            StorageError::Sqlite(e) => write!(f, "sqlite error: {}", e),
+            StorageError::UnsupportedVersion(v) => write!(
+                f,
+                "schema version {} is newer than the supported {}",
+                v, SCHEMA_VERSION
+            ),
+            StorageError::Value {
+                table,
+                column,
+                value,
+            } => write!(f, "invalid value `{}` in {}.{}", value, table, column),
+        }
+    }
+}
+
+impl std::error::Error for StorageError {}
+
+impl From<rusqlite::Error> for StorageError {
+    fn from(e: rusqlite::Error) -> Self {
+        StorageError::Sqlite(e)
+    }
+}
+
+/// Datasets with their fetches recorded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Dataset {
+    BusStops,
+    BusServices,
+    BusRoutes,
+    TaxiStands,
+    ErpRates,
+    BikeParking,
+    CarparkAvail,
+    TrafficSpeedBands,
+    TrafficIncidents,
+}
+
+impl Dataset {
+    pub const ALL: [Dataset; 9] = [
+        Dataset::BusStops,
+        Dataset::BusServices,
+        Dataset::BusRoutes,
+        Dataset::TaxiStands,
+        Dataset::ErpRates,
+        Dataset::BikeParking,
+        Dataset::CarparkAvail,
+        Dataset::TrafficSpeedBands,
+        Dataset::TrafficIncidents,
+    ];
+
+    /// Name stored in the `fetches` table
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Dataset::BusStops => "BusStops",
+            Dataset::BusServices => "BusServices",
+            Dataset::BusRoutes => "BusRoutes",
+            Dataset::TaxiStands => "TaxiStands",
+            Dataset::ErpRates => "ERPRates",
+            Dataset::BikeParking => "BicycleParking",
+            Dataset::CarparkAvail => "CarParkAvailability",
+            Dataset::TrafficSpeedBands => "TrafficSpeedBands",
+            Dataset::TrafficIncidents => "TrafficIncidents",
+        }
+    }
+}
+
+impl Display for Dataset {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A realtime record and when it was fetched
+#[derive(Debug, Clone, PartialEq)]
+pub struct Observation<T> {
+    pub fetched_at: SgDateTime,
+    pub record: T,
+}
+
+/// SQLite database of DataMall datasets
+#[derive(Debug)]
+pub struct Store {
+    conn: Connection,
+}
+
+impl Store {
+    /// Opens or creates a database file with the schema
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, StorageError> {
+        Store::from_connection(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> Result<Self, StorageError> {
+        Store::from_connection(Connection::open_in_memory()?)
+    }
+
+    /// Creates the schema if the database is empty
+    pub fn from_connection(conn: Connection) -> Result<Self, StorageError> {
+        let version: i32 = conn.query_row("PRAGMA user_version", [], |r| r.get(0))?;
+        if version > SCHEMA_VERSION {
+            return Err(StorageError::UnsupportedVersion(version));
+        }
+
+        conn.execute_batch(SCHEMA)?;
+        conn.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+        Ok(Store { conn })
+    }
+
+    /// The underlying connection, for queries not covered here
+    pub fn connection(&self) -> &Connection {
+        &self.conn
+    }
+
+    /// Time of the latest load of `dataset`
+    pub fn last_fetch(&self, dataset: Dataset) -> Result<Option<SgDateTime>, StorageError> {
+        let millis: Option<i64> = self.conn.query_row(
+            "SELECT MAX(fetched_at) FROM fetches WHERE dataset = ?1",
+            [dataset.as_str()],
+            |r| r.get(0),
+        )?;
+        millis.map(from_millis).transpose()
+    }
+
+    pub fn upsert_bus_stops(
+        &mut self,
+        stops: &[BusStop],
+        fetched_at: SgDateTime,
+    ) -> Result<(), StorageError> {
+        self.upsert(stops, fetched_at)
+    }
+
+    pub fn upsert_bus_services(
+        &mut self,
+        services: &[BusService],
+        fetched_at: SgDateTime,
+    ) -> Result<(), StorageError> {
+        self.upsert(services, fetched_at)
+    }
+
+    pub fn upsert_bus_routes(
+        &mut self,
+        routes: &[BusRoute],
+        fetched_at: SgDateTime,
+    ) -> Result<(), StorageError> {
+        self.upsert(routes, fetched_at)
+    }
+
+    pub fn upsert_taxi_stands(
+        &mut self,
+        stands: &[TaxiStand],
+        fetched_at: SgDateTime,
+    ) -> Result<(), StorageError> {
+        self.upsert(stands, fetched_at)
+    }
+
+    /// Upserts a row for every vehicle type of every rate
+    pub fn upsert_erp_rates(
+        &mut self,
+        rates: &[ErpRate],
+        fetched_at: SgDateTime,
+    ) -> Result<(), StorageError> {
+        self.upsert(rates, fetched_at)
+    }
+
+    /// Replaces the stored bus stops with a full download and reports the changes
+    pub fn sync_bus_stops(
+        &mut self,
+        stops: Vec<BusStop>,
+        fetched_at: SgDateTime,
+    ) -> Result<ChangeReport<BusStop>, StorageError> {
+        self.sync(stops, fetched_at)
+    }
+
+    pub fn sync_bus_services(
+        &mut self,
+        services: Vec<BusService>,
+        fetched_at: SgDateTime,
+    ) -> Result<ChangeReport<BusService>, StorageError> {
+        self.sync(services, fetched_at)
+    }
+
+    pub fn sync_bus_routes(
+        &mut self,
+        routes: Vec<BusRoute>,
+        fetched_at: SgDateTime,
+    ) -> Result<ChangeReport<BusRoute>, StorageError> {
+        self.sync(routes, fetched_at)
+    }
+
+    pub fn sync_taxi_stands(
+        &mut self,
+        stands: Vec<TaxiStand>,
+        fetched_at: SgDateTime,
+    ) -> Result<ChangeReport<TaxiStand>, StorageError> {
+        self.sync(stands, fetched_at)
+    }
+
+    /// Changes are reported per vehicle type, see `sync::SyncRecord::split`
+    pub fn sync_erp_rates(
+        &mut self,
+        rates: Vec<ErpRate>,
+        fetched_at: SgDateTime,
+    ) -> Result<ChangeReport<ErpRate>, StorageError> {
+        self.sync(rates, fetched_at)
+    }
+
+    pub fn upsert_bike_parking(
+        &mut self,
+        parking: &[BikeParking],
+        fetched_at: SgDateTime,
+    ) -> Result<(), StorageError> {
+        let tx = self.conn.transaction()?;
+        {
+            let mut stmt =
+                tx.prepare("INSERT OR REPLACE INTO bike_parking VALUES (?1, ?2, ?3, ?4, ?5, ?6)")?;
+            for p in parking {
+                stmt.execute(params![
+                    p.desc,
+                    p.lat,
+                    p.long,
+                    p.rack_type.as_str(),
+                    p.rack_count,
+                    p.shelter_indicator,
+                ])?;
+            }
+        }
+        record_fetch(&tx, Dataset::BikeParking, fetched_at, parking.len())?;
+        Ok(tx.commit()?)
+    }
+
+    /// Adds an observation of every carpark at `fetched_at` and updates their details
+    pub fn insert_carpark_avail(
+        &mut self,
+        carparks: &[CarPark],
+        fetched_at: SgDateTime,
+    ) -> Result<(), StorageError> {
+        let millis = fetched_at.timestamp_millis();
+        let tx = self.conn.transaction()?;
+        {
+            let mut details =
+                tx.prepare("INSERT OR REPLACE INTO carparks VALUES (?1, ?2, ?3, ?4, ?5, ?6)")?;
+            let mut avail =
+                tx.prepare("INSERT OR REPLACE INTO carpark_avail VALUES (?1, ?2, ?3, ?4, ?5)")?;
+            for c in carparks {
+                details.execute(params![
+                    c.carpark_id,
+                    c.agency.as_str(),
+                    c.area,
+                    c.dev,
+                    c.coords.as_ref().map(|c| c.lat),
+                    c.coords.as_ref().map(|c| c.long),
+                ])?;
+                avail.execute(params![
+                    c.carpark_id,
+                    c.agency.as_str(),
+                    c.lot_type.as_str(),
+                    millis,
+                    c.avail_lots,
+                ])?;
+            }
+        }
+        record_fetch(&tx, Dataset::CarparkAvail, fetched_at, carparks.len())?;
+        Ok(tx.commit()?)
+    }
+
+    /// Adds an observation of every link at `fetched_at` and updates their details
+    pub fn insert_speed_bands(
+        &mut self,
+        bands: &[TrafficSpeedBand],
+        fetched_at: SgDateTime,
+    ) -> Result<(), StorageError> {
+        let millis = fetched_at.timestamp_millis();
+        let tx = self.conn.transaction()?;
+        {
+            let mut links = tx.prepare(
+                "INSERT OR REPLACE INTO speed_band_links VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            )?;
+            let mut observations =
+                tx.prepare("INSERT OR REPLACE INTO speed_bands VALUES (?1, ?2, ?3, ?4, ?5)")?;
+            for b in bands {
+                let loc = b.coord_start_end.as_ref();
+                links.execute(params![
+                    b.link_id,
+                    b.road_name,
+                    b.road_category.as_str(),
+                    loc.map(|l| l.start.lat),
+                    loc.map(|l| l.start.long),
+                    loc.map(|l| l.end.lat),
+                    loc.map(|l| l.end.long),
+                ])?;
+                observations.execute(params![
+                    b.link_id,
+                    millis,
+                    b.speed_band,
+                    b.min_speed,
+                    b.max_speed,
+                ])?;
+            }
+        }
+        record_fetch(&tx, Dataset::TrafficSpeedBands, fetched_at, bands.len())?;
+        Ok(tx.commit()?)
+    }
+
+    /// Adds the incidents reported at `fetched_at`
+    pub fn insert_incidents(
+        &mut self,
+        incidents: &[TrafficIncident],
+        fetched_at: SgDateTime,
+    ) -> Result<(), StorageError> {
+        let millis = fetched_at.timestamp_millis();
+        let tx = self.conn.transaction()?;
+        {
+            let mut stmt =
+                tx.prepare("INSERT OR IGNORE INTO traffic_incidents VALUES (?1, ?2, ?3, ?4, ?5)")?;
+            for i in incidents {
+                stmt.execute(params![
+                    millis,
+                    i.incident_type.as_str(),
+                    i.lat,
+                    i.long,
+                    i.msg
+                ])?;
+            }
+        }
+        record_fetch(&tx, Dataset::TrafficIncidents, fetched_at, incidents.len())?;
+        Ok(tx.commit()?)
+    }
+
+    /// Bus stops by code
+    pub fn bus_stops(&self) -> Result<Vec<BusStop>, StorageError> {
+        BusStop::load(&self.conn)
+    }
+
+    pub fn bus_stop(&self, bus_stop_code: u32) -> Result<Option<BusStop>, StorageError> {
+        let stop = self
+            .conn
+            .query_row(
+                "SELECT * FROM bus_stops WHERE bus_stop_code = ?1",
+                [bus_stop_code],
+                |r| Ok(bus_stop(r)),
+            )
+            .optional()?;
+        stop.transpose()
+    }
+
+    /// Bus services by service number and direction
+    pub fn bus_services(&self) -> Result<Vec<BusService>, StorageError> {
+        BusService::load(&self.conn)
+    }
+
+    /// Bus routes by service, direction and stop sequence
+    pub fn bus_routes(&self) -> Result<Vec<BusRoute>, StorageError> {
+        BusRoute::load(&self.conn)
+    }
+
+    /// Stops of a service in one direction, in order
+    pub fn route(&self, service_no: &str, direction: u32) -> Result<Vec<BusRoute>, StorageError> {
+        query(
+            &self.conn,
+            "SELECT * FROM bus_routes WHERE service_no = ?1 AND direction = ?2 ORDER BY stop_seq",
+            params![service_no, direction],
+            bus_route,
+        )
+    }
+
+    /// Route stops of every service calling at a stop
+    pub fn routes_at(&self, bus_stop_code: u32) -> Result<Vec<BusRoute>, StorageError> {
+        query(
+            &self.conn,
+            "SELECT * FROM bus_routes WHERE bus_stop_code = ?1
+             ORDER BY service_no, direction, stop_seq",
+            [bus_stop_code],
+            bus_route,
+        )
+    }
+
+    /// `BusNetwork` of the stored stops, services and routes
+    pub fn bus_network(&self) -> Result<BusNetwork, StorageError> {
+        Ok(BusNetwork::new(
+            self.bus_stops()?,
+            self.bus_services()?,
+            self.bus_routes()?,
+        ))
+    }
+
+    pub fn taxi_stands(&self) -> Result<Vec<TaxiStand>, StorageError> {
+        TaxiStand::load(&self.conn)
+    }
+
+    /// ERP rates with the vehicle types sharing a charge grouped together
+    pub fn erp_rates(&self) -> Result<Vec<ErpRate>, StorageError> {
+        ErpRate::load(&self.conn)
+    }
+
+    pub fn erp_rates_in_zone(&self, zone_id: &ZoneId) -> Result<Vec<ErpRate>, StorageError> {
+        erp_rates_where(&self.conn, "zone_id = ?1", [zone_id.as_str()])
+    }
+
+    pub fn bike_parking(&self) -> Result<Vec<BikeParking>, StorageError> {
+        query(
+            &self.conn,
+            "SELECT * FROM bike_parking ORDER BY description, lat, long",
+            [],
+            bike_parking,
+        )
+    }
+
+    /// Latest observation of every carpark and lot type
+    pub fn latest_carpark_avail(&self) -> Result<Vec<Observation<CarPark>>, StorageError> {
+        self.carpark_avail_where(
+            "a.fetched_at = (SELECT MAX(fetched_at) FROM carpark_avail b
+             WHERE b.carpark_id = a.carpark_id AND b.agency = a.agency
+             AND b.lot_type = a.lot_type)",
+            params![],
+        )
+    }
+
+    /// Observations of a carpark between `from` and `to` inclusive, oldest first
+    pub fn carpark_history(
+        &self,
+        carpark_id: &str,
+        from: SgDateTime,
+        to: SgDateTime,
+    ) -> Result<Vec<Observation<CarPark>>, StorageError> {
+        self.carpark_avail_where(
+            "a.carpark_id = ?1 AND a.fetched_at BETWEEN ?2 AND ?3",
+            params![carpark_id, from.timestamp_millis(), to.timestamp_millis()],
+        )
+    }
+
+    /// Latest observation of every link
+    pub fn latest_speed_bands(&self) -> Result<Vec<Observation<TrafficSpeedBand>>, StorageError> {
+        self.speed_bands_where(
+            "s.fetched_at = (SELECT MAX(fetched_at) FROM speed_bands b
+             WHERE b.link_id = s.link_id)",
+            params![],
+        )
+    }
+
+    /// Observations of a link between `from` and `to` inclusive, oldest first
+    pub fn speed_band_history(
+        &self,
+        link_id: u64,
+        from: SgDateTime,
+        to: SgDateTime,
+    ) -> Result<Vec<Observation<TrafficSpeedBand>>, StorageError> {
+        self.speed_bands_where(
+            "s.link_id = ?1 AND s.fetched_at BETWEEN ?2 AND ?3",
+            params![link_id, from.timestamp_millis(), to.timestamp_millis()],
+        )
+    }
+
+    /// Incidents reported between `from` and `to` inclusive, oldest first
+    pub fn incidents(
+        &self,
+        from: SgDateTime,
+        to: SgDateTime,
+    ) -> Result<Vec<Observation<TrafficIncident>>, StorageError> {
+        query(
+            &self.conn,
+            "SELECT * FROM traffic_incidents WHERE fetched_at BETWEEN ?1 AND ?2
+             ORDER BY fetched_at, rowid",
+            params![from.timestamp_millis(), to.timestamp_millis()],
+            |r| {
+                Ok(Observation {
+                    fetched_at: from_millis(r.get(0)?)?,
+                    record: TrafficIncident {
+                        incident_type: parse(r.get(1)?),
+                        lat: r.get(2)?,
+                        long: r.get(3)?,
+                        msg: r.get(4)?,
+                    },
+                })
+            },
+        )
+    }
+
+    fn upsert<T: Snapshot>(
+        &mut self,
+        records: &[T],
+        fetched_at: SgDateTime,
+    ) -> Result<(), StorageError> {
+        let tx = self.conn.transaction()?;
+        T::write(&tx, records)?;
+        record_fetch(&tx, T::DATASET, fetched_at, records.len())?;
+        Ok(tx.commit()?)
+    }
+
+    /// Diffs against the snapshot and writes only the changes, all in one transaction
+    fn sync<T: Snapshot>(
+        &mut self,
+        records: Vec<T>,
+        fetched_at: SgDateTime,
+    ) -> Result<ChangeReport<T>, StorageError> {
+        let count = records.len();
+        let tx = self.conn.transaction()?;
+        let report = sync::diff(T::load(&tx)?, records);
+
+        for removed in &report.removed {
+            T::delete(&tx, &removed.key())?;
+        }
+        T::write(&tx, &report.added)?;
+        let modified: Vec<T> = report.modified.iter().map(|m| m.new.clone()).collect();
+        T::write(&tx, &modified)?;
+
+        record_fetch(&tx, T::DATASET, fetched_at, count)?;
+        tx.commit()?;
+        Ok(report)
+    }
+
+    fn carpark_avail_where<P: rusqlite::Params>(
+        &self,
+        filter: &str,
+        params: P,
+    ) -> Result<Vec<Observation<CarPark>>, StorageError> {
+        let sql = format!(
+            "SELECT a.fetched_at, a.carpark_id, c.area, c.development, c.lat, c.long,
+             a.avail_lots, a.lot_type, a.agency
+             FROM carpark_avail a JOIN carparks c USING (carpark_id, agency)
+             WHERE {} ORDER BY a.fetched_at, a.carpark_id, a.agency, a.lot_type",
+            filter
+        );
+        query(&self.conn, &sql, params, |r| {
+            let lat: Option<f64> = r.get(4)?;
+            let long: Option<f64> = r.get(5)?;
+            Ok(Observation {
+                fetched_at: from_millis(r.get(0)?)?,
+                record: CarPark {
+                    carpark_id: r.get(1)?,
+                    area: r.get(2)?,
+                    dev: r.get(3)?,
+                    coords: lat.zip(long).map(|(lat, long)| Coordinates::new(lat, long)),
+                    avail_lots: r.get(6)?,
+                    lot_type: parse(r.get(7)?),
+                    agency: parse(r.get(8)?),
+                },
+            })
+        })
+    }
+
+    fn speed_bands_where<P: rusqlite::Params>(
+        &self,
+        filter: &str,
+        params: P,
+    ) -> Result<Vec<Observation<TrafficSpeedBand>>, StorageError> {
+        let sql = format!(
+            "SELECT s.fetched_at, s.link_id, l.road_name, l.road_category, s.speed_band,
+             s.min_speed, s.max_speed, l.start_lat, l.start_long, l.end_lat, l.end_long
+             FROM speed_bands s JOIN speed_band_links l USING (link_id)
+             WHERE {} ORDER BY s.fetched_at, s.link_id",
+            filter
+        );
+        query(&self.conn, &sql, params, |r| {
+            let start_lat: Option<f64> = r.get(7)?;
+            let coord_start_end = match start_lat {
+                Some(start_lat) => Some(Location::new(start_lat, r.get(8)?, r.get(9)?, r.get(10)?)),
+                None => None,
+            };
+            Ok(Observation {
+                fetched_at: from_millis(r.get(0)?)?,
+                record: TrafficSpeedBand {
+                    link_id: r.get(1)?,
+                    road_name: r.get(2)?,
+                    road_category: parse(r.get(3)?),
+                    speed_band: r.get(4)?,
+                    min_speed: r.get(5)?,
+                    max_speed: r.get(6)?,
+                    coord_start_end,
+                },
+            })
+        })
+    }
+}
+
+fn record_fetch(
+    tx: &Transaction<'_>,
+    dataset: Dataset,
+    fetched_at: SgDateTime,
+    records: usize,
+) -> Result<(), StorageError> {
+    tx.execute(
+        "INSERT OR REPLACE INTO fetches VALUES (?1, ?2, ?3)",
+        params![
+            dataset.as_str(),
+            fetched_at.timestamp_millis(),
+            records as i64
+        ],
+    )?;
+    Ok(())
+}
+
+/// Static dataset kept as a snapshot keyed by `SyncRecord::key`
+trait Snapshot: SyncRecord {
+    const DATASET: Dataset;
+
+    fn load(conn: &Connection) -> Result<Vec<Self>, StorageError>;
+
+    /// Inserts or replaces the rows of `records`
+    fn write(tx: &Transaction<'_>, records: &[Self]) -> Result<(), StorageError>;
+
+    fn delete(tx: &Transaction<'_>, key: &Self::Key) -> Result<(), StorageError>;
+}
+
+impl Snapshot for BusStop {
+    const DATASET: Dataset = Dataset::BusStops;
+
+    fn load(conn: &Connection) -> Result<Vec<Self>, StorageError> {
+        query(
+            conn,
+            "SELECT * FROM bus_stops ORDER BY bus_stop_code",
+            [],
+            bus_stop,
+        )
+    }
+
+    fn write(tx: &Transaction<'_>, stops: &[Self]) -> Result<(), StorageError> {
+        let mut stmt = tx.prepare_cached(
+            "INSERT INTO bus_stops VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT (bus_stop_code) DO UPDATE SET road_name = excluded.road_name,
+             description = excluded.description, lat = excluded.lat, long = excluded.long",
+        )?;
+        for s in stops {
+            stmt.execute(params![s.bus_stop_code, s.road_name, s.desc, s.lat, s.long])?;
+        }
+        Ok(())
+    }
+
+    fn delete(tx: &Transaction<'_>, bus_stop_code: &u32) -> Result<(), StorageError> {
+        tx.prepare_cached("DELETE FROM bus_stops WHERE bus_stop_code = ?1")?
+            .execute([bus_stop_code])?;
+        Ok(())
+    }
+}
+
+impl Snapshot for BusService {
+    const DATASET: Dataset = Dataset::BusServices;
+
+    fn load(conn: &Connection) -> Result<Vec<Self>, StorageError> {
+        query(
+            conn,
+            "SELECT * FROM bus_services ORDER BY service_no, direction",
+            [],
+            bus_service,
+        )
+    }
+
+    fn write(tx: &Transaction<'_>, services: &[Self]) -> Result<(), StorageError> {
+        let mut stmt = tx.prepare_cached(
+            "INSERT OR REPLACE INTO bus_services
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
+        )?;
+        for s in services {
+            stmt.execute(params![
+                s.service_no,
+                s.no_direction,
+                s.operator.as_str(),
+                s.category.as_str(),
+                s.origin_code,
+                s.dest_code,
+                s.am_peak_freq.min,
+                s.am_peak_freq.max,
+                s.am_offpeak_freq.min,
+                s.am_offpeak_freq.max,
+                s.pm_peak_freq.min,
+                s.pm_peak_freq.max,
+                s.pm_offpeak_freq.min,
+                s.pm_offpeak_freq.max,
+                s.loop_desc,
+            ])?;
+        }
+        Ok(())
+    }
+
+    fn delete(tx: &Transaction<'_>, key: &(String, u32)) -> Result<(), StorageError> {
+        tx.prepare_cached("DELETE FROM bus_services WHERE service_no = ?1 AND direction = ?2")?
+            .execute(params![key.0, key.1])?;
+        Ok(())
+    }
+}
+
+impl Snapshot for BusRoute {
+    const DATASET: Dataset = Dataset::BusRoutes;
+
+    fn load(conn: &Connection) -> Result<Vec<Self>, StorageError> {
+        query(
+            conn,
+            "SELECT * FROM bus_routes ORDER BY service_no, direction, stop_seq",
+            [],
+            bus_route,
+        )
+    }
+
+    fn write(tx: &Transaction<'_>, routes: &[Self]) -> Result<(), StorageError> {
+        let mut stmt = tx.prepare_cached(
+            "INSERT OR REPLACE INTO bus_routes
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+        )?;
+        for r in routes {
+            stmt.execute(params![
+                r.service_no,
+                r.direction,
+                r.stop_seq,
+                r.operator.as_str(),
+                r.bus_stop_code,
+                r.dist,
+                r.wd_first.map(time_to_sql),
+                r.wd_last.map(time_to_sql),
+                r.sat_first.map(time_to_sql),
+                r.sat_last.map(time_to_sql),
+                r.sun_first.map(time_to_sql),
+                r.sun_last.map(time_to_sql),
+            ])?;
+        }
+        Ok(())
+    }
+
+    fn delete(tx: &Transaction<'_>, key: &(String, u32, u32)) -> Result<(), StorageError> {
+        tx.prepare_cached(
+            "DELETE FROM bus_routes WHERE service_no = ?1 AND direction = ?2 AND stop_seq = ?3",
+        )?
+        .execute(params![key.0, key.1, key.2])?;
+        Ok(())
+    }
+}
+
+impl Snapshot for TaxiStand {
+    const DATASET: Dataset = Dataset::TaxiStands;
+
+    fn load(conn: &Connection) -> Result<Vec<Self>, StorageError> {
+        query(
+            conn,
+            "SELECT * FROM taxi_stands ORDER BY taxi_code",
+            [],
+            taxi_stand,
+        )
+    }
+
+    fn write(tx: &Transaction<'_>, stands: &[Self]) -> Result<(), StorageError> {
+        let mut stmt = tx.prepare_cached(
+            "INSERT OR REPLACE INTO taxi_stands VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        )?;
+        for s in stands {
+            stmt.execute(params![
+                s.taxi_code,
+                s.lat,
+                s.long,
+                s.is_barrier_free,
+                s.owner.as_str(),
+                s.stand_type.as_str(),
+                s.name,
+            ])?;
+        }
+        Ok(())
+    }
+
+    fn delete(tx: &Transaction<'_>, taxi_code: &String) -> Result<(), StorageError> {
+        tx.prepare_cached("DELETE FROM taxi_stands WHERE taxi_code = ?1")?
+            .execute([taxi_code])?;
+        Ok(())
+    }
+}
+
+impl Snapshot for ErpRate {
+    const DATASET: Dataset = Dataset::ErpRates;
+
+    fn load(conn: &Connection) -> Result<Vec<Self>, StorageError> {
+        erp_rates_where(conn, "1", [])
+    }
+
+    fn write(tx: &Transaction<'_>, rates: &[Self]) -> Result<(), StorageError> {
+        let mut stmt = tx.prepare_cached(
+            "INSERT OR REPLACE INTO erp_rates VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        )?;
+        for r in rates {
+            for vehicle in &r.vehicle_type {
+                stmt.execute(params![
+                    r.zone_id.as_str(),
+                    vehicle.as_str(),
+                    r.day_type.as_str(),
+                    r.start_time.map(time_to_sql).unwrap_or_default(),
+                    r.end_time.map(time_to_sql).unwrap_or_default(),
+                    date_to_sql(r.effective_date),
+                    r.charge_amt,
+                ])?;
+            }
+        }
+        Ok(())
+    }
+
+    fn delete(tx: &Transaction<'_>, key: &ErpRateKey) -> Result<(), StorageError> {
+        let mut stmt = tx.prepare_cached(
+            "DELETE FROM erp_rates WHERE zone_id = ?1 AND vehicle_type = ?2 AND day_type = ?3
+             AND start_time = ?4 AND effective_date = ?5",
+        )?;
+        for vehicle in &key.vehicle_types {
+            stmt.execute(params![
+                key.zone_id.as_str(),
+                vehicle.as_str(),
+                key.day_type.as_str(),
+                key.start_time.map(time_to_sql).unwrap_or_default(),
+                date_to_sql(key.effective_date),
+            ])?;
+        }
+        Ok(())
+    }
+}
+
+fn query<T, P, F>(conn: &Connection, sql: &str, params: P, f: F) -> Result<Vec<T>, StorageError>
+where
+    P: rusqlite::Params,
+    F: Fn(&Row<'_>) -> Result<T, StorageError>,
+{
+    let mut stmt = conn.prepare(sql)?;
+    let mut rows = stmt.query(params)?;
+    let mut records = Vec::new();
+    while let Some(row) = rows.next()? {
+        records.push(f(row)?);
+    }
+    Ok(records)
+}
+
+fn erp_rates_where<P: rusqlite::Params>(
+    conn: &Connection,
+    filter: &str,
+    params: P,
+) -> Result<Vec<ErpRate>, StorageError> {
+    let sql = format!(
+        "SELECT * FROM erp_rates WHERE {}
+         ORDER BY zone_id, day_type, start_time, effective_date, charge_amt, rowid",
+        filter
+    );
+    let rows = query(conn, &sql, params, |r| {
+        let vehicle: VehicleType = parse(r.get(1)?);
+        let rate = ErpRate {
+            vehicle_type: vec![],
+            zone_id: parse(r.get(0)?),
+            day_type: parse(r.get(2)?),
+            start_time: time_from_sql("erp_rates", "start_time", r.get(3)?)?,
+            end_time: time_from_sql("erp_rates", "end_time", r.get(4)?)?,
+            effective_date: date_from_sql("erp_rates", r.get(5)?)?,
+            charge_amt: r.get(6)?,
+        };
+        Ok((vehicle, rate))
+    })?;
+
+    let mut rates: Vec<ErpRate> = Vec::new();
+    for (vehicle, rate) in rows {
+        match rates.last_mut() {
+            Some(last)
+                if ErpRate {
+                    vehicle_type: vec![],
+                    ..last.clone()
+                } == rate =>
+            {
+                last.vehicle_type.push(vehicle)
+            }
+            _ => rates.push(ErpRate {
+                vehicle_type: vec![vehicle],
+                ..rate
+            }),
+        }
+    }
+    Ok(rates)
+}
+
+fn bus_stop(r: &Row<'_>) -> Result<BusStop, StorageError> {
+    Ok(BusStop {
+        bus_stop_code: r.get(0)?,
+        road_name: r.get(1)?,
+        desc: r.get(2)?,
+        lat: r.get(3)?,
+        long: r.get(4)?,
+    })
+}
+
+fn bus_service(r: &Row<'_>) -> Result<BusService, StorageError> {
+    let freq = |i| -> rusqlite::Result<BusFreq> {
+        Ok(BusFreq {
+            min: r.get(i)?,
+            max: r.get(i + 1)?,
+        })
+    };
+
+    Ok(BusService {
+        service_no: r.get(0)?,
+        no_direction: r.get(1)?,
+        operator: parse(r.get(2)?),
+        category: parse(r.get(3)?),
+        origin_code: r.get(4)?,
+        dest_code: r.get(5)?,
+        am_peak_freq: freq(6)?,
+        am_offpeak_freq: freq(8)?,
+        pm_peak_freq: freq(10)?,
+        pm_offpeak_freq: freq(12)?,
+        loop_desc: r.get(14)?,
+    })
+}
+
+fn bus_route(r: &Row<'_>) -> Result<BusRoute, StorageError> {
+    let time = |i, column| -> Result<Option<NaiveTime>, StorageError> {
+        let time: Option<String> = r.get(i)?;
+        match time {
+            Some(t) => time_from_sql("bus_routes", column, t),
+            None => Ok(None),
+        }
+    };
+
+    Ok(BusRoute {
+        service_no: r.get(0)?,
+        direction: r.get(1)?,
+        stop_seq: r.get(2)?,
+        operator: parse(r.get(3)?),
+        bus_stop_code: r.get(4)?,
+        dist: r.get(5)?,
+        wd_first: time(6, "wd_first")?,
+        wd_last: time(7, "wd_last")?,
+        sat_first: time(8, "sat_first")?,
+        sat_last: time(9, "sat_last")?,
+        sun_first: time(10, "sun_first")?,
+        sun_last: time(11, "sun_last")?,
+    })
+}
+
+fn taxi_stand(r: &Row<'_>) -> Result<TaxiStand, StorageError> {
+    Ok(TaxiStand {
+        taxi_code: r.get(0)?,
+        lat: r.get(1)?,
+        long: r.get(2)?,
+        is_barrier_free: r.get(3)?,
+        owner: parse(r.get(4)?),
+        stand_type: parse(r.get(5)?),
+        name: r.get(6)?,
+    })
+}
+
+fn bike_parking(r: &Row<'_>) -> Result<BikeParking, StorageError> {
+    Ok(BikeParking {
+        desc: r.get(0)?,
+        lat: r.get(1)?,
+        long: r.get(2)?,
+        rack_type: parse(r.get(3)?),
+        rack_count: r.get(4)?,
+        shelter_indicator: r.get(5)?,
+    })
+}
+
+/// Enums generated by `str_enum` never fail to parse
+fn parse<T: FromStr<Err = Infallible>>(s: String) -> T {
+    match s.parse() {
+        Ok(v) => v,
+        Err(e) => match e {},
+    }
+}
+
+/// `HH:MM`, with seconds only if there are any
+fn time_to_sql(time: NaiveTime) -> String {
+    if time.second() == 0 && time.nanosecond() == 0 {
+        time.format("%H:%M").to_string()
+    } else {
+        time.format("%H:%M:%S%.f").to_string()
+    }
+}
+
+/// `None` for an empty string
+fn time_from_sql(
+    table: &'static str,
+    column: &'static str,
+    time: String,
+) -> Result<Option<NaiveTime>, StorageError> {
+    if time.is_empty() {
+        return Ok(None);
+    }
+
+    NaiveTime::parse_from_str(&time, "%H:%M:%S%.f")
+        .or_else(|_| NaiveTime::parse_from_str(&time, "%H:%M"))
+        .map(Some)
+        .map_err(|_| StorageError::Value {
+            table,
+            column,
+            value: time,
+        })
+}
+
+fn date_to_sql(date: NaiveDate) -> String {
+    date.format("%Y-%m-%d").to_string()
+}
+
+fn date_from_sql(table: &'static str, date: String) -> Result<NaiveDate, StorageError> {
+    NaiveDate::parse_from_str(&date, "%Y-%m-%d").map_err(|_| StorageError::Value {
+        table,
+        column: "effective_date",
+        value: date,
+    })
+}
+
+fn from_millis(millis: i64) -> Result<SgDateTime, StorageError> {
+    DateTime::from_timestamp_millis(millis)
+        .map(|t| sg_time::to_sg(&t))
+        .ok_or_else(|| StorageError::Value {
+            table: "fetches",
+            column: "fetched_at",
+            value: millis.to_string(),
+        })
+}
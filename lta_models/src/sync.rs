@@ -0,0 +1,295 @@
+//! Change reports between two downloads of a static dataset
+//!
+//! Records are matched on their natural key:
+//! - bus stops on the stop code
+//! - bus services on the service number and direction
+//! - bus routes on the service number, direction and stop sequence
+//! - taxi stands on the taxi code
+//! - ERP rates on the zone, vehicle types, day type, start time and effective date,
+//!   split into one record per vehicle type
+//!
+//! When a key appears more than once in a download, the last record wins.
+//! Changed fields are reported with their old and new values in their `Debug` form.
+//!
+//! With the `sqlite` feature, `storage::Store::sync_bus_stops` and friends compare a
+//! download against the stored snapshot and write the changes in one transaction.
+//!
+//! ## Example
+//! ```rust
+//! use lta_models::bus::bus_stops::BusStopsResp;
+//! use lta_models::sync;
+//!
+//! let stops: BusStopsResp =
+//!     serde_json::from_str(include_str!("../../dumped_data/bus_stops.json")).unwrap();
+//! let old = stops.value;
+//! let mut new = old[1..].to_vec();
+//! new[0].road_name = "Renamed Rd".to_string();
+//!
+//! let report = sync::diff(old.clone(), new);
+//! assert_eq!(report.removed, vec![old[0].clone()]);
+//! assert_eq!(report.modified[0].fields[0].field, "road_name");
+//! assert_eq!(report.unchanged, old.len() - 2);
+//! ```
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+/// Record of a static dataset with a natural key
+pub trait SyncRecord: Clone + PartialEq {
+    type Key: Clone + Debug + Eq + Hash;
+
+    fn key(&self) -> Self::Key;
+
+    /// Fields that differ from `new`
+    fn field_changes(&self, new: &Self) -> Vec<FieldChange>;
+
+    /// Splits records into the keyed units, e.g. ERP rates per vehicle type
+    fn split(records: Vec<Self>) -> Vec<Self> {
+        records
+    }
+}
+
+/// A changed field with the `Debug` form of its values
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldChange {
+    pub field: &'static str,
+    pub old: String,
+    pub new: String,
+}
+
+impl FieldChange {
+    pub fn new<V: Debug>(field: &'static str, old: &V, new: &V) -> Self {
+        FieldChange {
+            field,
+            old: format!("{:?}", old),
+            new: format!("{:?}", new),
+        }
+    }
+}
+
+/// Record present in both downloads with different fields
+#[derive(Debug, Clone, PartialEq)]
+pub struct Modified<T: SyncRecord> {
+    pub key: T::Key,
+    pub old: T,
+    pub new: T,
+    pub fields: Vec<FieldChange>,
+}
+
+/// Added, removed and modified records, in download order
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChangeReport<T: SyncRecord> {
+    pub added: Vec<T>,
+    pub removed: Vec<T>,
+    pub modified: Vec<Modified<T>>,
+    pub unchanged: usize,
+}
+
+impl<T: SyncRecord> ChangeReport<T> {
+    /// Number of added, removed and modified records
+    pub fn len(&self) -> usize {
+        self.added.len() + self.removed.len() + self.modified.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Compares the `old` and `new` downloads of a dataset
+pub fn diff<T: SyncRecord>(old: Vec<T>, new: Vec<T>) -> ChangeReport<T> {
+    let old = dedup(T::split(old));
+    let mut old_by_key: HashMap<T::Key, T> = old.iter().map(|r| (r.key(), r.clone())).collect();
+
+    let mut report = ChangeReport {
+        added: vec![],
+        removed: vec![],
+        modified: vec![],
+        unchanged: 0,
+    };
+
+    for new in dedup(T::split(new)) {
+        let key = new.key();
+        match old_by_key.remove(&key) {
+            None => report.added.push(new),
+            Some(old) if old == new => report.unchanged += 1,
+            Some(old) => report.modified.push(Modified {
+                fields: old.field_changes(&new),
+                key,
+                old,
+                new,
+            }),
+        }
+    }
+
+    report.removed = old
+        .into_iter()
+        .filter(|r| old_by_key.contains_key(&r.key()))
+        .collect();
+    report
+}
+
+/// Keeps the last record of every key at the position of its first
+fn dedup<T: SyncRecord>(records: Vec<T>) -> Vec<T> {
+    let mut index: HashMap<T::Key, usize> = HashMap::with_capacity(records.len());
+    let mut deduped: Vec<T> = Vec::with_capacity(records.len());
+    for record in records {
+        match index.get(&record.key()) {
+            Some(&i) => deduped[i] = record,
+            None => {
+                index.insert(record.key(), deduped.len());
+                deduped.push(record);
+            }
+        }
+    }
+    deduped
+}
+
+/// Compares the listed fields of two records
+#[cfg(any(feature = "bus", feature = "taxi", feature = "traffic"))]
+macro_rules! field_changes {
+    ($old:expr, $new:expr, [$($field:ident),* $(,)?]) => {{
+        let mut changes = Vec::new();
+        $(if $old.$field != $new.$field {
+            changes.push(FieldChange::new(stringify!($field), &$old.$field, &$new.$field));
+        })*
+        changes
+    }};
+}
+
+/// Implements `SyncRecord` keyed by `$key` and comparing the listed fields
+#[cfg(any(feature = "bus", feature = "taxi"))]
+macro_rules! sync_record {
+    ($record:ty, $key:ty, |$r:ident| $key_expr:expr, [$($field:ident),* $(,)?]) => {
+        impl SyncRecord for $record {
+            type Key = $key;
+
+            fn key(&self) -> $key {
+                let $r = self;
+                $key_expr
+            }
+
+            fn field_changes(&self, new: &Self) -> Vec<FieldChange> {
+                field_changes!(self, new, [$($field),*])
+            }
+        }
+    };
+}
+
+#[cfg(feature = "bus")]
+mod bus {
+    use super::{FieldChange, SyncRecord};
+    use crate::bus::bus_routes::BusRoute;
+    use crate::bus::bus_services::BusService;
+    use crate::bus::bus_stops::BusStop;
+
+    sync_record!(
+        BusStop,
+        u32,
+        |s| s.bus_stop_code,
+        [road_name, desc, lat, long]
+    );
+
+    sync_record!(
+        BusService,
+        (String, u32),
+        |s| (s.service_no.clone(), s.no_direction),
+        [
+            operator,
+            category,
+            origin_code,
+            dest_code,
+            am_peak_freq,
+            am_offpeak_freq,
+            pm_peak_freq,
+            pm_offpeak_freq,
+            loop_desc,
+        ]
+    );
+
+    sync_record!(
+        BusRoute,
+        (String, u32, u32),
+        |r| (r.service_no.clone(), r.direction, r.stop_seq),
+        [
+            operator,
+            bus_stop_code,
+            dist,
+            wd_first,
+            wd_last,
+            sat_first,
+            sat_last,
+            sun_first,
+            sun_last,
+        ]
+    );
+}
+
+#[cfg(feature = "taxi")]
+mod taxi {
+    use super::{FieldChange, SyncRecord};
+    use crate::taxi::taxi_stands::TaxiStand;
+
+    sync_record!(
+        TaxiStand,
+        String,
+        |s| s.taxi_code.clone(),
+        [lat, long, is_barrier_free, owner, stand_type, name]
+    );
+}
+
+#[cfg(feature = "traffic")]
+pub use self::traffic::ErpRateKey;
+
+#[cfg(feature = "traffic")]
+mod traffic {
+    use super::{FieldChange, SyncRecord};
+    use crate::traffic::erp_rates::{DayType, ErpRate, VehicleType, ZoneId};
+    use lta_utils_commons::chrono::{NaiveDate, NaiveTime};
+
+    /// Natural key of an ERP rate, after `split` it has a single vehicle type
+    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+    pub struct ErpRateKey {
+        pub zone_id: ZoneId,
+        pub vehicle_types: Vec<VehicleType>,
+        pub day_type: DayType,
+        pub start_time: Option<NaiveTime>,
+        pub effective_date: NaiveDate,
+    }
+
+    /// `split` makes one rate per vehicle type and drops rates without any, which
+    /// charge no vehicle and are not stored by `Store::upsert_erp_rates` either
+    impl SyncRecord for ErpRate {
+        type Key = ErpRateKey;
+
+        fn key(&self) -> ErpRateKey {
+            ErpRateKey {
+                zone_id: self.zone_id.clone(),
+                vehicle_types: self.vehicle_type.clone(),
+                day_type: self.day_type.clone(),
+                start_time: self.start_time,
+                effective_date: self.effective_date,
+            }
+        }
+
+        fn field_changes(&self, new: &Self) -> Vec<FieldChange> {
+            field_changes!(self, new, [end_time, charge_amt])
+        }
+
+        fn split(records: Vec<Self>) -> Vec<Self> {
+            records
+                .into_iter()
+                .flat_map(|rate| {
+                    rate.vehicle_type
+                        .iter()
+                        .map(|vehicle| ErpRate {
+                            vehicle_type: vec![vehicle.clone()],
+                            ..rate.clone()
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .collect()
+        }
+    }
+}
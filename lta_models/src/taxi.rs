@@ -36,6 +36,9 @@ pub mod taxi_avail {
         }
     }
 
+    impl_page!(TaxiAvailResp, InternalCoordinates);
+    impl_has_location!(InternalCoordinates);
+
     impl Into<Coordinates> for InternalCoordinates {
         fn into(self) -> Coordinates {
             Coordinates {
@@ -52,26 +55,24 @@ pub mod taxi_stands {
 
     pub const URL: &str = "http://datamall2.mytransport.sg/ltaodataservice/TaxiStands";
 
-    #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
-    pub enum TaxiStandOwner {
-        LTA,
-        CCS,
-        Private,
-
-        #[serde(other)]
-        Unknown
+    str_enum! {
+        #[derive(Debug, Clone, PartialEq)]
+        pub enum TaxiStandOwner {
+            LTA,
+            CCS,
+            Private,
+        }
     }
 
-    #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
-    pub enum TaxiStandType {
-        /// Allow taxis to queue in the taxi bays and wait for passengers
-        Stand,
-
-        /// Allow taxis to perform immediate pick up and drop off of passengers
-        Stop,
+    str_enum! {
+        #[derive(Debug, Clone, PartialEq)]
+        pub enum TaxiStandType {
+            /// Allow taxis to queue in the taxi bays and wait for passengers
+            Stand,
 
-        #[serde(other)]
-        Unknown
+            /// Allow taxis to perform immediate pick up and drop off of passengers
+            Stop,
+        }
     }
 
     #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
@@ -106,4 +107,7 @@ pub mod taxi_stands {
             self.value
         }
     }
+
+    impl_page!(TaxiStandsResp, TaxiStand);
+    impl_has_location!(TaxiStand);
 }
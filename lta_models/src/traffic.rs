@@ -3,8 +3,8 @@
 pub mod prelude {
     pub use {
         crate::traffic::bike_parking::{BikeParking, BikeParkingResp, RackType},
-        crate::traffic::carpark_avail::{Agency, CarPark, CarparkAvailResp},
-        crate::traffic::erp_rates::{DayType, ErpRate, ErpRatesResp, VehicleError},
+        crate::traffic::carpark_avail::{Agency, CarPark, CarparkAvailResp, LotType},
+        crate::traffic::erp_rates::{DayType, ErpRate, ErpRatesResp, VehicleType, ZoneId},
         crate::traffic::est_travel_time::{
             EstTravelTime, EstTravelTimeResp, Highway, HighwayDirection,
         },
@@ -20,11 +20,11 @@ pub mod prelude {
     };
 }
 
+pub mod erp_charges;
+pub mod erp_zones;
+
 pub mod erp_rates {
-    use core::fmt;
     use serde::{Deserialize, Serialize};
-    use std::fmt::Formatter;
-    use std::str::FromStr;
 
     use lta_utils_commons::{
         chrono::{NaiveDate, NaiveTime},
@@ -33,105 +33,65 @@ pub mod erp_rates {
             str_date,
             str_time_option::{de_str_time_opt_erp, ser_str_time_opt},
         },
+        sg_time::{self, SgDateTime},
     };
 
     pub const URL: &str = "http://datamall2.mytransport.sg/ltaodataservice/ERPRates";
 
-    #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
-    pub enum VehicleType {
-        #[serde(alias = "Passenger Cars")]
-        PassengerCars,
-
-        #[serde(alias = "Motorcycles")]
-        Motorcycles,
-
-        #[serde(alias = "Light Goods Vehicles")]
-        LightGoodsVehicles,
-
-        #[serde(alias = "Heavy Goods Vehicles")]
-        HeavyGoodsVehicles,
-
-        #[serde(alias = "Very Heavy Goods Vehicles")]
-        VeryHeavyGoodsVehicles,
-
-        #[serde(alias = "Taxis")]
-        Taxis,
-
-        #[serde(alias = "Big Buses")]
-        BigBuses,
-
-        #[serde(other)]
-        Unknown
-    }
-
-    #[derive(Debug, Clone, PartialEq)]
-    pub struct VehicleError;
-
-    impl fmt::Display for VehicleError {
-        fn fmt(&self, f: &mut Formatter) -> fmt::Result {
-            write!(f, "VehicleErr")
+    str_enum! {
+        #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+        pub enum VehicleType {
+            PassengerCars | "Passenger Cars",
+            Motorcycles,
+            LightGoodsVehicles | "Light Goods Vehicles",
+            HeavyGoodsVehicles | "Heavy Goods Vehicles",
+            VeryHeavyGoodsVehicles | "Very Heavy Goods Vehicles",
+            Taxis,
+            BigBuses | "Big Buses",
         }
     }
 
-    impl FromStr for VehicleType {
-        type Err = VehicleError;
-
-        fn from_str(s: &str) -> Result<Self, Self::Err> {
-            let res = match s {
-                "Passenger Cars" => VehicleType::PassengerCars,
-                "Motorcycles" => VehicleType::Motorcycles,
-                "Light Goods Vehicles" => VehicleType::LightGoodsVehicles,
-                "Heavy Goods Vehicles" => VehicleType::HeavyGoodsVehicles,
-                "Very Heavy Goods Vehicles" => VehicleType::VeryHeavyGoodsVehicles,
-                "Taxis" => VehicleType::Taxis,
-                "Big Buses" => VehicleType::BigBuses,
-                _ => VehicleType::Unknown,
-            };
-
-            Ok(res)
+    str_enum! {
+        #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+        pub enum DayType {
+            Weekdays,
+            Saturday,
         }
     }
 
-    #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
-    pub enum DayType {
-        Weekdays,
-        Saturday,
-    }
-
-    #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
-    pub enum ZoneId {
-        AY1,
-        AYC,
-        AYT,
-        BKE,
-        BKZ,
-        BMC,
-        CBD,
-        OC1,
-        CT1,
-        PE1,
-        CT4,
-        PE2,
-        THM,
-        OR1,
-        PE3,
-        DZ1,
-        CT5,
-        OC2,
-        OC3,
-        KP2,
-        CT6,
-        UBT,
-        TPZ,
-        KBZ,
-        GBZ,
-        SR2,
-        SR1,
-        KAL,
-        EC3,
-
-        #[serde(other)]
-        Unknown
+    str_enum! {
+        #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+        pub enum ZoneId {
+            AY1,
+            AYC,
+            AYT,
+            BKE,
+            BKZ,
+            BMC,
+            CBD,
+            OC1,
+            CT1,
+            PE1,
+            CT4,
+            PE2,
+            THM,
+            OR1,
+            PE3,
+            DZ1,
+            CT5,
+            OC2,
+            OC3,
+            KP2,
+            CT6,
+            UBT,
+            TPZ,
+            KBZ,
+            GBZ,
+            SR2,
+            SR1,
+            KAL,
+            EC3,
+        }
     }
 
     impl Sep for VehicleType {
@@ -170,6 +130,22 @@ pub mod erp_rates {
         pub effective_date: NaiveDate,
     }
 
+    impl ErpRate {
+        /// Time the charge starts on `date`
+        pub fn start_on(&self, date: NaiveDate) -> Option<SgDateTime> {
+            self.start_time.map(|t| sg_time::at(date, t))
+        }
+
+        /// Time the charge ends on `date`. An end time of midnight is the end of `date`
+        pub fn end_on(&self, date: NaiveDate) -> Option<SgDateTime> {
+            match (self.start_time, self.end_time) {
+                (Some(start), Some(end)) => Some(sg_time::on_service_date(date, end, start)),
+                (None, Some(end)) => Some(sg_time::at(date, end)),
+                _ => None,
+            }
+        }
+    }
+
     #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
     pub struct ErpRatesResp {
         pub value: Vec<ErpRate>,
@@ -180,33 +156,36 @@ pub mod erp_rates {
             self.value
         }
     }
+
+    impl_page!(ErpRatesResp, ErpRate);
 }
 
 pub mod carpark_avail {
     use serde::{Deserialize, Serialize};
 
     use lta_utils_commons::de::from_str_to_coords;
+    use lta_utils_commons::geo::HasLocation;
     use lta_utils_commons::Coordinates;
 
     pub const URL: &str = "http://datamall2.mytransport.sg/ltaodataservice/CarParkAvailabilityv2";
 
-    #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
-    pub enum LotType {
-        C,
-        L,
-        Y,
-        H,
-        #[serde(other)]
-        Unknown
+    str_enum! {
+        #[derive(Debug, Clone, PartialEq)]
+        pub enum LotType {
+            C,
+            L,
+            Y,
+            H,
+        }
     }
 
-    #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
-    pub enum Agency {
-        HDB,
-        URA,
-        LTA,
-        #[serde(other)]
-        Unknown
+    str_enum! {
+        #[derive(Debug, Clone, PartialEq)]
+        pub enum Agency {
+            HDB,
+            URA,
+            LTA,
+        }
     }
 
     #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
@@ -241,38 +220,44 @@ pub mod carpark_avail {
             self.value
         }
     }
+
+    impl_page!(CarparkAvailResp, CarPark);
+
+    impl HasLocation for CarPark {
+        fn coordinates(&self) -> Option<Coordinates> {
+            self.coords
+        }
+    }
 }
 
 pub mod est_travel_time {
     use serde::{Deserialize, Serialize};
-    use serde_repr::*;
 
     pub const URL: &str = "http://datamall2.mytransport.sg/ltaodataservice/EstTravelTimes";
 
-    #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
-    pub enum Highway {
-        PIE,
-        AYE,
-        NSC,
-        ECP,
-        CTE,
-        TPE,
-        KPE,
-        SLE,
-        BKE,
-        KJE,
-        MCE,
-        #[serde(other)]
-        Unknown
-    }
-
-    #[derive(Debug, Clone, PartialEq, Serialize_repr, Deserialize_repr)]
-    #[repr(u32)]
-    pub enum HighwayDirection {
-        EastToWest = 1,
-        WestToEast = 2,
-        #[serde(other)]
-        Unknown
+    str_enum! {
+        #[derive(Debug, Clone, PartialEq)]
+        pub enum Highway {
+            PIE,
+            AYE,
+            NSC,
+            ECP,
+            CTE,
+            TPE,
+            KPE,
+            SLE,
+            BKE,
+            KJE,
+            MCE,
+        }
+    }
+
+    int_enum! {
+        #[derive(Debug, Clone, PartialEq)]
+        pub enum HighwayDirection {
+            EastToWest = 1,
+            WestToEast = 2,
+        }
     }
 
     #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
@@ -305,22 +290,24 @@ pub mod est_travel_time {
             self.value
         }
     }
+
+    impl_page!(EstTravelTimeResp, EstTravelTime);
 }
 
 pub mod faulty_traffic_lights {
-    use lta_utils_commons::chrono::prelude::*;
     use serde::{Deserialize, Serialize};
 
     use lta_utils_commons::serde_date::ymd_hms_option;
+    use lta_utils_commons::sg_time::SgDateTime;
 
     pub const URL: &str = "http://datamall2.mytransport.sg/ltaodataservice/FaultyTrafficLights";
 
-    #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
-    pub enum TechnicalAlarmType {
-        Blackout = 4,
-        FlashingYellow = 13,
-        #[serde(other)]
-        Unknown
+    int_enum! {
+        #[derive(Debug, Clone, PartialEq)]
+        pub enum TechnicalAlarmType {
+            Blackout = 4,
+            FlashingYellow = 13,
+        }
     }
 
     #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
@@ -336,10 +323,10 @@ pub mod faulty_traffic_lights {
         pub technical_alarm_type: TechnicalAlarmType,
 
         #[serde(with = "ymd_hms_option")]
-        pub start_date: Option<DateTime<Utc>>,
+        pub start_date: Option<SgDateTime>,
 
         #[serde(with = "ymd_hms_option")]
-        pub end_date: Option<DateTime<Utc>>,
+        pub end_date: Option<SgDateTime>,
 
         pub message: String,
     }
@@ -354,6 +341,8 @@ pub mod faulty_traffic_lights {
             self.value
         }
     }
+
+    impl_page!(FaultyTrafficLightResp, FaultyTrafficLight);
 }
 
 pub mod road {
@@ -365,13 +354,11 @@ pub mod road {
     pub const URL_ROAD_OPENING: &str =
         "http://datamall2.mytransport.sg/ltaodataservice/RoadOpenings";
     pub const URL_ROAD_WORKS: &str = "http://datamall2.mytransport.sg/ltaodataservice/RoadWorks";
-    
+
     #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
     pub enum RoadDetailsType {
         RoadOpening,
         RoadWorks,
-        #[serde(other)]
-        Unknown
     }
 
     #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
@@ -404,6 +391,8 @@ pub mod road {
             self.value
         }
     }
+
+    impl_page!(RoadDetailsResp, RoadDetails);
 }
 
 pub mod traffic_images {
@@ -438,6 +427,9 @@ pub mod traffic_images {
             self.value
         }
     }
+
+    impl_page!(TrafficImageResp, TrafficImage);
+    impl_has_location!(TrafficImage);
 }
 
 pub mod traffic_incidents {
@@ -445,38 +437,21 @@ pub mod traffic_incidents {
 
     pub const URL: &str = "http://datamall2.mytransport.sg/ltaodataservice/TrafficIncidents";
 
-    #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
-    pub enum IncidentType {
-        Accident,
-
-        #[serde(alias = "Road Works")]
-        RoadWorks,
-
-        #[serde(alias = "Vehicle breakdown")]
-        VehicleBreakdown,
-
-        Weather,
-
-        Obstacle,
-
-        #[serde(alias = "Road Block")]
-        RoadBlock,
-
-        #[serde(alias = "Heavy Traffic")]
-        HeavyTraffic,
-
-        #[serde(alias = "Misc.")]
-        Misc,
-
-        Diversion,
-
-        #[serde(alias = "Unattended Vehicle")]
-        UnattendedVehicle,
-
-        Roadwork,
-
-        #[serde(other)]
-        Unknown
+    str_enum! {
+        #[derive(Debug, Clone, PartialEq)]
+        pub enum IncidentType {
+            Accident,
+            RoadWorks | "Road Works",
+            VehicleBreakdown | "Vehicle breakdown",
+            Weather,
+            Obstacle,
+            RoadBlock | "Road Block",
+            HeavyTraffic | "Heavy Traffic",
+            Misc | "Misc.",
+            Diversion,
+            UnattendedVehicle | "Unattended Vehicle",
+            Roadwork,
+        }
     }
 
     #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
@@ -504,6 +479,9 @@ pub mod traffic_incidents {
             self.value
         }
     }
+
+    impl_page!(TrafficIncidentResp, TrafficIncident);
+    impl_has_location!(TrafficIncident);
 }
 
 pub mod traffic_speed_bands {
@@ -514,31 +492,17 @@ pub mod traffic_speed_bands {
 
     pub const URL: &str = "http://datamall2.mytransport.sg/ltaodataservice/TrafficSpeedBandsv2";
 
-    #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
-    pub enum RoadCategory {
-        #[serde(alias = "A")]
-        Expressway,
-
-        #[serde(alias = "B")]
-        MajorArterialRoads,
-
-        #[serde(alias = "C")]
-        ArterialRoads,
-
-        #[serde(alias = "D")]
-        MinorArterialRoads,
-
-        #[serde(alias = "E")]
-        SmallRoads,
-
-        #[serde(alias = "F")]
-        SlipRoads,
-
-        #[serde(alias = "G")]
-        NoCategoryInfoAvail,
-
-        #[serde(other)]
-        Unknown
+    str_enum! {
+        #[derive(Debug, Clone, PartialEq)]
+        pub enum RoadCategory {
+            Expressway | "A",
+            MajorArterialRoads | "B",
+            ArterialRoads | "C",
+            MinorArterialRoads | "D",
+            SmallRoads | "E",
+            SlipRoads | "F",
+            NoCategoryInfoAvail | "G",
+        }
     }
 
     #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
@@ -573,6 +537,17 @@ pub mod traffic_speed_bands {
             self.value
         }
     }
+
+    impl_page!(TrafficSpeedBandResp, TrafficSpeedBand);
+
+    /// Speed bands without a location are left out
+    #[cfg(feature = "geo-types")]
+    impl From<TrafficSpeedBandResp> for geo_types::MultiLineString<f64> {
+        fn from(resp: TrafficSpeedBandResp) -> Self {
+            let locations = resp.value.iter().filter_map(|b| b.coord_start_end.as_ref());
+            lta_utils_commons::geo::to_multi_line_string(locations)
+        }
+    }
 }
 
 pub mod vms_emas {
@@ -605,6 +580,9 @@ pub mod vms_emas {
             self.value
         }
     }
+
+    impl_page!(VMSResp, VMS);
+    impl_has_location!(VMS);
 }
 
 pub mod bike_parking {
@@ -614,49 +592,23 @@ pub mod bike_parking {
 
     pub const URL: &str = "http://datamall2.mytransport.sg/ltaodataservice/BicycleParkingv2";
 
-    #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
-    pub enum RackType {
-        #[serde(alias = "Yellow Box")]
-        YellowBox,
-
-        #[serde(alias = "Yellow Box_Private")]
-        YellowBoxPrivate,
-
-        #[serde(alias = "Racks_MRT")]
-        RacksMRT,
-
-        #[serde(alias = "Racks_Bus Stop")]
-        RacksBusStop,
-
-        #[serde(alias = "Racks_URA")]
-        RacksURA,
-
-        #[serde(alias = "Racks_AVA")]
-        RacksAVA,
-
-        #[serde(alias = "Racks_ITE")]
-        RacksITE,
-
-        #[serde(alias = "Racks_JTC")]
-        RacksJTC,
-
-        #[serde(alias = "Racks_PA")]
-        RacksPA,
-
-        #[serde(alias = "Racks_NParks")]
-        RacksNParks,
-
-        #[serde(alias = "Racks_HDB")]
-        RacksHDB,
-
-        #[serde(alias = "Racks_NLB")]
-        RacksNLB,
-
-        #[serde(alias = "Racks_NEA")]
-        RacksNEA,
-
-        #[serde(other)]
-        Unknown
+    str_enum! {
+        #[derive(Debug, Clone, PartialEq)]
+        pub enum RackType {
+            YellowBox | "Yellow Box",
+            YellowBoxPrivate | "Yellow Box_Private",
+            RacksMRT | "Racks_MRT",
+            RacksBusStop | "Racks_Bus Stop",
+            RacksURA | "Racks_URA",
+            RacksAVA | "Racks_AVA",
+            RacksITE | "Racks_ITE",
+            RacksJTC | "Racks_JTC",
+            RacksPA | "Racks_PA",
+            RacksNParks | "Racks_NParks",
+            RacksHDB | "Racks_HDB",
+            RacksNLB | "Racks_NLB",
+            RacksNEA | "Racks_NEA",
+        }
     }
 
     #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
@@ -689,4 +641,7 @@ pub mod bike_parking {
             self.value
         }
     }
+
+    impl_page!(BikeParkingResp, BikeParking);
+    impl_has_location!(BikeParking);
 }
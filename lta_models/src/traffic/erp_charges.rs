@@ -0,0 +1,203 @@
+//! ERP charges for a zone, vehicle type and time
+//!
+//! `ErpRateTable` indexes the rows of `get_erp_rates` so a charge can be looked up
+//! directly. When LTA publishes new rates, rows with a later `effective_date` take
+//! over from that date onwards while older rows still answer for earlier dates.
+//!
+//! ERP is not charged on Sundays and public holidays. Public holidays are not part
+//! of the API, add them with `ErpRateTable::with_holidays`, e.g. from
+//! `calendar::Calendar::sg().dates()` with the `calendar` feature.
+//!
+//! ## Example
+//! ```rust
+//! use lta_models::traffic::erp_charges::ErpRateTable;
+//! use lta_models::traffic::erp_rates::{ErpRatesResp, VehicleType, ZoneId};
+//! use lta_utils_commons::chrono::{NaiveDate, NaiveTime};
+//! use lta_utils_commons::sg_time;
+//!
+//! let json = r#"{"value": [{"VehicleType": "Passenger Cars/Light Goods Vehicles/Taxis",
+//!     "DayType": "Weekdays", "StartTime": "08:30", "EndTime": "09:00", "ZoneID": "CT1",
+//!     "ChargeAmount": 2.5, "EffectiveDate": "2019-11-16"}]}"#;
+//! let rates: ErpRatesResp = serde_json::from_str(json).unwrap();
+//! let table = ErpRateTable::new(rates.value);
+//!
+//! let tuesday = NaiveDate::from_ymd_opt(2019, 11, 19).unwrap();
+//! let at = sg_time::at(tuesday, NaiveTime::from_hms_opt(8, 47, 0).unwrap());
+//! assert_eq!(table.charge(&ZoneId::CT1, &VehicleType::Taxis, &at), 2.5);
+//! ```
+
+use crate::traffic::erp_rates::{DayType, ErpRate, VehicleType, ZoneId};
+use lta_utils_commons::chrono::{DateTime, Datelike, NaiveDate, NaiveTime, TimeZone, Weekday};
+use lta_utils_commons::sg_time;
+use std::collections::{HashMap, HashSet};
+use std::iter::FromIterator;
+
+/// A period of the day with a fixed charge
+#[derive(Debug, Clone, PartialEq)]
+pub struct ErpSlot {
+    pub start: NaiveTime,
+
+    /// `None` if the slot runs until midnight
+    pub end: Option<NaiveTime>,
+
+    pub charge_amt: f32,
+}
+
+impl ErpSlot {
+    /// `start <= time < end`
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        time >= self.start && self.end.is_none_or(|end| time < end)
+    }
+}
+
+/// Slots of one zone, vehicle type and day type from a single `effective_date`
+#[derive(Debug, Clone, PartialEq)]
+struct RateSet {
+    effective_date: NaiveDate,
+    slots: Vec<ErpSlot>,
+}
+
+type Key = (ZoneId, VehicleType, DayType);
+
+/// ERP rates indexed by zone, vehicle type and day type
+#[derive(Debug, Clone, Default)]
+pub struct ErpRateTable {
+    /// Sorted by `effective_date`, oldest first
+    rates: HashMap<Key, Vec<RateSet>>,
+    holidays: HashSet<NaiveDate>,
+}
+
+impl ErpRateTable {
+    pub fn new(rates: Vec<ErpRate>) -> Self {
+        rates.into_iter().collect()
+    }
+
+    /// Adds a rate. Rows listing several vehicle types apply to each of them
+    pub fn insert(&mut self, rate: ErpRate) {
+        let start = match rate.start_time {
+            Some(start) => start,
+            None => return,
+        };
+
+        // the API uses 00:00 to end the last slot of the day
+        let end = rate.end_time.filter(|&end| end > start);
+        let slot = ErpSlot {
+            start,
+            end,
+            charge_amt: rate.charge_amt,
+        };
+
+        let effective_date = rate.effective_date;
+        for vehicle_type in rate.vehicle_type {
+            let key = (rate.zone_id.clone(), vehicle_type, rate.day_type.clone());
+            let sets = self.rates.entry(key).or_default();
+            let pos = sets.binary_search_by(|s| s.effective_date.cmp(&effective_date));
+
+            let set = match pos {
+                Ok(i) => &mut sets[i],
+                Err(i) => {
+                    sets.insert(
+                        i,
+                        RateSet {
+                            effective_date,
+                            slots: Vec::new(),
+                        },
+                    );
+                    &mut sets[i]
+                }
+            };
+
+            // the same slot can be listed under a vehicle type on its own and in a group
+            if set.slots.contains(&slot) {
+                continue;
+            }
+
+            let i = set.slots.partition_point(|s| s.start <= slot.start);
+            set.slots.insert(i, slot.clone());
+        }
+    }
+
+    /// Dates that are charged like Sundays
+    pub fn with_holidays<I>(mut self, holidays: I) -> Self
+    where
+        I: IntoIterator<Item = NaiveDate>,
+    {
+        self.holidays.extend(holidays);
+        self
+    }
+
+    pub fn is_holiday(&self, date: NaiveDate) -> bool {
+        self.holidays.contains(&date)
+    }
+
+    /// ERP day type of `date`, `None` on Sundays and public holidays when there is no charge
+    pub fn day_type(&self, date: NaiveDate) -> Option<DayType> {
+        if self.is_holiday(date) {
+            return None;
+        }
+
+        match date.weekday() {
+            Weekday::Sun => None,
+            Weekday::Sat => Some(DayType::Saturday),
+            _ => Some(DayType::Weekdays),
+        }
+    }
+
+    /// Every charged slot of a zone for a vehicle type on `date`, in order of start time.
+    /// Empty on Sundays, public holidays and for zones without rates.
+    pub fn schedule(
+        &self,
+        zone: &ZoneId,
+        vehicle_type: &VehicleType,
+        date: NaiveDate,
+    ) -> &[ErpSlot] {
+        let day_type = match self.day_type(date) {
+            Some(d) => d,
+            None => return &[],
+        };
+
+        let key = (zone.clone(), vehicle_type.clone(), day_type);
+        self.rates
+            .get(&key)
+            .and_then(|sets| sets.iter().rev().find(|s| s.effective_date <= date))
+            .map_or(&[], |s| s.slots.as_slice())
+    }
+
+    /// Slot in force at `at`, if any
+    pub fn slot_at<Tz: TimeZone>(
+        &self,
+        zone: &ZoneId,
+        vehicle_type: &VehicleType,
+        at: &DateTime<Tz>,
+    ) -> Option<&ErpSlot> {
+        let at = sg_time::to_sg(at);
+        self.schedule(zone, vehicle_type, at.date_naive())
+            .iter()
+            .find(|s| s.contains(at.time()))
+    }
+
+    /// Charge for passing a gantry of `zone` at `at`, 0 if there is none
+    pub fn charge<Tz: TimeZone>(
+        &self,
+        zone: &ZoneId,
+        vehicle_type: &VehicleType,
+        at: &DateTime<Tz>,
+    ) -> f32 {
+        self.slot_at(zone, vehicle_type, at)
+            .map_or(0.0, |s| s.charge_amt)
+    }
+
+    /// Zones that have rates
+    pub fn zones(&self) -> impl Iterator<Item = &ZoneId> {
+        let zones: HashSet<&ZoneId> = self.rates.keys().map(|(zone, _, _)| zone).collect();
+        zones.into_iter()
+    }
+}
+
+impl FromIterator<ErpRate> for ErpRateTable {
+    fn from_iter<I: IntoIterator<Item = ErpRate>>(iter: I) -> Self {
+        let mut table = ErpRateTable::default();
+        iter.into_iter().for_each(|r| table.insert(r));
+        table
+    }
+}
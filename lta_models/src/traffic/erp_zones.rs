@@ -0,0 +1,212 @@
+//! ERP gantry metadata
+//!
+//! The API only returns zone codes such as `CT1`. A `GantryTable` holds the
+//! gantries of each zone with their road and coordinates, so charges from
+//! `erp_charges` can be put on a map and the gantry near a coordinate found.
+//!
+//! Gantry positions and zone descriptions are not bundled. Export the ERP gantry
+//! layer of LTA's geospatial datasets to a file of `zone,road,location,lat,long`
+//! lines and load it with `GantryTable::from_file`.
+//! `Gantry` also deserialises from records with those fields, so a JSON export can
+//! be read with `serde_json`.
+//!
+//! ## Example
+//! ```rust
+//! use lta_models::traffic::erp_rates::ZoneId;
+//! use lta_models::traffic::erp_zones::GantryTable;
+//!
+//! let table = GantryTable::from_reader(
+//!     "zone,road,location,lat,long
+//!      CT1,Central Expressway,after Braddell Road,1.34,103.85
+//!      PE1,Pan Island Expressway,after Adam Road,1.33,103.82"
+//!         .as_bytes(),
+//! )
+//! .unwrap();
+//! assert_eq!(table.roads(&ZoneId::CT1), vec!["Central Expressway"]);
+//!
+//! // 40m south of the CT1 gantry
+//! let ct1 = table.in_zone(&ZoneId::CT1).next().unwrap();
+//! let car = ct1.coordinates().destination(180.0, 40.0);
+//! let (gantry, dist_m) = table.nearest(car.lat, car.long).unwrap();
+//! assert_eq!(gantry.zone, ZoneId::CT1);
+//! assert!((dist_m - 40.0).abs() < 0.1);
+//! ```
+
+use crate::traffic::erp_rates::ZoneId;
+use lta_utils_commons::geo::HasLocation;
+use lta_utils_commons::Coordinates;
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display, Formatter};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::iter::FromIterator;
+use std::path::Path;
+
+/// An ERP gantry
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Gantry {
+    pub zone: ZoneId,
+    pub road: String,
+
+    /// Where the gantry is along `road`
+    pub location: String,
+    pub lat: f64,
+    pub long: f64,
+}
+
+impl Gantry {
+    pub fn new<R: Into<String>, L: Into<String>>(
+        zone: ZoneId,
+        road: R,
+        location: L,
+        lat: f64,
+        long: f64,
+    ) -> Self {
+        Gantry {
+            zone,
+            road: road.into(),
+            location: location.into(),
+            lat,
+            long,
+        }
+    }
+
+    pub fn coordinates(&self) -> Coordinates {
+        Coordinates::new(self.lat, self.long)
+    }
+}
+
+impl HasLocation for Gantry {
+    fn coordinates(&self) -> Option<Coordinates> {
+        Some(Gantry::coordinates(self))
+    }
+}
+
+/// Error reading a gantry file
+#[derive(Debug)]
+pub enum GantryError {
+    Io(io::Error),
+
+    /// Line number starting from 1 and what is wrong with it
+    Parse(usize, String),
+}
+
+impl Display for GantryError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            GantryError::Io(e) => write!(f, "io error: {}", e),
+            GantryError::Parse(line, msg) => write!(f, "line {}: {}", line, msg),
+        }
+    }
+}
+
+impl std::error::Error for GantryError {}
+
+impl From<io::Error> for GantryError {
+    fn from(e: io::Error) -> Self {
+        GantryError::Io(e)
+    }
+}
+
+/// Gantries loaded from LTA's ERP gantry layer
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GantryTable {
+    gantries: Vec<Gantry>,
+}
+
+impl GantryTable {
+    pub fn new(gantries: Vec<Gantry>) -> Self {
+        GantryTable { gantries }
+    }
+
+    /// Reads `zone,road,location,lat,long` lines, `location` may contain commas.
+    /// Blank lines, lines starting with `#` and a `zone,road,location,lat,long`
+    /// header are skipped
+    pub fn from_reader<R: BufRead>(reader: R) -> Result<Self, GantryError> {
+        let mut gantries = Vec::new();
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line == "zone,road,location,lat,long" {
+                continue;
+            }
+
+            let parse_err =
+                || GantryError::Parse(i + 1, "expected zone,road,location,lat,long".to_string());
+            let (zone, rest) = line.split_once(',').ok_or_else(parse_err)?;
+            let (road, rest) = rest.split_once(',').ok_or_else(parse_err)?;
+            let (rest, long) = rest.rsplit_once(',').ok_or_else(parse_err)?;
+            let (location, lat) = rest.rsplit_once(',').ok_or_else(parse_err)?;
+            let coord = |value: &str| {
+                value.trim().parse::<f64>().map_err(|e| {
+                    GantryError::Parse(i + 1, format!("bad coordinate {}: {}", value, e))
+                })
+            };
+
+            let zone: ZoneId = match zone.trim().parse() {
+                Ok(zone) => zone,
+                Err(e) => match e {},
+            };
+            gantries.push(Gantry::new(
+                zone,
+                road.trim(),
+                location.trim(),
+                coord(lat)?,
+                coord(long)?,
+            ));
+        }
+
+        Ok(GantryTable::new(gantries))
+    }
+
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, GantryError> {
+        GantryTable::from_reader(BufReader::new(File::open(path)?))
+    }
+
+    pub fn gantries(&self) -> &[Gantry] {
+        &self.gantries
+    }
+
+    /// Gantries of `zone`
+    pub fn in_zone<'a>(&'a self, zone: &'a ZoneId) -> impl Iterator<Item = &'a Gantry> + 'a {
+        self.gantries.iter().filter(move |g| &g.zone == zone)
+    }
+
+    /// Roads with gantries in `zone`, in the order they were loaded
+    pub fn roads(&self, zone: &ZoneId) -> Vec<&str> {
+        let mut roads: Vec<&str> = Vec::new();
+        for gantry in self.gantries.iter().filter(|g| &g.zone == zone) {
+            if !roads.contains(&gantry.road.as_str()) {
+                roads.push(&gantry.road);
+            }
+        }
+        roads
+    }
+
+    /// Gantry closest to a coordinate and its distance in metres
+    pub fn nearest(&self, lat: f64, long: f64) -> Option<(&Gantry, f64)> {
+        self.within(lat, long, f64::INFINITY).into_iter().next()
+    }
+
+    /// Gantries within `radius_m` metres of a coordinate, closest first
+    ///
+    /// Gantries for both directions of a road can be a few metres apart,
+    /// check `zone` and `location` when more than one is returned.
+    pub fn within(&self, lat: f64, long: f64, radius_m: f64) -> Vec<(&Gantry, f64)> {
+        let at = Coordinates::new(lat, long);
+        let mut found: Vec<(&Gantry, f64)> = self
+            .gantries
+            .iter()
+            .map(|g| (g, at.distance_m(&g.coordinates())))
+            .filter(|(_, dist)| *dist <= radius_m)
+            .collect();
+        found.sort_by(|a, b| a.1.total_cmp(&b.1));
+        found
+    }
+}
+
+impl FromIterator<Gantry> for GantryTable {
+    fn from_iter<I: IntoIterator<Item = Gantry>>(iter: I) -> Self {
+        GantryTable::new(iter.into_iter().collect())
+    }
+}
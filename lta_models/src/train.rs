@@ -9,41 +9,39 @@ pub mod prelude {
 
 pub mod train_service_alert {
     use serde::{Deserialize, Serialize};
-    use serde_repr::*;
 
     use lta_utils_commons::de::{delimited, Sep, WrapErr};
+    use lta_utils_commons::serde_date::ymd_hms;
+    use lta_utils_commons::sg_time::SgDateTime;
     use std::ops::Deref;
     use std::str::FromStr;
 
     pub const URL: &str = "http://datamall2.mytransport.sg/ltaodataservice/TrainServiceAlerts";
 
-    #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
-    pub enum MrtLine {
-        CCL,
-        CEL,
-        CGL,
-        DTL,
-        EWL,
-        NEL,
-        NSL,
-        PEL,
-        PWL,
-        SEL,
-        SWL,
-        BPL,
-
-        #[serde(other)]
-        Unknown
+    str_enum! {
+        #[derive(Debug, Clone, PartialEq)]
+        pub enum MrtLine {
+            CCL,
+            CEL,
+            CGL,
+            DTL,
+            EWL,
+            NEL,
+            NSL,
+            PEL,
+            PWL,
+            SEL,
+            SWL,
+            BPL,
+        }
     }
 
-    #[derive(Debug, Clone, PartialEq, Serialize_repr, Deserialize_repr)]
-    #[repr(u32)]
-    pub enum TrainStatus {
-        Normal = 1,
-        Disrupted = 2,
-
-        #[serde(other)]
-        Unknown
+    int_enum! {
+        #[derive(Debug, Clone, PartialEq)]
+        pub enum TrainStatus {
+            Normal = 1,
+            Disrupted = 2,
+        }
     }
 
     impl Sep for StringWrap {
@@ -93,7 +91,9 @@ pub mod train_service_alert {
     #[serde(rename_all(deserialize = "PascalCase"))]
     pub struct TrainServiceAlertMessage {
         pub content: String,
-        pub created_date: String,
+
+        #[serde(with = "ymd_hms")]
+        pub created_date: SgDateTime,
     }
 
     #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
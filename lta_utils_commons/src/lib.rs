@@ -1,45 +1,164 @@
 //! Utilities for transforming data and other misc
-
-#[macro_use]
-extern crate lazy_static;
+//!
+//! ## Features
+//! - `http`: `Client` trait and `reqwest` errors, needed by the async client
+//! - `blocking`: `http` with blocking `reqwest`, needed by the blocking client
+//!
+//! - `geo-types`: conversions between `Coordinates`, `Location` and `geo::*` types and `geo_types`
+//!
+//! Without them, this crate only depends on `serde`, `serde_json` and `chrono`
+//! so the models can be used without an HTTP client.
 
 pub use chrono;
+#[cfg(feature = "http")]
 pub use reqwest;
 pub use serde;
-use serde::Serialize;
-use std::fmt::Debug;
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Debug, Display, Formatter};
 
 /// Result type for lta-rs
-pub type LTAResult<T> = reqwest::Result<T>;
+pub type LTAResult<T> = Result<T, LTAError>;
+
 /// Error type for lta-rs
-pub type LTAError = reqwest::Error;
+#[derive(Debug)]
+pub enum LTAError {
+    /// Request failed or the response body could not be read
+    #[cfg(feature = "http")]
+    Http(reqwest::Error),
 
-/// Regex patterns
-pub mod regex {
-    use regex::Regex;
+    /// Response was received but could not be deserialised
+    Parse(parse::ParseError),
+}
 
-    lazy_static! {
-        pub static ref BUS_FREQ_RE: Regex =
-            Regex::new(r"^(\d{1,3})?-?(\d{1,3})?$").unwrap();
+impl Display for LTAError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(feature = "http")]
+            LTAError::Http(e) => write!(f, "{}", e),
+            LTAError::Parse(e) => write!(f, "{}", e),
+        }
+    }
+}
 
-        pub static ref CARPARK_COORDS_RE: Regex =
-            Regex::new(r"^([+-]?([0-9]*[.])?[0-9]+) ([+-]?([0-9]*[.])?[0-9]+)$").unwrap();
+impl std::error::Error for LTAError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            #[cfg(feature = "http")]
+            LTAError::Http(e) => Some(e),
+            LTAError::Parse(e) => Some(e),
+        }
+    }
+}
 
-        pub static ref SPEED_BAND_RE: Regex =
-            Regex::new(r"^([+-]?([0-9]*[.])?[0-9]+) ([+-]?([0-9]*[.])?[0-9]+) ([+-]?([0-9]*[.])?[0-9]+) ([+-]?([0-9]*[.])?[0-9]+)$")
-                .unwrap();
+#[cfg(feature = "http")]
+impl From<reqwest::Error> for LTAError {
+    fn from(e: reqwest::Error) -> Self {
+        LTAError::Http(e)
+    }
+}
+
+impl From<parse::ParseError> for LTAError {
+    fn from(e: parse::ParseError) -> Self {
+        LTAError::Parse(e)
+    }
+}
+
+/// Singapore time (SGT), UTC+8 all year round
+///
+/// Every timestamp in lta-rs is a `SgDateTime`. Schedule fields that only hold a time
+/// of day are `NaiveTime`s in Singapore time, use `at` or `on_service_date` to give them a date.
+pub mod sg_time {
+    use chrono::{
+        DateTime, Duration, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc,
+    };
+
+    /// Date and time in Singapore
+    pub type SgDateTime = DateTime<FixedOffset>;
+
+    /// Offset of Singapore from UTC in seconds
+    pub const SGT_OFFSET_SECS: i32 = 8 * 3600;
+
+    /// `FixedOffset` for Singapore
+    pub fn sgt() -> FixedOffset {
+        FixedOffset::east_opt(SGT_OFFSET_SECS).expect("UTC+8 is a valid offset")
+    }
+
+    /// Current time in Singapore
+    pub fn now() -> SgDateTime {
+        Utc::now().with_timezone(&sgt())
+    }
+
+    /// Treats a naive date time as Singapore local time
+    pub fn from_local(dt: NaiveDateTime) -> SgDateTime {
+        sgt()
+            .from_local_datetime(&dt)
+            .earliest()
+            .expect("a fixed offset always maps local times")
+    }
+
+    /// Converts a timestamp in any time zone to Singapore time
+    pub fn to_sg<Tz: TimeZone>(dt: &DateTime<Tz>) -> SgDateTime {
+        dt.with_timezone(&sgt())
+    }
+
+    /// Combines a date and a time of day in Singapore
+    pub fn at(date: NaiveDate, time: NaiveTime) -> SgDateTime {
+        from_local(date.and_time(time))
+    }
+
+    /// Combines a schedule time with the service date it belongs to.
+    ///
+    /// Services such as buses keep running past midnight, so times earlier than
+    /// `day_start` are taken to be on the day after `date`.
+    pub fn on_service_date(date: NaiveDate, time: NaiveTime, day_start: NaiveTime) -> SgDateTime {
+        let dt = at(date, time);
+        if time < day_start {
+            dt + Duration::days(1)
+        } else {
+            dt
+        }
     }
 }
 
 /// Utils for date types
 pub mod serde_date {
-    pub mod ymd_hms_option {
-        use chrono::{DateTime, TimeZone, Utc};
+    /// `YYYY-MM-DD HH:MM:SS` in Singapore time
+    pub mod ymd_hms {
+        use crate::sg_time::{from_local, SgDateTime};
+        use chrono::NaiveDateTime;
         use serde::{Deserialize, Deserializer, Serializer};
 
-        const FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+        pub(crate) const FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+        /// Also accepts fractional seconds, e.g. `2014-04-12 01:58:00.0`
+        pub(crate) const PARSE_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.f";
+
+        pub fn serialize<S>(date: &SgDateTime, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            serializer.serialize_str(&date.format(FORMAT).to_string())
+        }
+
+        pub fn deserialize<'de, D>(deserializer: D) -> Result<SgDateTime, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            let s = String::deserialize(deserializer)?;
+            NaiveDateTime::parse_from_str(&s, PARSE_FORMAT)
+                .map(from_local)
+                .map_err(serde::de::Error::custom)
+        }
+    }
+
+    /// Same as `ymd_hms`, empty strings and `-` are `None`
+    pub mod ymd_hms_option {
+        use super::ymd_hms::{FORMAT, PARSE_FORMAT};
+        use crate::sg_time::{from_local, SgDateTime};
+        use chrono::NaiveDateTime;
+        use serde::{Deserialize, Deserializer, Serializer};
 
-        pub fn serialize<S>(date: &Option<DateTime<Utc>>, serializer: S) -> Result<S::Ok, S::Error>
+        pub fn serialize<S>(date: &Option<SgDateTime>, serializer: S) -> Result<S::Ok, S::Error>
         where
             S: Serializer,
         {
@@ -52,21 +171,50 @@ pub mod serde_date {
             }
         }
 
-        pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<DateTime<Utc>>, D::Error>
+        pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<SgDateTime>, D::Error>
         where
             D: Deserializer<'de>,
         {
             let s = String::deserialize(deserializer)?;
-            Utc.datetime_from_str(&s, FORMAT)
-                .map(Some)
+            if s.is_empty() || s == "-" {
+                return Ok(None);
+            }
+
+            NaiveDateTime::parse_from_str(&s, PARSE_FORMAT)
+                .map(|dt| Some(from_local(dt)))
+                .map_err(serde::de::Error::custom)
+        }
+    }
+
+    /// RFC 3339 timestamps with any offset, converted to Singapore time
+    pub mod rfc3339 {
+        use crate::sg_time::{sgt, SgDateTime};
+        use chrono::DateTime;
+        use serde::{Deserialize, Deserializer, Serializer};
+
+        pub fn serialize<S>(date: &SgDateTime, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            serializer.serialize_str(&date.to_rfc3339())
+        }
+
+        pub fn deserialize<'de, D>(deserializer: D) -> Result<SgDateTime, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            let s = String::deserialize(deserializer)?;
+            DateTime::parse_from_rfc3339(&s)
+                .map(|dt| dt.with_timezone(&sgt()))
                 .map_err(serde::de::Error::custom)
         }
     }
 
     pub mod str_time_option {
-        use chrono::{NaiveTime, Timelike};
+        use chrono::NaiveTime;
         use serde::{Deserialize, Deserializer, Serializer};
 
+        /// Serialises as `HH:MM:SS`
         pub fn ser_str_time_opt<S>(
             opt_time: &Option<NaiveTime>,
             serializer: S,
@@ -75,16 +223,7 @@ pub mod serde_date {
             S: Serializer,
         {
             match opt_time {
-                Some(time) => {
-                    let hr = time.hour();
-                    let min = time.minute();
-                    let mut sec_str = String::with_capacity(1);
-                    sec_str.push_str("0");
-
-                    let s = [hr.to_string(), min.to_string(), sec_str].join(":");
-
-                    serializer.serialize_str(&s)
-                }
+                Some(time) => serializer.serialize_str(&time.format("%H:%M:%S").to_string()),
                 None => serializer.serialize_none(),
             }
         }
@@ -97,14 +236,8 @@ pub mod serde_date {
             if s.eq("-") {
                 return Ok(None);
             }
-            let hr = &mut s[0..1].parse().map_err(serde::de::Error::custom)?;
-            let min = &s[3..4].parse().map_err(serde::de::Error::custom)?;
-            if *hr == 24 {
-                *hr = 0
-            }
 
-            let time = NaiveTime::from_hms_opt(*hr, *min, 0);
-            Ok(time)
+            parse_hr_min(&s, s.get(0..2), s.get(3..5))
         }
 
         pub fn de_str_time_opt_br<'de, D>(deserializer: D) -> Result<Option<NaiveTime>, D::Error>
@@ -115,14 +248,30 @@ pub mod serde_date {
             if s.eq("-") {
                 return Ok(None);
             }
-            let hr = &mut s[0..1].parse().map_err(serde::de::Error::custom)?;
-            let min = &s[2..3].parse().map_err(serde::de::Error::custom)?;
-            if *hr == 24 {
-                *hr = 0
+
+            parse_hr_min(&s, s.get(0..2), s.get(2..4))
+        }
+
+        /// `24` as the hour is treated as midnight
+        fn parse_hr_min<E>(
+            s: &str,
+            hr: Option<&str>,
+            min: Option<&str>,
+        ) -> Result<Option<NaiveTime>, E>
+        where
+            E: serde::de::Error,
+        {
+            let invalid = || E::custom(format!("invalid time `{}`", s));
+
+            let mut hr: u32 = hr.ok_or_else(invalid)?.parse().map_err(|_| invalid())?;
+            let min: u32 = min.ok_or_else(invalid)?.parse().map_err(|_| invalid())?;
+            if hr == 24 {
+                hr = 0
             }
 
-            let time = NaiveTime::from_hms_opt(*hr, *min, 0);
-            Ok(time)
+            NaiveTime::from_hms_opt(hr, min, 0)
+                .map(Some)
+                .ok_or_else(invalid)
         }
     }
 
@@ -158,11 +307,12 @@ pub mod de {
     use std::marker::PhantomData as Phantom;
     use std::str::FromStr;
 
-    use crate::{regex::*, Coordinates, Location};
+    use crate::svy21::{is_wgs84, Svy21Coordinates};
+    use crate::{Coordinates, Location};
     use serde::de::{self, Visitor};
-    use serde::export::Formatter;
     use serde::{Deserialize, Deserializer};
     use serde_json::Value;
+    use std::fmt::Formatter;
 
     /// Error for wrapped data
     pub struct WrapErr;
@@ -178,14 +328,25 @@ pub mod de {
         }
     }
 
-    /// If error, return None
-    pub fn treat_error_as_none<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+    /// Returns None for `null`, `{}` or an object where every field is an empty string,
+    /// which is how the API represents missing data. Anything else has to deserialise as `T`
+    pub fn treat_empty_as_none<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
     where
         T: Deserialize<'de>,
         D: Deserializer<'de>,
     {
         let value: Value = Deserialize::deserialize(deserializer)?;
-        Ok(T::deserialize(value).ok())
+        let is_empty = match &value {
+            Value::Null => true,
+            Value::Object(map) => map.values().all(|v| v.as_str() == Some("")),
+            _ => false,
+        };
+
+        if is_empty {
+            return Ok(None);
+        }
+
+        T::deserialize(value).map(Some).map_err(de::Error::custom)
     }
 
     /// Simple conversion of Y and N to boolean
@@ -205,21 +366,86 @@ pub mod de {
     /// To be used when coordinates are space separated
     /// in a string and you would like to convert them to a Coordinates
     /// structure.
+    ///
+    /// Some carparks are given as several points, in that case the first point is used
     pub fn from_str_to_coords<'de, D>(deserializer: D) -> Result<Option<Coordinates>, D::Error>
     where
         D: Deserializer<'de>,
     {
         let s = String::deserialize(deserializer)?;
 
-        if s.is_empty() || !CARPARK_COORDS_RE.is_match(s.as_str()) {
+        if s.is_empty() {
             return Ok(None);
         }
 
-        let caps = CARPARK_COORDS_RE.captures(&s).unwrap();
-        let lat: f64 = caps.get(1).map_or(0.0, |m| m.as_str().parse().unwrap());
-        let long: f64 = caps.get(3).map_or(0.0, |m| m.as_str().parse().unwrap());
+        match parse_floats(&s) {
+            Some(nums) if nums.len() >= 2 && nums.len() % 2 == 0 => {
+                Ok(Some(Coordinates::new(nums[0], nums[1])))
+            }
+            _ => Err(de::Error::custom(format!("invalid coordinates `{}`", s))),
+        }
+    }
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum AnyCoords {
+        Wgs84 {
+            #[serde(alias = "Latitude", alias = "latitude")]
+            lat: f64,
 
-        Ok(Some(Coordinates::new(lat, long)))
+            #[serde(alias = "Longitude", alias = "longitude", alias = "lng")]
+            long: f64,
+        },
+        Svy21(Svy21Coordinates),
+        Str(String),
+    }
+
+    /// Reads coordinates given either in WGS84 or in SVY21, as
+    /// - an object with `lat` and `long`, or `Latitude` and `Longitude`
+    /// - an object with `northing` and `easting`, or `y` and `x`
+    /// - a string of two numbers separated by a space or comma, either latitude and
+    ///   longitude or SVY21 easting and northing (`x,y`) as in URA data
+    ///
+    /// SVY21 values outside `Svy21Coordinates::in_extent` are an error.
+    pub fn wgs84_or_svy21<'de, D>(deserializer: D) -> Result<Coordinates, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match AnyCoords::deserialize(deserializer)? {
+            AnyCoords::Wgs84 { lat, long } => Ok(Coordinates::new(lat, long)),
+            AnyCoords::Svy21(svy21) if svy21.in_extent() => Ok(svy21.to_wgs84()),
+            AnyCoords::Svy21(svy21) => Err(de::Error::custom(format!(
+                "SVY21 coordinates `{} {}` are outside Singapore",
+                svy21.easting, svy21.northing
+            ))),
+            AnyCoords::Str(s) => {
+                let nums: Option<Vec<f64>> = s
+                    .split(|c: char| c == ',' || c.is_whitespace())
+                    .filter(|n| !n.is_empty())
+                    .map(|n| f64::from_str(n).ok().filter(|f| f.is_finite()))
+                    .collect();
+
+                match nums.as_deref() {
+                    Some(&[lat, long]) if is_wgs84(lat, long) => Ok(Coordinates::new(lat, long)),
+                    Some(&[x, y]) if Svy21Coordinates::new(y, x).in_extent() => {
+                        Ok(Svy21Coordinates::new(y, x).to_wgs84())
+                    }
+                    _ => Err(de::Error::custom(format!("invalid coordinates `{}`", s))),
+                }
+            }
+        }
+    }
+
+    /// `wgs84_or_svy21` that returns None for `null` or an empty string
+    pub fn wgs84_or_svy21_option<'de, D>(deserializer: D) -> Result<Option<Coordinates>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match Value::deserialize(deserializer)? {
+            Value::Null => Ok(None),
+            Value::String(s) if s.trim().is_empty() => Ok(None),
+            v => wgs84_or_svy21(v).map(Some).map_err(de::Error::custom),
+        }
     }
 
     pub fn from_str_loc_to_loc<'de, D>(deserializer: D) -> Result<Option<Location>, D::Error>
@@ -228,19 +454,23 @@ pub mod de {
     {
         let s = String::deserialize(deserializer)?;
 
-        if s.is_empty() || !SPEED_BAND_RE.is_match(s.as_str()) {
+        if s.is_empty() {
             return Ok(None);
         }
 
-        let caps = SPEED_BAND_RE.captures(&s).unwrap();
-        let lat_start = caps.get(1).map_or(0.0, |m| m.as_str().parse().unwrap());
-        let long_start = caps.get(3).map_or(0.0, |m| m.as_str().parse().unwrap());
-        let lat_end = caps.get(5).map_or(0.0, |m| m.as_str().parse().unwrap());
-        let long_end = caps.get(7).map_or(0.0, |m| m.as_str().parse().unwrap());
+        match parse_floats(&s).as_deref() {
+            Some(&[lat_start, long_start, lat_end, long_end]) => Ok(Some(Location::new(
+                lat_start, long_start, lat_end, long_end,
+            ))),
+            _ => Err(de::Error::custom(format!("invalid location `{}`", s))),
+        }
+    }
 
-        Ok(Some(Location::new(
-            lat_start, long_start, lat_end, long_end,
-        )))
+    /// Parses whitespace separated finite numbers
+    pub fn parse_floats(s: &str) -> Option<Vec<f64>> {
+        s.split_whitespace()
+            .map(|n| f64::from_str(n).ok().filter(|f| f.is_finite()))
+            .collect()
     }
 
     pub fn from_str<'de, T, D>(deserializer: D) -> Result<T, D::Error>
@@ -288,8 +518,156 @@ pub mod de {
     }
 }
 
+/// Strict and lenient parsing of API responses
+///
+/// In `ParseMode::Strict` the first record that fails to deserialise fails the
+/// whole response. In `ParseMode::Lenient` bad records are skipped and returned
+/// as `RecordError`s next to the records that did parse.
+pub mod parse {
+    use serde::de::DeserializeOwned;
+    use serde_json::Value;
+    use std::fmt::{self, Display, Formatter};
+
+    /// How a response with bad records should be handled
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    pub enum ParseMode {
+        /// Fail on the first bad record
+        #[default]
+        Strict,
+
+        /// Skip bad records and report them
+        Lenient,
+    }
+
+    /// Deserialisation error with the path to the field that failed,
+    /// e.g. `value[3].Location`
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct ParseError {
+        pub path: String,
+        pub msg: String,
+    }
+
+    impl Display for ParseError {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            write!(f, "{}: {}", self.path, self.msg)
+        }
+    }
+
+    impl std::error::Error for ParseError {}
+
+    impl ParseError {
+        /// Error at `path` with the message of `err`
+        pub fn new<E: Display>(path: String, err: E) -> Self {
+            ParseError {
+                path,
+                msg: err.to_string(),
+            }
+        }
+    }
+
+    /// A record skipped in `ParseMode::Lenient`
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct RecordError {
+        /// Position of the record in the response
+        pub index: usize,
+
+        pub error: ParseError,
+
+        /// The record as received
+        pub raw: Value,
+    }
+
+    /// Parsed data along with records that were skipped.
+    /// `errors` is always empty in `ParseMode::Strict`
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct Parsed<T> {
+        pub data: T,
+        pub errors: Vec<RecordError>,
+    }
+
+    /// Response whose records are held in an array under `RECORDS_KEY`
+    /// and can be parsed one by one
+    pub trait Page: DeserializeOwned {
+        type Record: DeserializeOwned;
+
+        /// Key of the array holding the records, e.g. `value`
+        const RECORDS_KEY: &'static str;
+
+        fn set_records(&mut self, records: Vec<Self::Record>);
+    }
+
+    fn join_path(prefix: &str, path: &serde_path_to_error::Path) -> String {
+        let path = path.to_string();
+        if path == "." {
+            prefix.to_string()
+        } else {
+            format!("{}.{}", prefix, path)
+        }
+    }
+
+    /// Deserialises the whole body, failing with the path of the first bad field
+    pub fn from_slice_strict<T>(body: &[u8]) -> Result<T, ParseError>
+    where
+        T: DeserializeOwned,
+    {
+        let de = &mut serde_json::Deserializer::from_slice(body);
+        serde_path_to_error::deserialize(de)
+            .map_err(|e| ParseError::new(e.path().to_string(), e.inner()))
+    }
+
+    /// Deserialises a `Page` record by record according to `mode`
+    pub fn parse_page<P, M>(body: &[u8], mode: ParseMode) -> Result<Parsed<M>, ParseError>
+    where
+        P: Page + Into<M>,
+    {
+        let mut value: Value =
+            serde_json::from_slice(body).map_err(|e| ParseError::new(".".to_string(), e))?;
+
+        let raw_records = match value.get_mut(P::RECORDS_KEY) {
+            Some(Value::Array(arr)) => std::mem::take(arr),
+            _ => {
+                return Err(ParseError::new(
+                    P::RECORDS_KEY.to_string(),
+                    "expected an array of records",
+                ))
+            }
+        };
+
+        let mut page: P = serde_path_to_error::deserialize(value)
+            .map_err(|e| ParseError::new(e.path().to_string(), e.inner()))?;
+
+        let mut records = Vec::with_capacity(raw_records.len());
+        let mut errors = Vec::new();
+
+        for (index, raw) in raw_records.into_iter().enumerate() {
+            match serde_path_to_error::deserialize(&raw) {
+                Ok(record) => records.push(record),
+                Err(e) => {
+                    let prefix = format!("{}[{}]", P::RECORDS_KEY, index);
+                    let error = ParseError::new(join_path(&prefix, e.path()), e.inner());
+                    match mode {
+                        ParseMode::Strict => return Err(error),
+                        ParseMode::Lenient => errors.push(RecordError { index, error, raw }),
+                    }
+                }
+            }
+        }
+
+        page.set_records(records);
+
+        Ok(Parsed {
+            data: page.into(),
+            errors,
+        })
+    }
+}
+
+/// Max number of records the API returns per request, use `$skip` for the rest
+pub const PAGE_SIZE: u32 = 500;
+
 /// A `Client` to make requests with
 /// The `Client` holds a connection pool internally, so it is advised that you create one and reuse it
+#[cfg(feature = "http")]
 pub trait Client<C, RB> {
     /// General constructor
     fn new(api_key: Option<String>, client: C) -> Self;
@@ -305,7 +683,7 @@ pub trait Client<C, RB> {
 }
 
 /// Starting and ending location
-#[derive(Debug, Clone, PartialEq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Location {
     pub start: Coordinates,
     pub end: Coordinates,
@@ -325,7 +703,7 @@ impl Location {
 }
 
 /// Coordinate on the map
-#[derive(Debug, Clone, PartialEq, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct Coordinates {
     pub lat: f64,
     pub long: f64,
@@ -336,3 +714,578 @@ impl Coordinates {
         Coordinates { lat, long }
     }
 }
+
+/// Distances, bearings and areas on WGS84 coordinates
+///
+/// Distances are great-circle distances in metres on a spherical earth, which is
+/// accurate to about 0.5% and plenty for distances within Singapore.
+///
+/// ## Example
+/// ```rust
+/// use lta_utils_commons::geo::BoundingBox;
+/// use lta_utils_commons::Coordinates;
+///
+/// let raffles = Coordinates::new(1.2839, 103.8515);
+/// let marina_bay = Coordinates::new(1.2764, 103.8546);
+///
+/// let dist = raffles.distance_m(&marina_bay);
+/// assert!((dist - 902.0).abs() < 5.0);
+///
+/// let bbox = BoundingBox::around(&raffles, 1000.0);
+/// assert!(bbox.contains(&marina_bay));
+/// ```
+pub mod geo {
+    use crate::{Coordinates, Location};
+    use serde::{Deserialize, Serialize};
+
+    /// Mean radius of the earth in metres
+    pub const EARTH_RADIUS_M: f64 = 6_371_008.8;
+
+    /// Anything with a position on the map
+    pub trait HasLocation {
+        /// `None` if the record has no location
+        fn coordinates(&self) -> Option<Coordinates>;
+
+        /// Distance in metres to `to`, `None` if the record has no location
+        fn distance_m(&self, to: &Coordinates) -> Option<f64> {
+            self.coordinates().map(|c| c.distance_m(to))
+        }
+    }
+
+    impl<T: HasLocation + ?Sized> HasLocation for &T {
+        fn coordinates(&self) -> Option<Coordinates> {
+            (**self).coordinates()
+        }
+    }
+
+    impl HasLocation for Coordinates {
+        fn coordinates(&self) -> Option<Coordinates> {
+            Some(*self)
+        }
+    }
+
+    impl Coordinates {
+        /// Great-circle distance in metres
+        pub fn distance_m(&self, to: &Coordinates) -> f64 {
+            let (lat1, lat2) = (self.lat.to_radians(), to.lat.to_radians());
+            let d_lat = lat2 - lat1;
+            let d_long = (to.long - self.long).to_radians();
+
+            let h = (d_lat / 2.0).sin().powi(2)
+                + lat1.cos() * lat2.cos() * (d_long / 2.0).sin().powi(2);
+            2.0 * EARTH_RADIUS_M * h.sqrt().min(1.0).asin()
+        }
+
+        /// Initial bearing towards `to` in degrees clockwise from north, from 0 to 360
+        pub fn bearing(&self, to: &Coordinates) -> f64 {
+            let (lat1, lat2) = (self.lat.to_radians(), to.lat.to_radians());
+            let d_long = (to.long - self.long).to_radians();
+
+            let y = d_long.sin() * lat2.cos();
+            let x = lat1.cos() * lat2.sin() - lat1.sin() * lat2.cos() * d_long.cos();
+            (y.atan2(x).to_degrees() + 360.0) % 360.0
+        }
+
+        /// Point reached after travelling `dist_m` metres from here on a bearing in degrees
+        pub fn destination(&self, bearing: f64, dist_m: f64) -> Coordinates {
+            let lat1 = self.lat.to_radians();
+            let long1 = self.long.to_radians();
+            let bearing = bearing.to_radians();
+            let d = dist_m / EARTH_RADIUS_M;
+
+            let lat2 = (lat1.sin() * d.cos() + lat1.cos() * d.sin() * bearing.cos()).asin();
+            let long2 = long1
+                + (bearing.sin() * d.sin() * lat1.cos()).atan2(d.cos() - lat1.sin() * lat2.sin());
+
+            Coordinates::new(
+                lat2.to_degrees(),
+                (long2.to_degrees() + 540.0) % 360.0 - 180.0,
+            )
+        }
+    }
+
+    impl Location {
+        /// Great-circle distance from start to end in metres
+        pub fn length_m(&self) -> f64 {
+            self.start.distance_m(&self.end)
+        }
+
+        /// Bearing from start to end in degrees
+        pub fn bearing(&self) -> f64 {
+            self.start.bearing(&self.end)
+        }
+    }
+
+    /// Area between two latitudes and two longitudes.
+    /// Boxes crossing the antimeridian are not supported
+    #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+    pub struct BoundingBox {
+        /// South west corner
+        pub min: Coordinates,
+
+        /// North east corner
+        pub max: Coordinates,
+    }
+
+    impl BoundingBox {
+        /// Box with the corners of any two opposite points
+        pub fn new(a: Coordinates, b: Coordinates) -> Self {
+            BoundingBox {
+                min: Coordinates::new(a.lat.min(b.lat), a.long.min(b.long)),
+                max: Coordinates::new(a.lat.max(b.lat), a.long.max(b.long)),
+            }
+        }
+
+        /// Smallest box containing every point, `None` if there are none
+        pub fn from_points<'a, I>(points: I) -> Option<Self>
+        where
+            I: IntoIterator<Item = &'a Coordinates>,
+        {
+            let mut points = points.into_iter();
+            let first = points.next()?;
+            let mut bbox = BoundingBox::new(*first, *first);
+            for p in points {
+                bbox.extend(p);
+            }
+
+            Some(bbox)
+        }
+
+        /// Box containing every point within `radius_m` metres of `center`
+        pub fn around(center: &Coordinates, radius_m: f64) -> Self {
+            let d_lat = (radius_m / EARTH_RADIUS_M).to_degrees();
+            let d_long = d_lat / center.lat.to_radians().cos().max(f64::EPSILON);
+
+            BoundingBox {
+                min: Coordinates::new(center.lat - d_lat, center.long - d_long),
+                max: Coordinates::new(center.lat + d_lat, center.long + d_long),
+            }
+        }
+
+        /// Grows the box to contain `point`
+        pub fn extend(&mut self, point: &Coordinates) {
+            self.min.lat = self.min.lat.min(point.lat);
+            self.min.long = self.min.long.min(point.long);
+            self.max.lat = self.max.lat.max(point.lat);
+            self.max.long = self.max.long.max(point.long);
+        }
+
+        /// Whether `point` is inside or on the edge of the box
+        pub fn contains(&self, point: &Coordinates) -> bool {
+            (self.min.lat..=self.max.lat).contains(&point.lat)
+                && (self.min.long..=self.max.long).contains(&point.long)
+        }
+
+        pub fn intersects(&self, other: &BoundingBox) -> bool {
+            self.min.lat <= other.max.lat
+                && other.min.lat <= self.max.lat
+                && self.min.long <= other.max.long
+                && other.min.long <= self.max.long
+        }
+
+        pub fn center(&self) -> Coordinates {
+            Coordinates::new(
+                (self.min.lat + self.max.lat) / 2.0,
+                (self.min.long + self.max.long) / 2.0,
+            )
+        }
+    }
+
+    /// Area enclosed by a ring of points, with optional holes.
+    /// Rings are closed implicitly, the last point does not have to repeat the first
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    pub struct Polygon {
+        pub exterior: Vec<Coordinates>,
+        pub holes: Vec<Vec<Coordinates>>,
+    }
+
+    impl Polygon {
+        pub fn new(exterior: Vec<Coordinates>) -> Self {
+            Polygon {
+                exterior,
+                holes: Vec::new(),
+            }
+        }
+
+        pub fn with_hole(mut self, hole: Vec<Coordinates>) -> Self {
+            self.holes.push(hole);
+            self
+        }
+
+        /// `None` if the polygon has no points
+        pub fn bbox(&self) -> Option<BoundingBox> {
+            BoundingBox::from_points(&self.exterior)
+        }
+
+        /// Whether `point` is inside the exterior and outside every hole.
+        /// Points exactly on an edge may be either
+        pub fn contains(&self, point: &Coordinates) -> bool {
+            ring_contains(&self.exterior, point)
+                && !self.holes.iter().any(|h| ring_contains(h, point))
+        }
+    }
+
+    /// Even-odd rule, treating coordinates as planar
+    fn ring_contains(ring: &[Coordinates], point: &Coordinates) -> bool {
+        let mut inside = false;
+        let mut j = match ring.len() {
+            0 => return false,
+            n => n - 1,
+        };
+
+        for (i, a) in ring.iter().enumerate() {
+            let b = &ring[j];
+            if (a.lat > point.lat) != (b.lat > point.lat)
+                && point.long < (b.long - a.long) * (point.lat - a.lat) / (b.lat - a.lat) + a.long
+            {
+                inside = !inside;
+            }
+            j = i;
+        }
+
+        inside
+    }
+
+    /// Points of every record with a location
+    #[cfg(feature = "geo-types")]
+    pub fn to_multi_point<I>(items: I) -> geo_types::MultiPoint<f64>
+    where
+        I: IntoIterator,
+        I::Item: HasLocation,
+    {
+        items
+            .into_iter()
+            .filter_map(|t| t.coordinates())
+            .map(geo_types::Point::from)
+            .collect()
+    }
+
+    /// A line from start to end of every location
+    #[cfg(feature = "geo-types")]
+    pub fn to_multi_line_string<'a, I>(locations: I) -> geo_types::MultiLineString<f64>
+    where
+        I: IntoIterator<Item = &'a Location>,
+    {
+        locations
+            .into_iter()
+            .map(|l| geo_types::LineString::from(geo_types::Line::from(l.clone())))
+            .collect()
+    }
+
+    /// `From` conversions with `geo_types`, which puts longitude in `x` and latitude in `y`
+    #[cfg(feature = "geo-types")]
+    mod geo_types_conv {
+        use super::{BoundingBox, Polygon};
+        use crate::{Coordinates, Location};
+        use geo_types::{Coord, Line, LineString, Point, Rect};
+
+        impl From<Coordinates> for Coord<f64> {
+            fn from(c: Coordinates) -> Self {
+                Coord {
+                    x: c.long,
+                    y: c.lat,
+                }
+            }
+        }
+
+        impl From<Coord<f64>> for Coordinates {
+            fn from(c: Coord<f64>) -> Self {
+                Coordinates::new(c.y, c.x)
+            }
+        }
+
+        impl From<Coordinates> for Point<f64> {
+            fn from(c: Coordinates) -> Self {
+                Point(c.into())
+            }
+        }
+
+        impl From<Point<f64>> for Coordinates {
+            fn from(p: Point<f64>) -> Self {
+                p.0.into()
+            }
+        }
+
+        impl From<Location> for Line<f64> {
+            fn from(l: Location) -> Self {
+                Line::new(Coord::from(l.start), Coord::from(l.end))
+            }
+        }
+
+        impl From<Line<f64>> for Location {
+            fn from(l: Line<f64>) -> Self {
+                Location::from_coords(l.start.into(), l.end.into())
+            }
+        }
+
+        impl From<BoundingBox> for Rect<f64> {
+            fn from(b: BoundingBox) -> Self {
+                Rect::new(Coord::from(b.min), Coord::from(b.max))
+            }
+        }
+
+        impl From<Rect<f64>> for BoundingBox {
+            fn from(r: Rect<f64>) -> Self {
+                BoundingBox::new(r.min().into(), r.max().into())
+            }
+        }
+
+        fn ring(points: Vec<Coordinates>) -> LineString<f64> {
+            points.into_iter().map(Coord::from).collect()
+        }
+
+        fn points(ring: LineString<f64>) -> Vec<Coordinates> {
+            let mut points: Vec<_> = ring.0.into_iter().map(Coordinates::from).collect();
+            // geo_types closes rings by repeating the first point
+            if points.len() > 1 && points.first() == points.last() {
+                points.pop();
+            }
+            points
+        }
+
+        impl From<Polygon> for geo_types::Polygon<f64> {
+            fn from(p: Polygon) -> Self {
+                geo_types::Polygon::new(ring(p.exterior), p.holes.into_iter().map(ring).collect())
+            }
+        }
+
+        impl From<geo_types::Polygon<f64>> for Polygon {
+            fn from(p: geo_types::Polygon<f64>) -> Self {
+                let (exterior, holes) = p.into_inner();
+                Polygon {
+                    exterior: points(exterior),
+                    holes: holes.into_iter().map(points).collect(),
+                }
+            }
+        }
+    }
+}
+
+/// SVY21, the projected grid used by Singapore government geospatial data
+///
+/// SVY21 is a Transverse Mercator projection on WGS84 with its origin at
+/// 1°22'N 103°50'E, given as northing and easting in metres, as EPSG:3414.
+/// Conversions are accurate to a few millimetres within Singapore, outside of
+/// it `Svy21Coordinates::in_extent` is false.
+///
+/// ## Example
+/// ```rust
+/// use lta_utils_commons::svy21::Svy21Coordinates;
+/// use lta_utils_commons::Coordinates;
+///
+/// let wgs84 = Coordinates::new(1.294_919_268_85, 103.773_674_368_86);
+/// let svy21 = wgs84.to_svy21();
+/// assert!((svy21.northing - 30811.19).abs() < 0.01);
+/// assert!((svy21.easting - 21362.12).abs() < 0.01);
+/// assert!(svy21.in_extent());
+///
+/// let back = svy21.to_wgs84();
+/// assert!(back.distance_m(&wgs84) < 0.001);
+/// ```
+pub mod svy21 {
+    use crate::geo::HasLocation;
+    use crate::Coordinates;
+    use serde::{Deserialize, Serialize};
+
+    /// Semi-major axis of WGS84 in metres
+    const A: f64 = 6_378_137.0;
+
+    /// Flattening of WGS84
+    const F: f64 = 1.0 / 298.257_223_563;
+
+    /// Origin latitude, 1°22'N
+    const ORIGIN_LAT: f64 = 1.0 + 22.0 / 60.0;
+
+    /// Origin longitude, 103°50'E
+    const ORIGIN_LONG: f64 = 103.0 + 50.0 / 60.0;
+
+    /// False northing in metres
+    const FALSE_NORTHING: f64 = 38_744.572;
+
+    /// False easting in metres
+    const FALSE_EASTING: f64 = 28_001.642;
+
+    /// Scale factor on the central meridian
+    const K: f64 = 1.0;
+
+    const E2: f64 = 2.0 * F - F * F;
+
+    /// Point on the SVY21 grid in metres
+    #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+    pub struct Svy21Coordinates {
+        #[serde(alias = "Northing", alias = "y", alias = "Y")]
+        pub northing: f64,
+
+        #[serde(alias = "Easting", alias = "x", alias = "X")]
+        pub easting: f64,
+    }
+
+    impl Svy21Coordinates {
+        pub fn new(northing: f64, easting: f64) -> Self {
+            Svy21Coordinates { northing, easting }
+        }
+
+        /// Whether the point is in or around Singapore, from 1.1°N to 1.5°N and
+        /// 103.55°E to 104.15°E, where SVY21 is defined
+        pub fn in_extent(&self) -> bool {
+            let (min_n, max_n) = (9_000.0, 54_000.0);
+            let (min_e, max_e) = (-4_000.0, 64_000.0);
+            (min_n..=max_n).contains(&self.northing) && (min_e..=max_e).contains(&self.easting)
+        }
+
+        pub fn from_wgs84(coords: &Coordinates) -> Self {
+            let lat = coords.lat.to_radians();
+            let (sin_lat, cos_lat) = lat.sin_cos();
+            let sin2_lat = sin_lat * sin_lat;
+
+            let rho = rho(sin2_lat);
+            let v = v(sin2_lat);
+            let psi = v / rho;
+            let t = lat.tan();
+            let w = (coords.long - ORIGIN_LONG).to_radians();
+
+            let (t2, t4, t6) = (t * t, t.powi(4), t.powi(6));
+            let (psi2, psi3, psi4) = (psi * psi, psi.powi(3), psi.powi(4));
+            let (w2, w4, w6, w8) = (w * w, w.powi(4), w.powi(6), w.powi(8));
+
+            let n1 = w2 / 2.0 * v * sin_lat * cos_lat;
+            let n2 = w4 / 24.0 * v * sin_lat * cos_lat.powi(3) * (4.0 * psi2 + psi - t2);
+            let n3 = w6 / 720.0
+                * v
+                * sin_lat
+                * cos_lat.powi(5)
+                * (8.0 * psi4 * (11.0 - 24.0 * t2) - 28.0 * psi3 * (1.0 - 6.0 * t2)
+                    + psi2 * (1.0 - 32.0 * t2)
+                    - psi * 2.0 * t2
+                    + t4);
+            let n4 = w8 / 40320.0
+                * v
+                * sin_lat
+                * cos_lat.powi(7)
+                * (1385.0 - 3111.0 * t2 + 543.0 * t4 - t6);
+            let northing = FALSE_NORTHING
+                + K * (meridian_arc(lat) - meridian_arc(ORIGIN_LAT.to_radians())
+                    + n1
+                    + n2
+                    + n3
+                    + n4);
+
+            let e1 = w2 / 6.0 * cos_lat.powi(2) * (psi - t2);
+            let e2 = w4 / 120.0
+                * cos_lat.powi(4)
+                * (4.0 * psi3 * (1.0 - 6.0 * t2) + psi2 * (1.0 + 8.0 * t2) - psi * 2.0 * t2 + t4);
+            let e3 = w6 / 5040.0 * cos_lat.powi(6) * (61.0 - 479.0 * t2 + 179.0 * t4 - t6);
+            let easting = FALSE_EASTING + K * v * w * cos_lat * (1.0 + e1 + e2 + e3);
+
+            Svy21Coordinates { northing, easting }
+        }
+
+        pub fn to_wgs84(&self) -> Coordinates {
+            let b = A * (1.0 - F);
+            let n = (A - b) / (A + b);
+            let (n2, n3, n4) = (n * n, n.powi(3), n.powi(4));
+
+            let m = meridian_arc(ORIGIN_LAT.to_radians()) + (self.northing - FALSE_NORTHING) / K;
+            let g = A * (1.0 - n) * (1.0 - n2) * (1.0 + 9.0 * n2 / 4.0 + 225.0 * n4 / 64.0);
+            let sigma = m / g;
+
+            // footpoint latitude
+            let lat_prime = sigma
+                + (3.0 * n / 2.0 - 27.0 * n3 / 32.0) * (2.0 * sigma).sin()
+                + (21.0 * n2 / 16.0 - 55.0 * n4 / 32.0) * (4.0 * sigma).sin()
+                + 151.0 * n3 / 96.0 * (6.0 * sigma).sin()
+                + 1097.0 * n4 / 512.0 * (8.0 * sigma).sin();
+
+            let sin2 = lat_prime.sin().powi(2);
+            let rho = rho(sin2);
+            let v = v(sin2);
+            let psi = v / rho;
+            let (psi2, psi3, psi4) = (psi * psi, psi.powi(3), psi.powi(4));
+            let t = lat_prime.tan();
+            let (t2, t4, t6) = (t * t, t.powi(4), t.powi(6));
+
+            let e = self.easting - FALSE_EASTING;
+            let x = e / (K * v);
+            let (x3, x5, x7) = (x.powi(3), x.powi(5), x.powi(7));
+
+            let lat_factor = t / (K * rho);
+            let l1 = lat_factor * (e * x / 2.0);
+            let l2 =
+                lat_factor * (e * x3 / 24.0) * (-4.0 * psi2 + 9.0 * psi * (1.0 - t2) + 12.0 * t2);
+            let l3 = lat_factor
+                * (e * x5 / 720.0)
+                * (8.0 * psi4 * (11.0 - 24.0 * t2) - 12.0 * psi3 * (21.0 - 71.0 * t2)
+                    + 15.0 * psi2 * (15.0 - 98.0 * t2 + 15.0 * t4)
+                    + 180.0 * psi * (5.0 * t2 - 3.0 * t4)
+                    + 360.0 * t4);
+            let l4 = lat_factor
+                * (e * x7 / 40320.0)
+                * (1385.0 - 3633.0 * t2 + 4095.0 * t4 + 1575.0 * t6);
+            let lat = lat_prime - l1 + l2 - l3 + l4;
+
+            let sec = 1.0 / lat_prime.cos();
+            let g1 = x * sec;
+            let g2 = x3 * sec / 6.0 * (psi + 2.0 * t2);
+            let g3 = x5 * sec / 120.0
+                * (-4.0 * psi3 * (1.0 - 6.0 * t2)
+                    + psi2 * (9.0 - 68.0 * t2)
+                    + 72.0 * psi * t2
+                    + 24.0 * t4);
+            let g4 = x7 * sec / 5040.0 * (61.0 + 662.0 * t2 + 1320.0 * t4 + 720.0 * t6);
+            let long = ORIGIN_LONG.to_radians() + g1 - g2 + g3 - g4;
+
+            Coordinates::new(lat.to_degrees(), long.to_degrees())
+        }
+    }
+
+    impl From<Svy21Coordinates> for Coordinates {
+        fn from(svy21: Svy21Coordinates) -> Self {
+            svy21.to_wgs84()
+        }
+    }
+
+    impl From<Coordinates> for Svy21Coordinates {
+        fn from(coords: Coordinates) -> Self {
+            Svy21Coordinates::from_wgs84(&coords)
+        }
+    }
+
+    impl HasLocation for Svy21Coordinates {
+        fn coordinates(&self) -> Option<Coordinates> {
+            Some(self.to_wgs84())
+        }
+    }
+
+    impl Coordinates {
+        pub fn to_svy21(&self) -> Svy21Coordinates {
+            Svy21Coordinates::from_wgs84(self)
+        }
+    }
+
+    /// Distance along the meridian from the equator to `lat` in radians
+    fn meridian_arc(lat: f64) -> f64 {
+        let (e4, e6) = (E2 * E2, E2.powi(3));
+        let a0 = 1.0 - E2 / 4.0 - 3.0 * e4 / 64.0 - 5.0 * e6 / 256.0;
+        let a2 = 3.0 / 8.0 * (E2 + e4 / 4.0 + 15.0 * e6 / 128.0);
+        let a4 = 15.0 / 256.0 * (e4 + 3.0 * e6 / 4.0);
+        let a6 = 35.0 * e6 / 3072.0;
+
+        A * (a0 * lat - a2 * (2.0 * lat).sin() + a4 * (4.0 * lat).sin() - a6 * (6.0 * lat).sin())
+    }
+
+    /// Radius of curvature in the meridian
+    fn rho(sin2_lat: f64) -> f64 {
+        A * (1.0 - E2) / (1.0 - E2 * sin2_lat).powf(1.5)
+    }
+
+    /// Radius of curvature in the prime vertical
+    fn v(sin2_lat: f64) -> f64 {
+        A / (1.0 - E2 * sin2_lat).sqrt()
+    }
+
+    /// Whether a pair of numbers can be a latitude and longitude, SVY21 values are
+    /// thousands of metres so they never are
+    pub fn is_wgs84(lat: f64, long: f64) -> bool {
+        lat.abs() <= 90.0 && long.abs() <= 180.0
+    }
+}
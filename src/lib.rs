@@ -39,6 +39,13 @@
 //! lta = { version = "0.4.0", features = ["async"] }
 //! ```
 //!
+//! Every domain (`bus`, `traffic`, `taxi`, `train`, `crowd`) is a default feature.
+//! To only compile what you use, disable the defaults
+//! ```toml
+//! [dependencies]
+//! lta = { version = "0.4.0", default-features = false, features = ["blocking", "bus"] }
+//! ```
+//!
 //! Initialise API key
 //! ```rust
 //! use lta::{
@@ -68,17 +75,28 @@ pub use lta_async as r#async;
 pub use lta_models as models;
 pub use lta_utils_commons as utils;
 pub use utils::chrono;
+#[cfg(any(feature = "async", feature = "blocking"))]
 pub use utils::reqwest;
 
 /// Necessary imports to use lts-rs.
 pub mod prelude {
-    pub use crate::utils::{Client, LTAResult};
+    #[cfg(any(feature = "async", feature = "blocking"))]
+    pub use crate::utils::Client;
+    pub use crate::utils::LTAResult;
 }
 
-#[cfg(test)]
+#[cfg(all(
+    test,
+    feature = "blocking",
+    feature = "bus",
+    feature = "traffic",
+    feature = "taxi",
+    feature = "train",
+    feature = "crowd"
+))]
 mod tests {
     use crate::blocking::lta_client::LTAClient;
-    use crate::utils::{Client};
+    use crate::utils::Client;
     use std::env;
     use std::fs::File;
     use std::io::prelude::*;